@@ -0,0 +1,126 @@
+use aspen::{
+    application::Application,
+    context_stack::{DrawContext, LayoutContext, MockContextEventLoop, MockContextWindow},
+    element::{Element, ElementPointer},
+    spatial::SpatialGrid,
+    vello::kurbo::{Affine, Point, Rect, Size},
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const PIN_SIZE: Size = Size::new(120., 60.);
+// Average board space area each pin gets, so that density stays constant as boards grow
+const AREA_PER_PIN: f64 = 400. * 400.;
+const VIEWPORT: Size = Size::new(1920., 1080.);
+
+// Deterministic scattering of pins over a square board sized for the pin count.
+fn pin_rects(count: usize) -> Vec<Rect> {
+    let side = (count as f64 * AREA_PER_PIN).sqrt();
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % 1_000_000) as f64 / 1_000_000.
+    };
+
+    (0..count)
+        .map(|_| Rect::from_center_size(Point::new(next() * side, next() * side), PIN_SIZE))
+        .collect()
+}
+
+fn spatial_index(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spatial_index");
+    for count in [1_000, 10_000, 100_000] {
+        let rects = pin_rects(count);
+        let mut grid = SpatialGrid::new(256.);
+        for (index, rect) in rects.iter().enumerate() {
+            grid.insert(*rect, index);
+        }
+
+        let viewport = Rect::from_origin_size(Point::new(1000., 1000.), VIEWPORT);
+        let point = rects[count / 2].center();
+
+        group.bench_with_input(BenchmarkId::new("grid_cull", count), &count, |b, _| {
+            b.iter(|| grid.query(black_box(viewport)).count())
+        });
+        group.bench_with_input(BenchmarkId::new("linear_cull", count), &count, |b, _| {
+            b.iter(|| {
+                rects
+                    .iter()
+                    .filter(|rect| rect.overlaps(black_box(viewport)))
+                    .count()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("grid_hit_test", count), &count, |b, _| {
+            b.iter(|| grid.query_point(black_box(point)).count())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("linear_hit_test", count),
+            &count,
+            |b, _| {
+                b.iter(|| {
+                    rects
+                        .iter()
+                        .rev()
+                        .filter(|rect| rect.contains(black_box(point)))
+                        .count()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+// Registers a mouse region for each rect, the way a board does for each of its pins.
+struct Regions {
+    rects: Vec<Rect>,
+}
+
+impl Element for Regions {
+    fn layout(&mut self, _min: Size, max: Size, _cx: &mut LayoutContext) -> Size {
+        max
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        for rect in self.rects.iter() {
+            cx.mouse_region(*rect).on_down(|_| {}).on_drag(|_| {});
+        }
+    }
+}
+
+fn mouse_regions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mouse_regions");
+    for count in [1_000, 10_000, 100_000] {
+        // Scaled down so that every pin lands in the window
+        let rects = pin_rects(count);
+        let scale = VIEWPORT.width / (count as f64 * AREA_PER_PIN).sqrt();
+        let rects: Vec<Rect> = rects
+            .into_iter()
+            .map(|rect| Affine::scale(scale).transform_rect_bbox(rect))
+            .collect();
+        let point = rects[count / 2].center();
+
+        let mut application = Application::new(|_| {
+            ElementPointer::new(Regions {
+                rects: rects.clone(),
+            })
+        });
+        application.event_state.window_size = VIEWPORT;
+        let mut window = MockContextWindow::new();
+        window.expect_set_cursor().return_const(());
+        window.expect_request_redraw().return_const(());
+        let event_loop = MockContextEventLoop::new();
+        let child_lookup = application.layout(&window, &event_loop);
+        application.draw(child_lookup, &window, &event_loop);
+        application.event_state.mouse_position = Some(point);
+
+        group.bench_with_input(BenchmarkId::new("hit_test", count), &count, |b, _| {
+            b.iter(|| application.process_mouse_regions(black_box(&window), &event_loop))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, spatial_index, mouse_regions);
+criterion_main!(benches);
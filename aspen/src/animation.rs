@@ -0,0 +1,369 @@
+use std::time::{Duration, Instant};
+
+use crate::{context_stack::UpdateContext, util::Mixable};
+
+// Longest step springs are simulated with. Longer frames are split into several steps so that
+// stiff springs stay stable.
+const MAX_SPRING_STEP: f64 = 1. / 240.;
+// Longest time a spring is advanced by at once. Frames after stalls, such as while the window
+// was hidden, would otherwise simulate an unbounded number of steps.
+const MAX_SPRING_ELAPSED: f64 = 0.1;
+
+/// Curve mapping linear progress through an animation onto eased progress.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    Smoothstep,
+    // CSS style curve through (0, 0), (x1, y1), (x2, y2) and (1, 1)
+    CubicBezier(f64, f64, f64, f64),
+}
+
+impl Easing {
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0., 1.);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1. - (1. - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4. * t * t * t,
+            Easing::EaseInOut => 1. - (-2. * t + 2.).powi(3) / 2.,
+            Easing::Smoothstep => t * t * (3. - 2. * t),
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let curve = |a: f64, b: f64, s: f64| {
+                    3. * a * s * (1. - s).powi(2) + 3. * b * s * s * (1. - s) + s * s * s
+                };
+                let slope = |a: f64, b: f64, s: f64| {
+                    3. * a * (1. - s).powi(2) + 6. * (b - a) * s * (1. - s) + 3. * (1. - b) * s * s
+                };
+
+                // Find the curve parameter at which x reaches t with Newton's method, falling back
+                // to bisection where the curve is too flat
+                let mut s = t;
+                for _ in 0..8 {
+                    let error = curve(x1, x2, s) - t;
+                    let derivative = slope(x1, x2, s);
+                    if error.abs() < 1e-6 || derivative.abs() < 1e-6 {
+                        break;
+                    }
+                    s = (s - error / derivative).clamp(0., 1.);
+                }
+                if (curve(x1, x2, s) - t).abs() >= 1e-6 {
+                    let (mut low, mut high) = (0., 1.);
+                    for _ in 0..32 {
+                        s = (low + high) / 2.;
+                        if curve(x1, x2, s) < t {
+                            low = s;
+                        } else {
+                            high = s;
+                        }
+                    }
+                }
+                curve(y1, y2, s)
+            }
+        }
+    }
+}
+
+/// Eased interpolation between two values over a fixed duration.
+#[derive(Clone, Debug)]
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    start: Instant,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl<T: Mixable + Clone> Tween<T> {
+    pub fn new(from: T, to: T, duration: Duration, easing: Easing, start: Instant) -> Self {
+        Self {
+            from,
+            to,
+            start,
+            duration,
+            easing,
+        }
+    }
+
+    // Linear progress through the tween from 0 to 1
+    pub fn progress(&self, now: Instant) -> f64 {
+        if self.duration.is_zero() {
+            return 1.;
+        }
+        let elapsed = now.saturating_duration_since(self.start);
+        (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.)
+    }
+
+    pub fn value_at(&self, now: Instant) -> T {
+        self.from
+            .mix(&self.to, self.easing.apply(self.progress(now)))
+    }
+
+    pub fn finished(&self, now: Instant) -> bool {
+        self.progress(now) >= 1.
+    }
+}
+
+/// Physical parameters of a damped spring.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpringConfig {
+    pub stiffness: f64,
+    pub damping: f64,
+    pub mass: f64,
+    // Distance and speed below which the spring snaps to its target and stops
+    pub rest: f64,
+}
+
+impl SpringConfig {
+    pub const GENTLE: SpringConfig = SpringConfig {
+        stiffness: 120.,
+        damping: 14.,
+        mass: 1.,
+        rest: 0.001,
+    };
+    pub const SNAPPY: SpringConfig = SpringConfig {
+        stiffness: 400.,
+        damping: 30.,
+        mass: 1.,
+        rest: 0.001,
+    };
+}
+
+impl Default for SpringConfig {
+    fn default() -> Self {
+        Self::GENTLE
+    }
+}
+
+/// Value pulled towards a target by a damped spring, carrying its velocity between retargets.
+#[derive(Clone, Debug)]
+pub struct Spring<T> {
+    pub value: T,
+    pub velocity: T,
+    pub target: T,
+    pub config: SpringConfig,
+}
+
+impl<T: Mixable + Clone> Spring<T> {
+    pub fn new(value: T, target: T, config: SpringConfig) -> Self {
+        Self {
+            velocity: value.sub(&value),
+            value,
+            target,
+            config,
+        }
+    }
+
+    pub fn at_rest(&self) -> bool {
+        self.velocity.magnitude() < self.config.rest
+            && self.target.sub(&self.value).magnitude() < self.config.rest
+    }
+
+    // Advances the simulation by the elapsed seconds. Returns false once the spring has settled.
+    pub fn step(&mut self, elapsed: f64) -> bool {
+        let elapsed = elapsed.clamp(0., MAX_SPRING_ELAPSED);
+        let steps = (elapsed / MAX_SPRING_STEP).ceil().max(1.);
+        let dt = elapsed / steps;
+        for _ in 0..steps as usize {
+            let displacement = self.value.sub(&self.target);
+            let force = displacement
+                .scale(-self.config.stiffness)
+                .sub(&self.velocity.scale(self.config.damping));
+            self.velocity = self.velocity.add(&force.scale(dt / self.config.mass));
+            self.value = self.value.add(&self.velocity.scale(dt));
+        }
+
+        if self.at_rest() {
+            self.value = self.target.clone();
+            self.velocity = self.velocity.scale(0.);
+            false
+        } else {
+            true
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Motion<T> {
+    Still,
+    Tween(Tween<T>),
+    Spring(Spring<T>, Instant),
+}
+
+/// Value which can be animated towards new targets by tweens or springs. Elements keep these in
+/// their state and call `update` every frame, which advances the animation and requests redraws
+/// for as long as it is running.
+#[derive(Clone, Debug)]
+pub struct Animated<T> {
+    value: T,
+    motion: Motion<T>,
+}
+
+impl<T: Mixable + Clone> Animated<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            motion: Motion::Still,
+        }
+    }
+
+    pub fn value(&self) -> T {
+        self.value.clone()
+    }
+
+    // Value the animation is heading towards, or the current value when still
+    pub fn target(&self) -> T {
+        match &self.motion {
+            Motion::Still => self.value.clone(),
+            Motion::Tween(tween) => tween.to.clone(),
+            Motion::Spring(spring, _) => spring.target.clone(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !matches!(self.motion, Motion::Still)
+    }
+
+    // Jumps straight to the value, stopping any running animation
+    pub fn set(&mut self, value: T) {
+        self.value = value;
+        self.motion = Motion::Still;
+    }
+
+    pub fn stop(&mut self) {
+        self.motion = Motion::Still;
+    }
+
+    pub fn animate_to(&mut self, target: T, duration: Duration, easing: Easing, now: Instant) {
+        self.motion = Motion::Tween(Tween::new(
+            self.value.clone(),
+            target,
+            duration,
+            easing,
+            now,
+        ));
+    }
+
+    // Springs towards the target, keeping the current velocity if a spring is already running
+    pub fn spring_to(&mut self, target: T, config: SpringConfig, now: Instant) {
+        match &mut self.motion {
+            Motion::Spring(spring, _) => {
+                spring.target = target;
+                spring.config = config;
+            }
+            motion => {
+                *motion = Motion::Spring(Spring::new(self.value.clone(), target, config), now)
+            }
+        }
+    }
+
+    // Moves the animation forward to the given time. Returns true while it is still running.
+    pub fn advance(&mut self, now: Instant) -> bool {
+        match &mut self.motion {
+            Motion::Still => return false,
+            Motion::Tween(tween) => {
+                self.value = tween.value_at(now);
+                if tween.finished(now) {
+                    self.motion = Motion::Still;
+                }
+            }
+            Motion::Spring(spring, last_step) => {
+                let elapsed = now.saturating_duration_since(*last_step).as_secs_f64();
+                *last_step = now;
+                let moving = spring.step(elapsed);
+                self.value = spring.value.clone();
+                if !moving {
+                    self.motion = Motion::Still;
+                }
+            }
+        }
+        self.is_active()
+    }
+
+    // Advances to the current frame time and redraws, asking for another frame while the
+    // animation is still running. Returns true while it is running.
+    pub fn update(&mut self, cx: &mut UpdateContext) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+        let active = self.advance(cx.frame_time());
+        cx.request_redraw();
+        if active {
+            cx.request_animation_frame();
+        }
+        active
+    }
+}
+
+impl<T: Mixable + Clone + Default> Default for Animated<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::Point;
+
+    use super::*;
+
+    #[test]
+    fn easings_span_zero_to_one() {
+        let easings = [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::Smoothstep,
+            Easing::CubicBezier(0.25, 0.1, 0.25, 1.),
+        ];
+        for easing in easings {
+            assert!(easing.apply(0.).abs() < 1e-6, "{easing:?}");
+            assert!((easing.apply(1.) - 1.).abs() < 1e-6, "{easing:?}");
+        }
+        assert!((Easing::CubicBezier(0., 0., 1., 1.).apply(0.3) - 0.3).abs() < 1e-4);
+        assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn animated_values_tween_and_spring_to_targets() {
+        let start = Instant::now();
+        let mut animated = Animated::new(Point::ZERO);
+        animated.animate_to(
+            Point::new(10., 20.),
+            Duration::from_millis(100),
+            Easing::Linear,
+            start,
+        );
+        assert!(animated.advance(start + Duration::from_millis(50)));
+        assert_eq!(animated.value(), Point::new(5., 10.));
+        assert!(!animated.advance(start + Duration::from_millis(150)));
+        assert_eq!(animated.value(), Point::new(10., 20.));
+
+        let mut animated = Animated::new(0.);
+        animated.spring_to(1., SpringConfig::SNAPPY, start);
+        let mut now = start;
+        let mut frames = 0;
+        while animated.advance(now) {
+            now += Duration::from_millis(16);
+            frames += 1;
+            assert!(frames < 200, "spring never settled");
+        }
+        assert_eq!(animated.value(), 1.);
+        assert!(frames > 1);
+    }
+
+    #[test]
+    fn springs_only_advance_a_bounded_time_per_step() {
+        let mut spring = Spring::new(0., 1., SpringConfig::GENTLE);
+        // An hour long stall would otherwise take close to a billion steps
+        spring.step(3600.);
+        let mut stalled = Spring::new(0., 1., SpringConfig::GENTLE);
+        stalled.step(MAX_SPRING_ELAPSED);
+        assert_eq!(spring.value, stalled.value);
+        assert_eq!(spring.velocity, stalled.velocity);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{any::Any, cell::RefCell, collections::{HashMap, HashSet}, sync::Arc};
+use std::{any::Any, cell::RefCell, collections::{HashMap, HashSet}, sync::Arc, time::Instant};
 
 use vello::{kurbo::{Affine, Size}, Scene};
 
@@ -20,6 +20,10 @@ pub struct Application<Root: Element> {
     pub regions: RefCell<HashMap<Token, (Affine, Size)>>,
     pub states: RefCell<HashMap<Token, Box<dyn Any>>>,
     pub focused_element: RefCell<Option<Token>>,
+    // Elements whose cached layouts went stale since the previous layout
+    pub invalidated: RefCell<HashSet<Token>>,
+    // Child lookup from the previous layout, reused for subtrees whose layouts are cached
+    pub child_lookup: RefCell<HashMap<Token, HashSet<Token>>>,
     pub base_token: Token,
     pub force_redraw: bool,
 
@@ -31,15 +35,23 @@ impl<Root: Element> Application<Root> {
     pub fn new<F>(root_constructor: F) -> Self
         where F: for<'a> FnOnce(&mut Context<'a>) -> ElementPointer<Root>,
     {
-        let event_state = EventState::new();
+        Self::with_event_state(EventState::new(), root_constructor)
+    }
+
+    // Starts from the given event state, such as one running on a fake clock, which the root is
+    // constructed with.
+    pub fn with_event_state<F>(event_state: EventState, root_constructor: F) -> Self
+        where F: for<'a> FnOnce(&mut Context<'a>) -> ElementPointer<Root>,
+    {
         let shaper = RefCell::new(Shaper::new());
         let states = RefCell::new(HashMap::new());
         let focused_element = RefCell::new(None);
+        let invalidated = RefCell::new(HashSet::new());
         let base_token = Token::new::<Self>();
 
         let root = {
             let tokens = Vec::new();
-            let mut context = Context::new(&event_state, &shaper, &states, &focused_element, base_token, &tokens);
+            let mut context = Context::new(&event_state, &shaper, &states, &focused_element, &invalidated, base_token, &tokens);
             RefCell::new(root_constructor(&mut context))
         };
 
@@ -52,6 +64,8 @@ impl<Root: Element> Application<Root> {
             states,
             tokens: Arc::new(Vec::new()),
             focused_element,
+            invalidated,
+            child_lookup: RefCell::new(HashMap::new()),
             base_token,
             force_redraw: false,
 
@@ -65,7 +79,14 @@ impl<Root: Element> Application<Root> {
 
     pub fn tick<'a>(&mut self, window: &'a dyn ContextWindow, event_loop: &'a dyn ContextEventLoop) -> Option<Scene> {
         self.refresh_tokens();
-        let mut redraw_requested = self.process_mouse_regions(window, event_loop);
+        self.event_state.begin_frame();
+        if self.event_state.theme_changed() {
+            // Cached layouts and drawings may hold colors from the previous theme
+            self.invalidated.borrow_mut().extend(self.tokens.iter().copied());
+            self.force_redraw = true;
+        }
+        let mut redraw_requested = self.fire_timers();
+        redraw_requested |= self.process_mouse_regions(window, event_loop);
         redraw_requested |= self.update(window, event_loop);
 
         let drawn_scene = if redraw_requested || self.force_redraw {
@@ -93,6 +114,7 @@ impl<Root: Element> Application<Root> {
             &self.shaper,
             &self.states,
             &self.focused_element,
+            &self.invalidated,
             self.base_token, 
             &self.tokens
         )
@@ -105,6 +127,7 @@ impl<Root: Element> Application<Root> {
                 &self.shaper,
                 &self.states,
                 &self.focused_element,
+                &self.invalidated,
                 self.base_token, 
                 &self.tokens
             ),
@@ -113,6 +136,39 @@ impl<Root: Element> Application<Root> {
         )
     }
 
+    // Runs the callbacks of timers which are due as of this frame, dropping the timers of
+    // elements which no longer exist. Returns true if a callback invalidated any element.
+    pub fn fire_timers(&self) -> bool {
+        let due = {
+            let mut timers = self.event_state.timers.borrow_mut();
+            timers.retain_owners(|owner| owner == self.base_token || self.tokens.contains(&owner));
+            timers.take_due(self.event_state.frame_time)
+        };
+        // Callbacks invalidate into their own set so that timers which change nothing don't
+        // cause a redraw
+        let invalidated = RefCell::new(HashSet::new());
+        for timer in due.iter() {
+            let cx = Context::new(
+                &self.event_state,
+                &self.shaper,
+                &self.states,
+                &self.focused_element,
+                &invalidated,
+                timer.owner,
+                &timer.children,
+            );
+            (timer.callback)(&cx);
+        }
+        let invalidated = invalidated.into_inner();
+        let redraw = !invalidated.is_empty();
+        self.invalidated.borrow_mut().extend(invalidated);
+        redraw
+    }
+
+    pub fn next_timer_deadline(&self) -> Option<Instant> {
+        self.event_state.timers.borrow().next_deadline()
+    }
+
     pub fn process_mouse_regions(&self, window: &dyn ContextWindow, event_loop: &dyn ContextEventLoop) -> bool {
         let mut mouse_region_manager = self.mouse_region_manager.borrow_mut();
         let mut regions = self.regions.borrow_mut();
@@ -121,11 +177,13 @@ impl<Root: Element> Application<Root> {
 
     pub fn update(&self, window: &dyn ContextWindow, event_loop: &dyn ContextEventLoop) -> bool {
         let mut mouse_region_manager = self.mouse_region_manager.borrow_mut();
+        let regions = self.regions.borrow();
         let mut redraw_requested = false;
         let mut update_context = UpdateContext::new(
             self.attached_context(window, event_loop),
             &mut mouse_region_manager,
             &mut redraw_requested,
+            &regions,
         );
 
         let mut root = self.root.borrow_mut();
@@ -136,11 +194,16 @@ impl<Root: Element> Application<Root> {
     pub fn layout(&self, window: &dyn ContextWindow, event_loop: &dyn ContextEventLoop) -> HashMap<Token, HashSet<Token>> {
         let mut regions = self.regions.borrow_mut();
         let mut child_lookup = HashMap::new();
+        // Invalidations made while laying out apply to the next layout
+        let mut invalidated = std::mem::take(&mut *self.invalidated.borrow_mut());
         {
+            let previous_child_lookup = self.child_lookup.borrow();
             let mut layout_context = LayoutContext::new(
                 self.attached_context(window, event_loop),
                 &mut regions,
                 &mut child_lookup,
+                &previous_child_lookup,
+                &mut invalidated,
             );
             let mut root = self.root.borrow_mut();
             let result = root.layout(
@@ -150,6 +213,7 @@ impl<Root: Element> Application<Root> {
             );
             result.position(Affine::IDENTITY, &mut layout_context);
         }
+        *self.child_lookup.borrow_mut() = child_lookup.clone();
 
         child_lookup
     }
@@ -0,0 +1,569 @@
+use parley::{style::StyleProperty, Layout};
+use serde_derive::{Deserialize, Serialize};
+use vello::{
+    kurbo::{Point, Rect, Size, Vec2},
+    peniko::Brush,
+};
+
+use super::{space_drag_pan, Board, BoardState, MoveFrame, MoveNote};
+use crate::{
+    context_stack::{DrawContext, UpdateContext},
+    theme::ThemeColor,
+    token::Token,
+};
+
+const FRAME_HEADER_HEIGHT: f64 = 32.;
+const FRAME_RADIUS: f64 = 8.;
+const FRAME_FILL_ALPHA: f32 = 0.15;
+const FRAME_HEADER_ALPHA: f32 = 0.5;
+const FRAME_HANDLE_SIZE: f64 = 16.;
+const FRAME_MIN_SIZE: Size = Size::new(96., 64.);
+const NOTE_WIDTH: f64 = 200.;
+const NOTE_MIN_HEIGHT: f64 = 48.;
+const ANNOTATION_PADDING: f64 = 10.;
+const ANNOTATION_FONT_SIZE: f32 = 14.;
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Rect")]
+struct RectDef {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Point")]
+struct PointDef {
+    x: f64,
+    y: f64,
+}
+
+/// Titled rectangle drawn behind pins to organize them. Dragging its header moves the pins
+/// inside of it along with it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Frame {
+    pub title: String,
+    #[serde(with = "RectDef")]
+    pub rect: Rect,
+    pub color: ThemeColor,
+    // Identifies the frame in commands while other frames are added and removed
+    #[serde(skip, default = "Token::new::<Frame>")]
+    token: Token,
+}
+
+impl PartialEq for Frame {
+    fn eq(&self, other: &Self) -> bool {
+        self.title == other.title && self.rect == other.rect && self.color == other.color
+    }
+}
+
+impl Frame {
+    pub fn new(title: impl Into<String>, rect: Rect, color: impl Into<ThemeColor>) -> Self {
+        Self {
+            title: title.into(),
+            rect,
+            color: color.into(),
+            token: Token::new::<Frame>(),
+        }
+    }
+
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    fn header(&self) -> Rect {
+        Rect::new(
+            self.rect.x0,
+            self.rect.y0,
+            self.rect.x1,
+            self.rect.y0 + FRAME_HEADER_HEIGHT.min(self.rect.height()),
+        )
+    }
+
+    fn resize_handle(&self) -> Rect {
+        Rect::from_points(
+            Point::new(self.rect.x1, self.rect.y1),
+            Point::new(self.rect.x1, self.rect.y1)
+                - Vec2::new(FRAME_HANDLE_SIZE, FRAME_HANDLE_SIZE),
+        )
+    }
+}
+
+/// Free floating comment on a board.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Note {
+    pub text: String,
+    #[serde(with = "PointDef")]
+    pub center: Point,
+    pub color: ThemeColor,
+    #[serde(skip, default = "Token::new::<Note>")]
+    token: Token,
+}
+
+impl PartialEq for Note {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text && self.center == other.center && self.color == other.color
+    }
+}
+
+impl Note {
+    pub fn new(text: impl Into<String>, center: Point, color: impl Into<ThemeColor>) -> Self {
+        Self {
+            text: text.into(),
+            center,
+            color: color.into(),
+            token: Token::new::<Note>(),
+        }
+    }
+
+    pub fn token(&self) -> Token {
+        self.token
+    }
+}
+
+/// Non functional data describing how a board is organized, kept separately from the graph
+/// formed by its pins and connections. Boards don't save it themselves; apps store the JSON
+/// from `to_json` next to their graph and hand it back with `set_layout_data`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutData {
+    pub frames: Vec<Frame>,
+    pub notes: Vec<Note>,
+}
+
+impl LayoutData {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+// Frame or note being dragged with the mouse movement not yet applied to it.
+pub(crate) enum AnnotationDrag {
+    Frame {
+        frame: Token,
+        resize: bool,
+        // Pins carried along with the frame, collected when the drag starts
+        pins: Option<Vec<Token>>,
+        delta: Vec2,
+    },
+    Note {
+        note: Token,
+        started: bool,
+        delta: Vec2,
+    },
+}
+
+impl AnnotationDrag {
+    fn delta(&mut self) -> &mut Vec2 {
+        match self {
+            AnnotationDrag::Frame { delta, .. } | AnnotationDrag::Note { delta, .. } => delta,
+        }
+    }
+}
+
+impl Board {
+    pub fn add_frame(&mut self, frame: Frame) -> Token {
+        let token = frame.token;
+        self.layout_data.frames.push(frame);
+        token
+    }
+
+    pub fn add_note(&mut self, note: Note) -> Token {
+        let token = note.token;
+        self.layout_data.notes.push(note);
+        token
+    }
+
+    pub fn frame(&self, token: Token) -> Option<&Frame> {
+        self.layout_data
+            .frames
+            .iter()
+            .find(|frame| frame.token == token)
+    }
+
+    pub fn note(&self, token: Token) -> Option<&Note> {
+        self.layout_data
+            .notes
+            .iter()
+            .find(|note| note.token == token)
+    }
+
+    pub(super) fn frame_mut(&mut self, token: Token) -> Option<&mut Frame> {
+        self.layout_data
+            .frames
+            .iter_mut()
+            .find(|frame| frame.token == token)
+    }
+
+    pub(super) fn note_mut(&mut self, token: Token) -> Option<&mut Note> {
+        self.layout_data
+            .notes
+            .iter_mut()
+            .find(|note| note.token == token)
+    }
+
+    pub fn layout_data(&self) -> &LayoutData {
+        &self.layout_data
+    }
+
+    pub fn set_layout_data(&mut self, layout_data: LayoutData) {
+        self.layout_data = layout_data;
+    }
+
+    fn annotation_text(&self, text: &str, max_advance: f64, cx: &DrawContext) -> Layout<Brush> {
+        cx.shaper.borrow_mut().layout_within(
+            text,
+            max_advance.max(0.) as f32,
+            &vec![
+                StyleProperty::FontSize(ANNOTATION_FONT_SIZE),
+                StyleProperty::Brush(Brush::Solid(self.level_of_detail.text.resolve(&cx.theme()))),
+            ],
+        )
+    }
+
+    pub(super) fn drag_annotations(&mut self, cx: &mut UpdateContext) {
+        let Some(mut drag) =
+            cx.with_state(|state: &mut BoardState, _| state.annotation_drag.take())
+        else {
+            return;
+        };
+        if !cx.mouse_down() {
+            return;
+        }
+        if *drag.delta() == Vec2::ZERO {
+            cx.with_state(|state: &mut BoardState, _| state.annotation_drag = Some(drag));
+            return;
+        }
+
+        match &mut drag {
+            AnnotationDrag::Frame {
+                frame,
+                resize,
+                pins,
+                delta,
+            } => {
+                let token = *frame;
+                let Some(frame) = self.frame(token) else {
+                    return;
+                };
+                let from = frame.rect;
+                let (to, pin_delta) = if *resize {
+                    let size = (from.size() + Size::new(delta.x, delta.y)).max(FRAME_MIN_SIZE);
+                    (Rect::from_origin_size(from.origin(), size), Vec2::ZERO)
+                } else {
+                    (from + *delta, *delta)
+                };
+
+                let started = pins.is_some();
+                let carried = pins.get_or_insert_with(|| {
+                    if *resize {
+                        return Vec::new();
+                    }
+                    self.pin_rects
+                        .iter()
+                        .filter(|(_, rect)| from.contains_rect(**rect))
+                        .map(|(token, _)| *token)
+                        .collect()
+                });
+                let moves = self
+                    .children
+                    .iter()
+                    .filter(|child| carried.contains(&child.token()))
+                    .map(|child| {
+                        let center = child.with_context(cx, |cx| child.center(cx));
+                        (child.token(), center, center + pin_delta)
+                    })
+                    .collect();
+                let command = MoveFrame::dragging(token, from, to, moves);
+                self.with_history(cx, |history, board| {
+                    // Each drag is its own undo step
+                    if !started {
+                        history.seal();
+                    }
+                    history.execute(command, board, cx);
+                });
+                *delta = Vec2::ZERO;
+            }
+            AnnotationDrag::Note {
+                note,
+                started,
+                delta,
+            } => {
+                let token = *note;
+                let Some(note) = self.note(token) else {
+                    return;
+                };
+                let command = MoveNote::dragging(token, note.center, note.center + *delta);
+                self.with_history(cx, |history, board| {
+                    if !*started {
+                        history.seal();
+                    }
+                    history.execute(command, board, cx);
+                });
+                *started = true;
+                *delta = Vec2::ZERO;
+            }
+        }
+
+        cx.with_state(|state: &mut BoardState, _| state.annotation_drag = Some(drag));
+        cx.request_redraw();
+    }
+
+    // Registers a region which starts dragging the annotation returned by the callback.
+    fn annotation_region(
+        &self,
+        region: Rect,
+        drag: impl Fn() -> AnnotationDrag + 'static,
+        cx: &mut DrawContext,
+    ) {
+        cx.mouse_region(region).on_drag(move |cx| {
+            if space_drag_pan(cx) {
+                return;
+            }
+            if let Some(delta) = cx.mouse_delta() {
+                cx.with_state(|state: &mut BoardState, _| {
+                    *state.annotation_drag.get_or_insert_with(&drag).delta() += delta;
+                });
+                cx.request_redraw();
+            }
+        });
+    }
+
+    pub(super) fn draw_frames(&self, visible: Rect, cx: &mut DrawContext) {
+        for frame in self.layout_data.frames.iter() {
+            if !frame.rect.overlaps(visible) {
+                continue;
+            }
+
+            let header = frame.header();
+            let token = frame.token;
+            self.annotation_region(
+                header,
+                move || AnnotationDrag::Frame {
+                    frame: token,
+                    resize: false,
+                    pins: None,
+                    delta: Vec2::ZERO,
+                },
+                cx,
+            );
+            self.annotation_region(
+                frame.resize_handle(),
+                move || AnnotationDrag::Frame {
+                    frame: token,
+                    resize: true,
+                    pins: None,
+                    delta: Vec2::ZERO,
+                },
+                cx,
+            );
+
+            let color = frame.color.resolve(&cx.theme());
+            cx.set_fill_brush(Brush::Solid(color.multiply_alpha(FRAME_FILL_ALPHA)));
+            cx.fill(&frame.rect.to_rounded_rect(FRAME_RADIUS));
+            cx.set_fill_brush(Brush::Solid(color.multiply_alpha(FRAME_HEADER_ALPHA)));
+            cx.fill(&header.to_rounded_rect((FRAME_RADIUS, FRAME_RADIUS, 0., 0.)));
+            cx.set_fill_brush(Brush::Solid(color));
+            cx.fill(
+                &frame
+                    .resize_handle()
+                    .to_rounded_rect((0., 0., FRAME_RADIUS, 0.)),
+            );
+
+            let title =
+                self.annotation_text(&frame.title, header.width() - ANNOTATION_PADDING * 2., cx);
+            let offset = (header.height() - title.height() as f64) / 2.;
+            cx.draw_layout_at(
+                &title,
+                header.origin() + Vec2::new(ANNOTATION_PADDING, offset),
+            );
+        }
+    }
+
+    pub(super) fn draw_notes(&self, visible: Rect, cx: &mut DrawContext) {
+        for note in self.layout_data.notes.iter() {
+            let text = self.annotation_text(&note.text, NOTE_WIDTH - ANNOTATION_PADDING * 2., cx);
+            let height = (text.height() as f64 + ANNOTATION_PADDING * 2.).max(NOTE_MIN_HEIGHT);
+            let rect = Rect::from_center_size(note.center, Size::new(NOTE_WIDTH, height));
+            if !rect.overlaps(visible) {
+                continue;
+            }
+
+            let token = note.token;
+            self.annotation_region(
+                rect,
+                move || AnnotationDrag::Note {
+                    note: token,
+                    started: false,
+                    delta: Vec2::ZERO,
+                },
+                cx,
+            );
+
+            cx.set_fill_brush(Brush::Solid(note.color.resolve(&cx.theme())));
+            cx.fill(&rect.to_rounded_rect(FRAME_RADIUS / 2.));
+            cx.draw_layout_at(
+                &text,
+                rect.origin() + Vec2::new(ANNOTATION_PADDING, ANNOTATION_PADDING),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::{kurbo::Affine, peniko::Color};
+    use winit::{
+        keyboard::{Key, ModifiersState, SmolStr},
+        window::{Cursor, CursorIcon},
+    };
+
+    use super::*;
+    use crate::{
+        components::board::{Blank, PinWrapper},
+        context_stack::Context,
+        test_runner::TestRunner,
+    };
+
+    // Board filling a 400 by 400 window, with its origin at the middle of the window. It holds a
+    // frame covering the middle of the window with a pin inside of it and a note below it.
+    // Returns the tokens of the frame, the pin and the note.
+    fn annotated_board() -> (TestRunner<Board>, Token, Token, Token) {
+        let mut tokens = None;
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            let frame = board.add_frame(Frame::new(
+                "Frame",
+                Rect::new(-100., -100., 100., 100.),
+                Color::WHITE,
+            ));
+            let inner = Board::new(Affine::IDENTITY, Blank::new(Color::WHITE), cx);
+            let pin = board.add_child(PinWrapper::new_sized(
+                Point::new(0., 20.),
+                Size::new(40., 40.),
+                inner,
+                cx,
+            ));
+            let note = board.add_note(Note::new("Note", Point::new(0., 150.), Color::WHITE));
+            tokens = Some((frame, pin, note));
+            board
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        let (frame, pin, note) = tokens.unwrap();
+        (test_runner, frame, pin, note)
+    }
+
+    fn drag(test_runner: &mut TestRunner<Board>, from: Point, to: Point) {
+        test_runner.move_mouse(from);
+        test_runner.press_mouse();
+        test_runner.move_mouse(to);
+        test_runner.release_mouse();
+    }
+
+    fn pin_center(board: &Board, pin: Token, cx: &Context) -> Point {
+        let child = board
+            .children
+            .iter()
+            .find(|child| child.token() == pin)
+            .unwrap();
+        child.with_context(cx, |cx| child.center(cx))
+    }
+
+    fn undo(test_runner: &mut TestRunner<Board>) {
+        test_runner.application.event_state.modifiers = ModifiersState::CONTROL.into();
+        test_runner.input_key(Key::Character(SmolStr::new_inline("z")));
+    }
+
+    #[test]
+    fn frames_and_notes_are_dragged_and_undone() {
+        let (mut test_runner, frame, pin, note) = annotated_board();
+        test_runner.with_root(|_, cx| cx.focus());
+
+        // Dragging the header carries the pin along
+        drag(
+            &mut test_runner,
+            Point::new(150., 110.),
+            Point::new(190., 150.),
+        );
+        test_runner.with_root(|board, cx| {
+            assert_eq!(
+                board.frame(frame).unwrap().rect,
+                Rect::new(-60., -60., 140., 140.)
+            );
+            assert_eq!(pin_center(board, pin, cx), Point::new(40., 60.));
+        });
+
+        // Dragging the handle in the bottom right corner resizes the frame, leaving the pin
+        drag(
+            &mut test_runner,
+            Point::new(335., 335.),
+            Point::new(355., 365.),
+        );
+        test_runner.with_root(|board, cx| {
+            assert_eq!(
+                board.frame(frame).unwrap().rect,
+                Rect::new(-60., -60., 160., 170.)
+            );
+            assert_eq!(pin_center(board, pin, cx), Point::new(40., 60.));
+        });
+
+        drag(
+            &mut test_runner,
+            Point::new(120., 350.),
+            Point::new(100., 340.),
+        );
+        test_runner.with_root(|board, _| {
+            assert_eq!(board.note(note).unwrap().center, Point::new(-20., 140.));
+        });
+
+        undo(&mut test_runner);
+        test_runner.with_root(|board, _| {
+            assert_eq!(board.note(note).unwrap().center, Point::new(0., 150.));
+            assert_eq!(
+                board.frame(frame).unwrap().rect,
+                Rect::new(-60., -60., 160., 170.)
+            );
+        });
+        undo(&mut test_runner);
+        test_runner.with_root(|board, _| {
+            assert_eq!(
+                board.frame(frame).unwrap().rect,
+                Rect::new(-60., -60., 140., 140.)
+            );
+        });
+        undo(&mut test_runner);
+        test_runner.with_root(|board, cx| {
+            assert_eq!(
+                board.frame(frame).unwrap().rect,
+                Rect::new(-100., -100., 100., 100.)
+            );
+            assert_eq!(pin_center(board, pin, cx), Point::new(0., 20.));
+        });
+    }
+
+    #[test]
+    fn layout_data_round_trips_through_json() {
+        let layout_data = LayoutData {
+            frames: vec![Frame::new(
+                "Frame",
+                Rect::new(-10., 0., 200., 150.5),
+                Color::new([0.5, 0.25, 1., 1.]),
+            )],
+            notes: vec![Note::new(
+                "Remember",
+                Point::new(3., -4.),
+                Color::new([1., 1., 0., 0.5]),
+            )],
+        };
+
+        let json = layout_data.to_json().unwrap();
+        assert_eq!(LayoutData::from_json(&json).unwrap(), layout_data);
+        assert!(LayoutData::from_json("{\"frames\": 3}").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use vello::kurbo::{Point, Rect};
+
+use super::{Board, BoardState, MovePins};
+use crate::{
+    context_stack::{Context, UpdateContext},
+    element::ElementPointer,
+    token::Token,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arrange {
+    AlignLeft,
+    AlignRight,
+    AlignTop,
+    AlignBottom,
+    // Lines up the horizontal centers of the pins, stacking them in a column
+    AlignCenterHorizontal,
+    // Lines up the vertical centers of the pins, placing them in a row
+    AlignCenterVertical,
+    // Spaces pins so the gaps between them are equal, keeping the outermost pins in place
+    DistributeHorizontally,
+    DistributeVertically,
+}
+
+impl Arrange {
+    // Computes new centers for the given pin rects. Pins which don't move are left out.
+    pub fn compute(&self, pins: &[(Token, Rect)]) -> HashMap<Token, Point> {
+        let Some(bounds) = pins
+            .iter()
+            .map(|(_, rect)| *rect)
+            .reduce(|bounds, rect| bounds.union(rect))
+        else {
+            return HashMap::new();
+        };
+
+        let centers = pins.iter().map(|(token, rect)| {
+            let center = rect.center();
+            let (half_width, half_height) = (rect.width() / 2., rect.height() / 2.);
+            let new_center = match self {
+                Arrange::AlignLeft => Point::new(bounds.x0 + half_width, center.y),
+                Arrange::AlignRight => Point::new(bounds.x1 - half_width, center.y),
+                Arrange::AlignTop => Point::new(center.x, bounds.y0 + half_height),
+                Arrange::AlignBottom => Point::new(center.x, bounds.y1 - half_height),
+                Arrange::AlignCenterHorizontal => Point::new(bounds.center().x, center.y),
+                Arrange::AlignCenterVertical => Point::new(center.x, bounds.center().y),
+                Arrange::DistributeHorizontally | Arrange::DistributeVertically => center,
+            };
+            (*token, new_center)
+        });
+
+        let mut centers: HashMap<Token, Point> = match self {
+            Arrange::DistributeHorizontally => distribute(pins, |rect| (rect.x0, rect.x1))
+                .into_iter()
+                .map(|(token, x)| (token, Point::new(x, rect_of(pins, token).center().y)))
+                .collect(),
+            Arrange::DistributeVertically => distribute(pins, |rect| (rect.y0, rect.y1))
+                .into_iter()
+                .map(|(token, y)| (token, Point::new(rect_of(pins, token).center().x, y)))
+                .collect(),
+            _ => centers.collect(),
+        };
+
+        centers.retain(|token, center| rect_of(pins, *token).center() != *center);
+        centers
+    }
+}
+
+fn rect_of(pins: &[(Token, Rect)], token: Token) -> Rect {
+    pins.iter()
+        .find(|(pin, _)| *pin == token)
+        .map(|(_, rect)| *rect)
+        .unwrap()
+}
+
+// New centers along an axis which leave equal gaps between consecutive pins.
+fn distribute(pins: &[(Token, Rect)], extent: impl Fn(&Rect) -> (f64, f64)) -> Vec<(Token, f64)> {
+    let mut sorted: Vec<(Token, f64, f64)> = pins
+        .iter()
+        .map(|(token, rect)| {
+            let (min, max) = extent(rect);
+            (*token, min, max)
+        })
+        .collect();
+    sorted.sort_by(|a, b| (a.1 + a.2).total_cmp(&(b.1 + b.2)));
+
+    let (start, end) = match (sorted.first(), sorted.last()) {
+        (Some(first), Some(last)) if sorted.len() > 2 => (first.1, last.2),
+        _ => return Vec::new(),
+    };
+    let occupied: f64 = sorted.iter().map(|(_, min, max)| max - min).sum();
+    let gap = (end - start - occupied) / (sorted.len() - 1) as f64;
+
+    let mut position = start;
+    sorted
+        .into_iter()
+        .map(|(token, min, max)| {
+            let center = position + (max - min) / 2.;
+            position += max - min + gap;
+            (token, center)
+        })
+        .collect()
+}
+
+impl Board {
+    pub(super) fn apply_arrange(&mut self, arrange: Arrange, cx: &mut UpdateContext) {
+        let selection = cx.with_state(|state: &mut BoardState, _| state.selection.clone());
+        let pins: Vec<(Token, Rect)> = self
+            .pin_rects(cx)
+            .into_iter()
+            .filter(|(token, _)| selection.contains(token))
+            .collect();
+        let centers = arrange.compute(&pins);
+        if centers.is_empty() {
+            return;
+        }
+
+        let moves = self
+            .children
+            .iter()
+            .filter_map(|child| {
+                let center = centers.get(&child.token())?;
+                Some((
+                    child.token(),
+                    child.with_context(cx, |cx| child.center(cx)),
+                    *center,
+                ))
+            })
+            .collect();
+        self.with_history(cx, |history, board| {
+            history.execute(MovePins::new(moves), board, cx)
+        });
+        cx.request_redraw();
+    }
+}
+
+impl ElementPointer<Board> {
+    // Aligns or distributes the selected pins using their laid out regions.
+    pub fn arrange(&self, arrange: Arrange, cx: &Context) {
+        self.with_state(cx, |state: &mut BoardState, _| {
+            state.pending_arrange = Some(arrange);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::{
+        kurbo::{Affine, Size, Vec2},
+        peniko::Color,
+    };
+    use winit::{
+        keyboard::{Key, ModifiersState, SmolStr},
+        window::{Cursor, CursorIcon},
+    };
+
+    use super::*;
+    use crate::{
+        components::{
+            board::{Blank, PinWrapper},
+            layout::{Align, SizedBox},
+        },
+        test_runner::TestRunner,
+    };
+
+    #[test]
+    fn distribute_leaves_equal_gaps() {
+        let tokens: Vec<Token> = (0..3).map(|_| Token::new::<Rect>()).collect();
+        let pins = vec![
+            (tokens[0], Rect::new(0., 0., 10., 10.)),
+            (tokens[1], Rect::new(15., 0., 45., 10.)),
+            (tokens[2], Rect::new(90., 0., 100., 10.)),
+        ];
+
+        let centers = Arrange::DistributeHorizontally.compute(&pins);
+        assert_eq!(centers.len(), 1);
+        assert_eq!(centers[&tokens[1]], Point::new(50., 5.));
+
+        let centers = Arrange::AlignBottom.compute(&[
+            (tokens[0], Rect::new(0., 0., 10., 10.)),
+            (tokens[1], Rect::new(20., 0., 30., 30.)),
+        ]);
+        assert_eq!(centers[&tokens[0]], Point::new(5., 25.));
+        assert!(!centers.contains_key(&tokens[1]));
+    }
+
+    #[test]
+    fn arranging_is_undone_in_one_step() {
+        let centers = [Point::new(-100., -100.), Point::ZERO, Point::new(100., 50.)];
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            for center in centers {
+                board.add_child(PinWrapper::new(
+                    center,
+                    SizedBox::new(Size::new(40., 40.)),
+                    cx,
+                ));
+            }
+            Align::center(board)
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        let current_centers = |test_runner: &TestRunner<Align<Board>>| {
+            test_runner.with_root(|root, cx| {
+                root.child.with_context(cx, |cx| {
+                    root.child
+                        .children
+                        .iter()
+                        .map(|child| child.with_context(cx, |cx| child.center(cx)))
+                        .collect::<Vec<_>>()
+                })
+            })
+        };
+
+        // Clicking a pin focuses the board and shift clicks add the others to the selection
+        for (index, center) in centers.iter().enumerate() {
+            if index > 0 {
+                test_runner.application.event_state.modifiers = ModifiersState::SHIFT.into();
+            }
+            test_runner.move_mouse(*center + Vec2::new(200., 200.));
+            test_runner.press_mouse();
+            test_runner.release_mouse();
+        }
+        test_runner.application.event_state.modifiers = ModifiersState::empty().into();
+
+        test_runner.with_root(|root, cx| root.child.arrange(Arrange::AlignLeft, cx));
+        test_runner.tick();
+        let arranged = current_centers(&test_runner);
+        assert!(arranged.iter().all(|center| center.x == -100.));
+
+        test_runner.application.event_state.modifiers = ModifiersState::CONTROL.into();
+        test_runner.input_key(Key::Character(SmolStr::new_inline("z")));
+        assert_eq!(current_centers(&test_runner), centers.to_vec());
+    }
+}
@@ -0,0 +1,399 @@
+use std::{collections::HashSet, rc::Rc};
+
+use ordered_float::OrderedFloat;
+use parley::style::StyleProperty;
+use vello::{
+    kurbo::{Affine, Circle, Line, Point, Rect, Stroke},
+    peniko::{Brush, Color},
+};
+
+use super::Board;
+use crate::{context_stack::DrawContext, element::ElementPointer, theme::ThemeColor, util::*};
+
+const GRID_MAX_SPACING: f64 = 8192.;
+// Grid marks are sized as their spacing divided by this ratio
+const GRID_MARK_RATIO: f64 = 50.;
+// Screen space mark size below which a level of the grid is no longer drawn
+const GRID_MIN_MARK: f64 = 0.75;
+// Screen space mark size above which a level is left out, as its marks are too far apart to help
+const GRID_MAX_MARK: f64 = 8.;
+// Screen space size marks are capped at
+const GRID_MARK_CAP: f64 = 2.;
+// How quickly levels fade in as their marks grow past the minimum size
+const GRID_FADE_RATE: f64 = 4.;
+const RULER_THICKNESS: f64 = 20.;
+// Screen space distance labelled ticks are kept apart by at least
+const RULER_MIN_SPACING: f64 = 64.;
+const RULER_MINOR_TICKS: usize = 4;
+const RULER_TICK_LENGTH: f64 = 6.;
+const RULER_FONT_SIZE: f32 = 10.;
+
+/// Draws what is shown behind a board's pins.
+pub trait Background {
+    /// Draws the background over the given board space bounds.
+    fn draw(&self, bounds: Rect, cx: &mut DrawContext);
+
+    /// Board space spacing of the finest grid level visible at a given zoom, which pins snap to.
+    fn grid_spacing(&self, _zoom: f64) -> Option<f64> {
+        None
+    }
+}
+
+impl<F: Fn(Rect, &mut DrawContext)> Background for F {
+    fn draw(&self, bounds: Rect, cx: &mut DrawContext) {
+        self(bounds, cx)
+    }
+}
+
+// Level of a multi level grid visible at the current zoom.
+struct GridLevel {
+    spacing: f64,
+    // Board space size of the level's marks, such as the radius of dots
+    mark: f64,
+    color: Color,
+}
+
+// Levels of the grid visible at the zoom from coarsest to finest. Finer levels fade in as they
+// grow on screen.
+fn grid_levels(zoom: f64, background: Color, foreground: Color) -> Vec<GridLevel> {
+    let mut levels = Vec::new();
+    let mut spacing = GRID_MAX_SPACING;
+    loop {
+        spacing /= 2.;
+        let mark = spacing / GRID_MARK_RATIO;
+        let screen_mark = zoom * mark;
+        if screen_mark < GRID_MIN_MARK {
+            break;
+        } else if screen_mark > GRID_MAX_MARK {
+            continue;
+        }
+
+        levels.push(GridLevel {
+            spacing,
+            mark: mark.min(GRID_MARK_CAP / zoom),
+            color: background.mix(&foreground, (screen_mark - GRID_MIN_MARK) * GRID_FADE_RATE),
+        });
+    }
+    levels
+}
+
+// Spacing of the finest grid level visible at the zoom.
+fn finest_grid_spacing(zoom: f64) -> f64 {
+    let mut spacing = GRID_MAX_SPACING / 2.;
+    while zoom * spacing / 2. / GRID_MARK_RATIO >= GRID_MIN_MARK {
+        spacing /= 2.;
+    }
+    spacing
+}
+
+// Multiples of the spacing between min and max, starting at or before min.
+fn grid_lines(min: f64, max: f64, spacing: f64) -> impl Iterator<Item = f64> {
+    let start = min - min.rem_euclid(spacing);
+    (0..)
+        .map(move |index| start + index as f64 * spacing)
+        .take_while(move |position| *position <= max)
+}
+
+fn zoom_of(cx: &DrawContext) -> f64 {
+    cx.current_transform().unskewed_scale().length() / 2.0f64.sqrt()
+}
+
+/// Solid color without a grid.
+pub struct Blank {
+    pub color: ThemeColor,
+}
+
+impl Blank {
+    pub fn new(color: impl Into<ThemeColor>) -> Self {
+        Self {
+            color: color.into(),
+        }
+    }
+}
+
+impl Background for Blank {
+    fn draw(&self, bounds: Rect, cx: &mut DrawContext) {
+        cx.set_fill_brush(Brush::Solid(self.color.resolve(&cx.theme())));
+        cx.fill(&bounds);
+    }
+}
+
+/// Grid of dots at each intersection.
+pub struct DotGrid {
+    pub background: ThemeColor,
+    pub dots: ThemeColor,
+}
+
+impl DotGrid {
+    pub fn new(background: impl Into<ThemeColor>, dots: impl Into<ThemeColor>) -> Self {
+        Self {
+            background: background.into(),
+            dots: dots.into(),
+        }
+    }
+}
+
+impl Default for DotGrid {
+    fn default() -> Self {
+        Self::new(ThemeColor::Background(0), ThemeColor::Background(3))
+    }
+}
+
+impl Background for DotGrid {
+    fn draw(&self, bounds: Rect, cx: &mut DrawContext) {
+        Blank::new(self.background).draw(bounds, cx);
+        if bounds.is_zero_area() {
+            return;
+        }
+
+        let theme = cx.theme();
+        let (background, dots) = (self.background.resolve(&theme), self.dots.resolve(&theme));
+        // Coarser levels take precedence where they overlap finer ones
+        let mut filled = HashSet::new();
+        for level in grid_levels(zoom_of(cx), background, dots) {
+            cx.set_fill_brush(Brush::Solid(level.color));
+            for x in grid_lines(bounds.min_x(), bounds.max_x(), level.spacing) {
+                for y in grid_lines(bounds.min_y(), bounds.max_y(), level.spacing) {
+                    if filled.insert((OrderedFloat(x), OrderedFloat(y))) {
+                        cx.fill(&Circle::new(Point::new(x, y).snap(), level.mark));
+                    }
+                }
+            }
+        }
+    }
+
+    fn grid_spacing(&self, zoom: f64) -> Option<f64> {
+        Some(finest_grid_spacing(zoom))
+    }
+}
+
+/// Grid of continuous horizontal and vertical lines.
+pub struct LineGrid {
+    pub background: ThemeColor,
+    pub lines: ThemeColor,
+}
+
+impl LineGrid {
+    pub fn new(background: impl Into<ThemeColor>, lines: impl Into<ThemeColor>) -> Self {
+        Self {
+            background: background.into(),
+            lines: lines.into(),
+        }
+    }
+}
+
+impl Background for LineGrid {
+    fn draw(&self, bounds: Rect, cx: &mut DrawContext) {
+        Blank::new(self.background).draw(bounds, cx);
+        if bounds.is_zero_area() {
+            return;
+        }
+
+        let theme = cx.theme();
+        let (background, lines) = (self.background.resolve(&theme), self.lines.resolve(&theme));
+        let mut filled_x = HashSet::new();
+        let mut filled_y = HashSet::new();
+        for level in grid_levels(zoom_of(cx), background, lines) {
+            cx.set_stroke_style(Stroke::new(level.mark / 2.));
+            cx.set_stroke_brush(Brush::Solid(level.color));
+            for x in grid_lines(bounds.min_x(), bounds.max_x(), level.spacing) {
+                if filled_x.insert(OrderedFloat(x)) {
+                    cx.stroke(&Line::new((x, bounds.min_y()), (x, bounds.max_y())));
+                }
+            }
+            for y in grid_lines(bounds.min_y(), bounds.max_y(), level.spacing) {
+                if filled_y.insert(OrderedFloat(y)) {
+                    cx.stroke(&Line::new((bounds.min_x(), y), (bounds.max_x(), y)));
+                }
+            }
+        }
+    }
+
+    fn grid_spacing(&self, zoom: f64) -> Option<f64> {
+        Some(finest_grid_spacing(zoom))
+    }
+}
+
+/// Grid of small crosses at each intersection.
+pub struct CrossGrid {
+    pub background: ThemeColor,
+    pub crosses: ThemeColor,
+}
+
+impl CrossGrid {
+    pub fn new(background: impl Into<ThemeColor>, crosses: impl Into<ThemeColor>) -> Self {
+        Self {
+            background: background.into(),
+            crosses: crosses.into(),
+        }
+    }
+}
+
+impl Background for CrossGrid {
+    fn draw(&self, bounds: Rect, cx: &mut DrawContext) {
+        Blank::new(self.background).draw(bounds, cx);
+        if bounds.is_zero_area() {
+            return;
+        }
+
+        let theme = cx.theme();
+        let (background, crosses) = (
+            self.background.resolve(&theme),
+            self.crosses.resolve(&theme),
+        );
+        let mut filled = HashSet::new();
+        for level in grid_levels(zoom_of(cx), background, crosses) {
+            let arm = level.mark * 2.;
+            cx.set_stroke_style(Stroke::new(level.mark / 2.));
+            cx.set_stroke_brush(Brush::Solid(level.color));
+            for x in grid_lines(bounds.min_x(), bounds.max_x(), level.spacing) {
+                for y in grid_lines(bounds.min_y(), bounds.max_y(), level.spacing) {
+                    if filled.insert((OrderedFloat(x), OrderedFloat(y))) {
+                        cx.stroke(&Line::new((x - arm, y), (x + arm, y)));
+                        cx.stroke(&Line::new((x, y - arm), (x, y + arm)));
+                    }
+                }
+            }
+        }
+    }
+
+    fn grid_spacing(&self, zoom: f64) -> Option<f64> {
+        Some(finest_grid_spacing(zoom))
+    }
+}
+
+/// Rulers along the top and left edges of a board labelled with board coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rulers {
+    pub background: ThemeColor,
+    pub foreground: ThemeColor,
+}
+
+impl Default for Rulers {
+    fn default() -> Self {
+        Self::new(ThemeColor::Background(1), ThemeColor::Muted)
+    }
+}
+
+impl Rulers {
+    pub fn new(background: impl Into<ThemeColor>, foreground: impl Into<ThemeColor>) -> Self {
+        Self {
+            background: background.into(),
+            foreground: foreground.into(),
+        }
+    }
+
+    // Board space distance between labelled ticks at the zoom.
+    fn spacing(zoom: f64) -> f64 {
+        let mut spacing = GRID_MAX_SPACING;
+        while spacing / 2. * zoom >= RULER_MIN_SPACING {
+            spacing /= 2.;
+        }
+        spacing
+    }
+
+    // Draws the rulers along the edges of the board's region, given the transform from board
+    // space to the region's space.
+    pub(super) fn draw(&self, region: Rect, transform: Affine, cx: &mut DrawContext) {
+        let zoom = transform.unskewed_scale().length() / 2.0f64.sqrt();
+        let spacing = Self::spacing(zoom);
+        let visible = transform.inverse().transform_rect_bbox(region);
+        let top = Rect::new(region.x0, region.y0, region.x1, region.y0 + RULER_THICKNESS);
+        let left = Rect::new(region.x0, region.y0, region.x0 + RULER_THICKNESS, region.y1);
+
+        let theme = cx.theme();
+        let (background, foreground) = (
+            self.background.resolve(&theme),
+            self.foreground.resolve(&theme),
+        );
+        cx.set_fill_brush(Brush::Solid(background));
+        cx.fill(&top);
+        cx.fill(&left);
+        cx.set_stroke_style(Stroke::new(1.));
+        cx.set_stroke_brush(Brush::Solid(foreground));
+        let styles = vec![
+            StyleProperty::FontSize(RULER_FONT_SIZE),
+            StyleProperty::Brush(Brush::Solid(foreground)),
+        ];
+
+        let minor_spacing = spacing / RULER_MINOR_TICKS as f64;
+        for x in grid_lines(visible.min_x(), visible.max_x(), minor_spacing) {
+            let screen_x = (transform * Point::new(x, 0.)).x.round() + 0.5;
+            if screen_x < left.x1 {
+                continue;
+            }
+            let labelled = x.rem_euclid(spacing) == 0.;
+            let length = RULER_TICK_LENGTH * if labelled { 2. } else { 1. };
+            cx.stroke(&Line::new((screen_x, top.y1 - length), (screen_x, top.y1)));
+            if labelled {
+                let label = cx
+                    .shaper
+                    .borrow_mut()
+                    .layout(&format!("{}", x + 0.), &styles);
+                cx.draw_layout_at(&label, Point::new(screen_x + 2., top.y0 + 1.));
+            }
+        }
+        for y in grid_lines(visible.min_y(), visible.max_y(), minor_spacing) {
+            let screen_y = (transform * Point::new(0., y)).y.round() + 0.5;
+            if screen_y < top.y1 {
+                continue;
+            }
+            let labelled = y.rem_euclid(spacing) == 0.;
+            let length = RULER_TICK_LENGTH * if labelled { 2. } else { 1. };
+            cx.stroke(&Line::new(
+                (left.x1 - length, screen_y),
+                (left.x1, screen_y),
+            ));
+            if labelled {
+                let label = cx
+                    .shaper
+                    .borrow_mut()
+                    .layout(&format!("{}", y + 0.), &styles);
+                cx.draw_layout_at(&label, Point::new(left.x0 + 1., screen_y + 2.));
+            }
+        }
+
+        cx.stroke(&Line::new((left.x1, top.y1), (region.x1, top.y1)));
+        cx.stroke(&Line::new((left.x1, top.y1), (left.x1, region.y1)));
+    }
+}
+
+impl Board {
+    pub fn with_background(
+        mut this: ElementPointer<Self>,
+        background: impl Background + 'static,
+    ) -> ElementPointer<Self> {
+        this.background = Rc::new(background);
+        this
+    }
+
+    pub fn with_rulers(mut this: ElementPointer<Self>, rulers: Rulers) -> ElementPointer<Self> {
+        this.rulers = Some(rulers);
+        this
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_levels_fade_in_and_match_snap_spacing() {
+        let black = Color::new([0., 0., 0., 1.]);
+        let white = Color::new([1., 1., 1., 1.]);
+        for zoom in [0.05, 0.3, 1., 2.5, 40.] {
+            let levels = grid_levels(zoom, black, white);
+            let finest = levels.last().unwrap();
+            assert_eq!(finest.spacing, finest_grid_spacing(zoom));
+            // Only the finest level can still be fading in
+            for level in levels.iter().rev().skip(1) {
+                assert_eq!(level.color, white);
+            }
+        }
+
+        assert_eq!(
+            grid_lines(-5., 20., 8.).collect::<Vec<_>>(),
+            vec![-8., 0., 8., 16.]
+        );
+    }
+}
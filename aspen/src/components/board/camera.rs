@@ -0,0 +1,316 @@
+use std::time::Duration;
+
+use vello::kurbo::{Affine, Point, Rect, Size};
+
+use super::{Board, BoardState};
+use crate::{
+    animation::{Easing, Tween},
+    context_stack::{Context, UpdateContext},
+    element::ElementPointer,
+    token::Token,
+    util::{AffineExt, Mixable},
+};
+
+pub const MIN_ZOOM: f64 = 0.025;
+pub const MAX_ZOOM: f64 = 100.;
+const CAMERA_ANIM_LENGTH: Duration = Duration::from_millis(300);
+// Screen space margin left around pins when fitting them into view
+const FIT_PADDING: f64 = 40.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraMove {
+    FitAll,
+    // Falls back to fitting everything when nothing is selected
+    FitSelection,
+    CenterOn(Token),
+    SetZoom(f64),
+}
+
+// Board space point shown at the center of the board's region along with the zoom it is shown
+// at. Boards only ever pan and zoom, so this fully describes the board's transform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Camera {
+    pub center: Point,
+    pub zoom: f64,
+}
+
+impl Camera {
+    pub fn from_transform(transform: Affine) -> Self {
+        Self {
+            center: transform.inverse() * Point::ZERO,
+            zoom: transform.unskewed_scale().length() / 2.0f64.sqrt(),
+        }
+    }
+
+    pub fn transform(&self) -> Affine {
+        Affine::scale(self.zoom) * Affine::translate(-self.center.to_vec2())
+    }
+
+    pub fn fitting(bounds: Rect, viewport: Size) -> Camera {
+        let available = Size::new(
+            (viewport.width - FIT_PADDING * 2.).max(1.),
+            (viewport.height - FIT_PADDING * 2.).max(1.),
+        );
+        let zoom = (available.width / bounds.width().max(1.))
+            .min(available.height / bounds.height().max(1.))
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+
+        Camera {
+            center: bounds.center(),
+            zoom,
+        }
+    }
+}
+
+// Zoom is treated multiplicatively, so differences between cameras hold zoom ratios and mixing
+// interpolates zoom geometrically, which makes zooming feel uniform across scales.
+impl Mixable for Camera {
+    fn add(&self, other: &Self) -> Self {
+        Camera {
+            center: self.center + other.center.to_vec2(),
+            zoom: self.zoom * other.zoom,
+        }
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        Camera {
+            center: (self.center - other.center).to_point(),
+            zoom: self.zoom / other.zoom,
+        }
+    }
+
+    fn scale(&self, factor: f64) -> Self {
+        Camera {
+            center: (self.center.to_vec2() * factor).to_point(),
+            zoom: self.zoom.powf(factor),
+        }
+    }
+
+    fn magnitude(&self) -> f64 {
+        self.center.to_vec2().hypot().hypot(self.zoom.ln())
+    }
+}
+
+impl Board {
+    pub(super) fn start_camera_move(&self, camera_move: CameraMove, cx: &mut UpdateContext) {
+        let Some(region) = cx.region() else {
+            // The board's size isn't known until it has been laid out once
+            cx.with_state(|state: &mut BoardState, _| {
+                state.pending_camera_move = Some(camera_move)
+            });
+            cx.request_redraw();
+            return;
+        };
+
+        let (current, selection) = cx.with_state(|state: &mut BoardState, _| {
+            (
+                Camera::from_transform(state.transform),
+                state.selection.clone(),
+            )
+        });
+
+        let fit = |selected: &dyn Fn(Token) -> bool| {
+            self.pin_rects(cx)
+                .into_iter()
+                .filter(|(token, _)| selected(*token))
+                .map(|(_, rect)| rect)
+                .reduce(|bounds, rect| bounds.union(rect))
+                .map(|bounds| Camera::fitting(bounds, region.size()))
+        };
+
+        let target = match camera_move {
+            CameraMove::FitAll => fit(&|_| true),
+            CameraMove::FitSelection if selection.is_empty() => fit(&|_| true),
+            CameraMove::FitSelection => fit(&|token| selection.contains(&token)),
+            CameraMove::CenterOn(token) => self
+                .pin_rects(cx)
+                .into_iter()
+                .find(|(pin, _)| *pin == token)
+                .map(|(_, rect)| Camera {
+                    center: rect.center(),
+                    zoom: current.zoom,
+                }),
+            CameraMove::SetZoom(zoom) => Some(Camera {
+                center: current.center,
+                zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+            }),
+        };
+
+        if let Some(target) = target {
+            let now = cx.frame_time();
+            cx.with_state(|state: &mut BoardState, _| {
+                state.camera_motion.stop();
+                state.camera_animation = Some(Tween::new(
+                    current,
+                    target,
+                    CAMERA_ANIM_LENGTH,
+                    Easing::Smoothstep,
+                    now,
+                ));
+            });
+            cx.request_redraw();
+        }
+    }
+
+    pub(super) fn animate_camera(&self, cx: &mut UpdateContext) {
+        let now = cx.frame_time();
+        // Whether the camera moved and whether it is still moving
+        let (moved, animating) = cx.with_state(|state: &mut BoardState, _| {
+            let Some(animation) = state.camera_animation.as_ref() else {
+                return (false, false);
+            };
+
+            state.transform = animation.value_at(now).transform();
+            if animation.finished(now) {
+                state.camera_animation = None;
+            }
+            (true, state.camera_animation.is_some())
+        });
+
+        if moved {
+            cx.request_redraw();
+        }
+        if animating {
+            cx.request_animation_frame();
+        }
+    }
+}
+
+impl ElementPointer<Board> {
+    // Smoothly moves the board's view over the following frames.
+    pub fn move_camera(&self, camera_move: CameraMove, cx: &Context) {
+        self.with_state(cx, |state: &mut BoardState, _| {
+            state.pending_camera_move = Some(camera_move);
+        });
+    }
+
+    pub fn fit_all(&self, cx: &Context) {
+        self.move_camera(CameraMove::FitAll, cx);
+    }
+
+    pub fn fit_selection(&self, cx: &Context) {
+        self.move_camera(CameraMove::FitSelection, cx);
+    }
+
+    pub fn center_on(&self, pin: Token, cx: &Context) {
+        self.move_camera(CameraMove::CenterOn(pin), cx);
+    }
+
+    pub fn set_zoom(&self, zoom: f64, cx: &Context) {
+        self.move_camera(CameraMove::SetZoom(zoom), cx);
+    }
+
+    pub fn zoom(&self, cx: &Context) -> f64 {
+        self.read_state(cx, |state: &BoardState, _| {
+            Camera::from_transform(state.transform).zoom
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::peniko::Color;
+    use winit::{
+        keyboard::{Key, NamedKey, SmolStr},
+        window::{Cursor, CursorIcon},
+    };
+
+    use super::*;
+    use crate::{
+        components::{
+            board::{Blank, PinWrapper},
+            layout::{Align, SizedBox},
+        },
+        test_runner::TestRunner,
+    };
+
+    // Board filling a 400 by 400 window with 40 by 40 pins at the given centers. Returns the
+    // pins' tokens.
+    fn board_with_pins(centers: &[Point]) -> (TestRunner<Align<Board>>, Vec<Token>) {
+        let mut pins = Vec::new();
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            for center in centers {
+                let pin = PinWrapper::new(*center, SizedBox::new(Size::new(40., 40.)), cx);
+                pins.push(board.add_child(pin));
+            }
+            Align::center(board)
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        (test_runner, pins)
+    }
+
+    fn camera(test_runner: &TestRunner<Align<Board>>) -> Camera {
+        test_runner.with_root(|root, cx| {
+            root.child.read_state(cx, |state: &BoardState, _| {
+                Camera::from_transform(state.transform)
+            })
+        })
+    }
+
+    fn assert_near(camera: Camera, center: Point, zoom: f64) {
+        assert!((camera.center - center).hypot() < 1e-6, "{camera:?}");
+        assert!((camera.zoom - zoom).abs() < 1e-6, "{camera:?}");
+    }
+
+    #[test]
+    fn cameras_mix_zoom_geometrically() {
+        let from = Camera {
+            center: Point::ZERO,
+            zoom: 1.,
+        };
+        let to = Camera {
+            center: Point::new(100., 0.),
+            zoom: 4.,
+        };
+        assert_near(from.mix(&to, 0.5), Point::new(50., 0.), 2.);
+        assert_near(from.add(&to.sub(&from)), to.center, to.zoom);
+        assert_near(from.add(&to.sub(&from).scale(0.5)), Point::new(50., 0.), 2.);
+        assert!(to.sub(&to).magnitude() < 1e-9);
+    }
+
+    #[test]
+    fn camera_moves_are_animated() {
+        let (mut test_runner, pins) =
+            board_with_pins(&[Point::new(-300., 0.), Point::new(300., 100.)]);
+
+        test_runner.with_root(|root, cx| root.child.fit_all(cx));
+        test_runner.tick();
+        test_runner.advance_time(CAMERA_ANIM_LENGTH / 2);
+        let halfway = camera(&test_runner);
+        assert!(halfway.zoom < 1. && halfway.zoom > 0.5, "{halfway:?}");
+        // Pins span 640 by 140 and the window leaves 320 by 320 within its padding
+        test_runner.advance_time(CAMERA_ANIM_LENGTH);
+        assert_near(camera(&test_runner), Point::new(0., 50.), 0.5);
+
+        test_runner.with_root(|root, cx| root.child.center_on(pins[0], cx));
+        test_runner.tick();
+        test_runner.advance_time(CAMERA_ANIM_LENGTH * 2);
+        assert_near(camera(&test_runner), Point::new(-300., 0.), 0.5);
+
+        test_runner.with_root(|root, cx| root.child.set_zoom(1000., cx));
+        test_runner.tick();
+        test_runner.advance_time(CAMERA_ANIM_LENGTH * 2);
+        assert_near(camera(&test_runner), Point::new(-300., 0.), MAX_ZOOM);
+        test_runner.with_root(|root, cx| assert_eq!(root.child.zoom(cx), MAX_ZOOM));
+    }
+
+    #[test]
+    fn shortcuts_frame_the_selection_and_fit_everything() {
+        let (mut test_runner, _) = board_with_pins(&[Point::ZERO, Point::new(600., 0.)]);
+
+        // Clicking a pin selects it and focuses the board
+        test_runner.move_mouse(Point::new(200., 200.));
+        test_runner.press_mouse();
+        test_runner.release_mouse();
+        test_runner.input_key(Key::Character(SmolStr::new_inline("f")));
+        test_runner.advance_time(CAMERA_ANIM_LENGTH * 2);
+        assert_near(camera(&test_runner), Point::ZERO, 8.);
+
+        test_runner.input_key(Key::Named(NamedKey::Home));
+        test_runner.advance_time(CAMERA_ANIM_LENGTH * 2);
+        assert_near(camera(&test_runner), Point::new(300., 0.), 0.5);
+    }
+}
@@ -0,0 +1,423 @@
+use std::{any::Any, mem};
+
+use vello::kurbo::{Point, Rect};
+
+use super::{Board, BoardState, Connection, Direction, PinPointer, Pinnable};
+use crate::{
+    context_stack::Context,
+    element::{Element, ElementPointer},
+    history::{Command, History},
+    token::Token,
+};
+
+/// Moves pins between centers. Moves recorded during a single drag coalesce into one command.
+pub struct MovePins {
+    moves: Vec<(Token, Point, Point)>,
+    dragging: bool,
+}
+
+impl MovePins {
+    // Each move is a pin along with its center before and after the move.
+    pub fn new(moves: Vec<(Token, Point, Point)>) -> Self {
+        Self {
+            moves,
+            dragging: false,
+        }
+    }
+
+    pub(super) fn dragging(moves: Vec<(Token, Point, Point)>) -> Self {
+        Self {
+            moves,
+            dragging: true,
+        }
+    }
+}
+
+impl Command<Board> for MovePins {
+    fn apply(&mut self, board: &mut Board, cx: &Context) {
+        for (token, _, to) in self.moves.iter() {
+            board.set_pin_center(*token, *to, cx);
+        }
+    }
+
+    fn revert(&mut self, board: &mut Board, cx: &Context) {
+        for (token, from, _) in self.moves.iter() {
+            board.set_pin_center(*token, *from, cx);
+        }
+    }
+
+    fn merge(&mut self, next: &dyn Any) -> bool {
+        let Some(next) = next.downcast_ref::<MovePins>() else {
+            return false;
+        };
+        if !self.dragging || !next.dragging {
+            return false;
+        }
+
+        for (token, from, to) in next.moves.iter() {
+            match self.moves.iter_mut().find(|(pin, _, _)| pin == token) {
+                Some((_, _, current)) => *current = *to,
+                None => self.moves.push((*token, *from, *to)),
+            }
+        }
+        true
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of_val(self) + mem::size_of_val(self.moves.as_slice())
+    }
+}
+
+/// Moves or resizes a frame along with the pins it carries.
+pub struct MoveFrame {
+    frame: Token,
+    from: Rect,
+    to: Rect,
+    pins: MovePins,
+}
+
+impl MoveFrame {
+    pub fn new(frame: Token, from: Rect, to: Rect, pins: Vec<(Token, Point, Point)>) -> Self {
+        Self {
+            frame,
+            from,
+            to,
+            pins: MovePins::new(pins),
+        }
+    }
+
+    pub(super) fn dragging(
+        frame: Token,
+        from: Rect,
+        to: Rect,
+        pins: Vec<(Token, Point, Point)>,
+    ) -> Self {
+        Self {
+            frame,
+            from,
+            to,
+            pins: MovePins::dragging(pins),
+        }
+    }
+}
+
+impl Command<Board> for MoveFrame {
+    fn apply(&mut self, board: &mut Board, cx: &Context) {
+        if let Some(frame) = board.frame_mut(self.frame) {
+            frame.rect = self.to;
+        }
+        self.pins.apply(board, cx);
+    }
+
+    fn revert(&mut self, board: &mut Board, cx: &Context) {
+        if let Some(frame) = board.frame_mut(self.frame) {
+            frame.rect = self.from;
+        }
+        self.pins.revert(board, cx);
+    }
+
+    fn merge(&mut self, next: &dyn Any) -> bool {
+        let Some(next) = next.downcast_ref::<MoveFrame>() else {
+            return false;
+        };
+        if next.frame != self.frame || !self.pins.merge(&next.pins) {
+            return false;
+        }
+
+        self.to = next.to;
+        true
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of_val(self) + self.pins.size()
+    }
+}
+
+/// Moves a note between centers.
+pub struct MoveNote {
+    note: Token,
+    from: Point,
+    to: Point,
+    dragging: bool,
+}
+
+impl MoveNote {
+    pub fn new(note: Token, from: Point, to: Point) -> Self {
+        Self {
+            note,
+            from,
+            to,
+            dragging: false,
+        }
+    }
+
+    pub(super) fn dragging(note: Token, from: Point, to: Point) -> Self {
+        Self {
+            note,
+            from,
+            to,
+            dragging: true,
+        }
+    }
+}
+
+impl Command<Board> for MoveNote {
+    fn apply(&mut self, board: &mut Board, _cx: &Context) {
+        if let Some(note) = board.note_mut(self.note) {
+            note.center = self.to;
+        }
+    }
+
+    fn revert(&mut self, board: &mut Board, _cx: &Context) {
+        if let Some(note) = board.note_mut(self.note) {
+            note.center = self.from;
+        }
+    }
+
+    fn merge(&mut self, next: &dyn Any) -> bool {
+        match next.downcast_ref::<MoveNote>() {
+            Some(next) if self.dragging && next.dragging && next.note == self.note => {
+                self.to = next.to;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Adds a pin to the board. Reverting removes the pin along with any wires attached to it.
+pub struct InsertPin {
+    token: Token,
+    pin: Option<PinPointer>,
+    removed_connections: Vec<Connection>,
+}
+
+impl InsertPin {
+    pub fn new(pin: ElementPointer<impl Pinnable + 'static>) -> Self {
+        Self {
+            token: pin.token(),
+            pin: Some(pin.map(|element| Box::new(element) as Box<dyn Pinnable + 'static>)),
+            removed_connections: Vec::new(),
+        }
+    }
+
+    pub fn token(&self) -> Token {
+        self.token
+    }
+}
+
+impl Command<Board> for InsertPin {
+    fn apply(&mut self, board: &mut Board, _cx: &Context) {
+        if let Some(pin) = self.pin.take() {
+            board.children.push(pin);
+        }
+        board.connections.append(&mut self.removed_connections);
+    }
+
+    fn revert(&mut self, board: &mut Board, _cx: &Context) {
+        if let Some(index) = board
+            .children
+            .iter()
+            .position(|child| child.token() == self.token)
+        {
+            self.pin = Some(board.children.remove(index));
+        }
+
+        let (removed, kept) = board
+            .connections
+            .drain(..)
+            .partition(|connection| connection.touches(self.token));
+        self.removed_connections = removed;
+        board.connections = kept;
+    }
+}
+
+/// Adds a wire between two pins.
+pub struct Connect {
+    connection: Connection,
+}
+
+impl Connect {
+    pub fn new(from: Token, to: Token, direction: Option<Direction>) -> Self {
+        Self {
+            connection: Connection::new(from, to, direction),
+        }
+    }
+}
+
+impl Command<Board> for Connect {
+    fn apply(&mut self, board: &mut Board, _cx: &Context) {
+        board.connections.push(self.connection);
+    }
+
+    fn revert(&mut self, board: &mut Board, _cx: &Context) {
+        if let Some(index) = board
+            .connections
+            .iter()
+            .rposition(|connection| *connection == self.connection)
+        {
+            board.connections.remove(index);
+        }
+    }
+}
+
+/// Moves pins into a new subgraph pin, routing wires that leave the group through ports.
+pub struct GroupSubgraph {
+    pins: Vec<Token>,
+    token: Token,
+    // Held while the group is undone so that redoing keeps the subgraph's token
+    subgraph: Option<PinPointer>,
+}
+
+impl GroupSubgraph {
+    pub fn new(pins: Vec<Token>, subgraph: PinPointer) -> Self {
+        Self {
+            pins,
+            token: subgraph.token(),
+            subgraph: Some(subgraph),
+        }
+    }
+}
+
+impl Command<Board> for GroupSubgraph {
+    fn apply(&mut self, board: &mut Board, cx: &Context) {
+        if let Some(subgraph) = self.subgraph.take() {
+            board.group_subgraph(&self.pins, subgraph, cx);
+        }
+    }
+
+    fn revert(&mut self, board: &mut Board, cx: &Context) {
+        self.subgraph = board
+            .expand_subgraph(self.token, cx)
+            .map(|(subgraph, _)| subgraph);
+    }
+}
+
+/// Moves the pins of a subgraph back onto the board holding it.
+pub struct ExpandSubgraph {
+    token: Token,
+    pins: Vec<Token>,
+    subgraph: Option<PinPointer>,
+}
+
+impl ExpandSubgraph {
+    pub fn new(token: Token) -> Self {
+        Self {
+            token,
+            pins: Vec::new(),
+            subgraph: None,
+        }
+    }
+}
+
+impl Command<Board> for ExpandSubgraph {
+    fn apply(&mut self, board: &mut Board, cx: &Context) {
+        if let Some((subgraph, pins)) = board.expand_subgraph(self.token, cx) {
+            self.subgraph = Some(subgraph);
+            self.pins = pins;
+        }
+    }
+
+    fn revert(&mut self, board: &mut Board, cx: &Context) {
+        if let Some(subgraph) = self.subgraph.take() {
+            board.group_subgraph(&self.pins, subgraph, cx);
+        }
+    }
+}
+
+impl Board {
+    pub(super) fn set_pin_center(&self, token: Token, center: Point, cx: &Context) {
+        if let Some(child) = self.children.iter().find(|child| child.token() == token) {
+            child.with_context(cx, |cx| child.set_center(center, cx));
+        }
+    }
+
+    // Runs the callback with the board's history taken out of its state so that commands are
+    // free to touch the board's and pins' states.
+    pub(super) fn with_history<Result>(
+        &mut self,
+        cx: &Context,
+        callback: impl FnOnce(&mut History<Board>, &mut Board) -> Result,
+    ) -> Result {
+        let mut history = cx.with_state(|state: &mut BoardState, _| mem::take(&mut state.history));
+        let result = callback(&mut history, self);
+        cx.with_state(|state: &mut BoardState, _| state.history = history);
+        result
+    }
+
+    pub(super) fn step_history(&mut self, undo: bool, cx: &Context) -> bool {
+        // Pins mid layout animation would otherwise snap back to the animation's targets
+        cx.with_state(|state: &mut BoardState, _| state.pin_animation = None);
+        self.with_history(cx, |history, board| {
+            if undo {
+                history.undo(board, cx)
+            } else {
+                history.redo(board, cx)
+            }
+        })
+    }
+}
+
+impl ElementPointer<Board> {
+    pub(super) fn with_board_context<Result>(
+        &mut self,
+        cx: &Context,
+        callback: impl FnOnce(&mut Board, &Context) -> Result,
+    ) -> Result {
+        let children = Element::children(&**self);
+        let cx = cx.child(self.token(), &children);
+        callback(self, &cx)
+    }
+
+    // Applies the command to the board and records it so that it can be undone.
+    pub fn execute(&mut self, command: impl Command<Board>, cx: &Context) {
+        self.with_board_context(cx, |board, cx| {
+            board.with_history(cx, |history, board| history.execute(command, board, cx))
+        });
+    }
+
+    pub fn insert_pin(
+        &mut self,
+        pin: ElementPointer<impl Pinnable + 'static>,
+        cx: &Context,
+    ) -> Token {
+        let command = InsertPin::new(pin);
+        let token = command.token();
+        self.execute(command, cx);
+        token
+    }
+
+    pub fn connect_pins(
+        &mut self,
+        from: Token,
+        to: Token,
+        direction: Option<Direction>,
+        cx: &Context,
+    ) {
+        self.execute(Connect::new(from, to, direction), cx);
+    }
+
+    pub fn undo(&mut self, cx: &Context) -> bool {
+        self.with_board_context(cx, |board, cx| board.step_history(true, cx))
+    }
+
+    pub fn redo(&mut self, cx: &Context) -> bool {
+        self.with_board_context(cx, |board, cx| board.step_history(false, cx))
+    }
+
+    // Groups the commands executed by the callback into a single undo step.
+    pub fn transaction<Result>(
+        &mut self,
+        cx: &Context,
+        callback: impl FnOnce(&mut Self, &Context) -> Result,
+    ) -> Result {
+        self.with_state(cx, |state: &mut BoardState, _| {
+            state.history.begin_transaction()
+        });
+        let result = callback(self, cx);
+        self.with_state(cx, |state: &mut BoardState, _| {
+            state.history.end_transaction()
+        });
+        result
+    }
+}
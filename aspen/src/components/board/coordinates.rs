@@ -0,0 +1,218 @@
+use vello::kurbo::{Affine, Point, Rect, Vec2};
+
+use super::{Board, BoardState};
+use crate::{context_stack::Context, element::ElementPointer, token::Token};
+
+// Screen space here is the window space the board was last drawn into, matching the mouse
+// positions reported by the window.
+impl Board {
+    /// Pins whose laid out rects intersect the board space rect, in drawing order.
+    pub fn pins_in_rect(&self, rect: Rect) -> Vec<Token> {
+        // Rects dragged out towards the top left have negative sizes
+        let rect = rect.abs();
+        let mut indices: Vec<usize> = self
+            .pin_index
+            .query(rect)
+            .filter(|(pin_rect, _)| pin_rect.overlaps(rect))
+            .map(|(_, index)| *index)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .filter_map(|index| self.children.get(index))
+            .map(|child| child.token())
+            .collect()
+    }
+
+    /// Topmost pin containing the board space point.
+    pub fn pin_at(&self, point: Point) -> Option<Token> {
+        self.pin_index
+            .query_point(point)
+            .filter(|(pin_rect, _)| pin_rect.contains(point))
+            .map(|(_, index)| *index)
+            .max()
+            .and_then(|index| self.children.get(index))
+            .map(|child| child.token())
+    }
+
+    fn screen_space(cx: &Context) -> (Affine, Rect) {
+        cx.read_state(|state: &BoardState, _| (state.screen_transform, state.screen_region))
+    }
+
+    pub(super) fn board_at(&self, path: &[Token]) -> Option<&Board> {
+        let Some((pin, rest)) = path.split_first() else {
+            return Some(self);
+        };
+        self.children
+            .iter()
+            .find(|child| child.token() == *pin)?
+            .nested_board()?
+            .board_at(rest)
+    }
+
+    // Board space to screen transform of the board held by the pin, however deeply it is nested.
+    fn nested_transform(&self, pin: Token, cx: &Context) -> Option<Affine> {
+        let mut path = self.path_to_pin(pin)?;
+        path.push(pin);
+        self.screen_transform_at(&path, Self::screen_space(cx).0, cx)
+    }
+
+    // Innermost pin under the screen space point, looking into the boards nested in pins which
+    // are drawn in full.
+    fn pin_at_screen(&self, point: Point, cx: &Context) -> Option<Token> {
+        let (screen, region) = Self::screen_space(cx);
+        if !region.contains(point) {
+            return None;
+        }
+
+        let path = self.path_to_board_at(point, screen, region, &[], cx);
+        (0..=path.len()).rev().find_map(|depth| {
+            let board = self.board_at(&path[..depth])?;
+            let transform = self.screen_transform_at(&path[..depth], screen, cx)?;
+            board.pin_at(transform.inverse() * point)
+        })
+    }
+}
+
+impl ElementPointer<Board> {
+    /// Transform from board space to screen space as of the board's last draw.
+    pub fn screen_transform(&self, cx: &Context) -> Affine {
+        self.read_state(cx, |state: &BoardState, _| state.screen_transform)
+    }
+
+    /// Screen space region the board was last drawn into, clipped to the window.
+    pub fn screen_region(&self, cx: &Context) -> Rect {
+        self.read_state(cx, |state: &BoardState, _| state.screen_region)
+    }
+
+    /// Board space point in screen space.
+    pub fn board_to_screen(&self, point: Point, cx: &Context) -> Point {
+        self.screen_transform(cx) * point
+    }
+
+    /// Screen space point in board space.
+    pub fn screen_to_board(&self, point: Point, cx: &Context) -> Point {
+        self.screen_transform(cx).inverse() * point
+    }
+
+    /// Screen space bounds of the board space rect.
+    pub fn board_rect_to_screen(&self, rect: Rect, cx: &Context) -> Rect {
+        self.screen_transform(cx).transform_rect_bbox(rect)
+    }
+
+    /// Board space bounds of the screen space rect.
+    pub fn screen_rect_to_board(&self, rect: Rect, cx: &Context) -> Rect {
+        self.screen_transform(cx)
+            .inverse()
+            .transform_rect_bbox(rect)
+    }
+
+    /// Board space vector in screen space. Vectors are only scaled, ignoring the board's pan.
+    pub fn board_vec_to_screen(&self, vec: Vec2, cx: &Context) -> Vec2 {
+        let transform = self.screen_transform(cx);
+        transform * vec.to_point() - transform * Point::ZERO
+    }
+
+    /// Screen space vector in board space.
+    pub fn screen_vec_to_board(&self, vec: Vec2, cx: &Context) -> Vec2 {
+        let transform = self.screen_transform(cx).inverse();
+        transform * vec.to_point() - transform * Point::ZERO
+    }
+
+    /// Transform from the board space of the board held by the pin to screen space. The pin may
+    /// be on this board or on any board nested in it.
+    pub fn nested_screen_transform(&self, pin: Token, cx: &Context) -> Option<Affine> {
+        self.with_context(cx, |cx| self.nested_transform(pin, cx))
+    }
+
+    /// Innermost pin under the screen space point, including pins on nested boards.
+    pub fn pin_at_screen_point(&self, point: Point, cx: &Context) -> Option<Token> {
+        self.with_context(cx, |cx| self.pin_at_screen(point, cx))
+    }
+
+    /// Pins on this board intersecting the screen space rect.
+    pub fn pins_in_screen_rect(&self, rect: Rect, cx: &Context) -> Vec<Token> {
+        self.pins_in_rect(self.screen_rect_to_board(rect, cx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::{kurbo::Size, peniko::Color};
+
+    use super::*;
+    use crate::{
+        components::{
+            board::{Blank, PinWrapper},
+            layout::Align,
+        },
+        test_runner::TestRunner,
+    };
+
+    fn new_pin(center: Point, size: Size, cx: &Context) -> ElementPointer<PinWrapper<Board>> {
+        let board = Board::new(Affine::IDENTITY, Blank::new(Color::WHITE), cx);
+        PinWrapper::new_sized(center, size, board, cx)
+    }
+
+    #[test]
+    fn pins_are_found_through_nested_boards() {
+        let mut inner = None;
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut outer = new_pin(Point::ZERO, Size::new(200., 200.), cx);
+            let pin = new_pin(Point::new(20., 20.), Size::new(40., 40.), cx);
+            inner = Some(pin.token());
+            outer.child.add_child(pin);
+
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            board.add_child(outer);
+            Align::center(board)
+        });
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        let inner = inner.unwrap();
+
+        test_runner.with_root(|root, cx| {
+            let board = &root.child;
+            let outer = board.children[0].token();
+            assert_eq!(
+                board.board_to_screen(Point::ZERO, cx),
+                Point::new(200., 200.)
+            );
+            assert_eq!(
+                board.screen_to_board(Point::new(250., 150.), cx),
+                Point::new(50., -50.)
+            );
+            assert_eq!(
+                board.board_vec_to_screen(Vec2::new(10., 10.), cx),
+                Vec2::new(10., 10.)
+            );
+            assert_eq!(
+                board.pins_in_screen_rect(Rect::new(90., 90., 150., 150.), cx),
+                vec![outer]
+            );
+            // Rects dragged towards the top left find the same pins
+            assert_eq!(
+                board.pins_in_rect(Rect::new(-50., -50., -110., -110.)),
+                vec![outer]
+            );
+            assert!(board
+                .pins_in_screen_rect(Rect::new(10., 10., 90., 90.), cx)
+                .is_empty());
+
+            let nested = board.nested_screen_transform(outer, cx).unwrap();
+            assert_eq!(nested * Point::new(20., 20.), Point::new(220., 220.));
+            let innermost = board.nested_screen_transform(inner, cx).unwrap();
+            assert_eq!(innermost * Point::ZERO, Point::new(220., 220.));
+            assert_eq!(
+                board.pin_at_screen_point(Point::new(220., 220.), cx),
+                Some(inner)
+            );
+            assert_eq!(
+                board.pin_at_screen_point(Point::new(250., 150.), cx),
+                Some(outer)
+            );
+            assert_eq!(board.pin_at_screen_point(Point::new(50., 50.), cx), None);
+        });
+    }
+}
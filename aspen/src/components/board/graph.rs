@@ -0,0 +1,55 @@
+use crate::token::Token;
+
+/// Hint for where the target of a connection should sit relative to its source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Above,
+    Below,
+    Before,
+    After,
+}
+
+impl Direction {
+    pub fn is_horizontal(&self) -> bool {
+        matches!(self, Direction::Before | Direction::After)
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        !self.is_horizontal()
+    }
+
+    // True if the target comes after the source when reading left to right or top to bottom.
+    pub fn is_forward(&self) -> bool {
+        matches!(self, Direction::Below | Direction::After)
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Above => Direction::Below,
+            Direction::Below => Direction::Above,
+            Direction::Before => Direction::After,
+            Direction::After => Direction::Before,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Connection {
+    pub from: Token,
+    pub to: Token,
+    pub direction: Option<Direction>,
+}
+
+impl Connection {
+    pub fn new(from: Token, to: Token, direction: Option<Direction>) -> Self {
+        Self {
+            from,
+            to,
+            direction,
+        }
+    }
+
+    pub fn touches(&self, token: Token) -> bool {
+        self.from == token || self.to == token
+    }
+}
@@ -0,0 +1,796 @@
+use std::collections::{HashMap, VecDeque};
+
+use vello::kurbo::{Point, Size, Vec2};
+
+use super::graph::{Connection, Direction};
+use crate::token::Token;
+
+// Gap between neighboring nodes within a layer
+const NODE_SPACING: f64 = 40.;
+// Gap between consecutive layers
+const LAYER_SPACING: f64 = 80.;
+const ORDERING_SWEEPS: usize = 8;
+const PLACEMENT_SWEEPS: usize = 4;
+const FORCE_ITERATIONS: usize = 300;
+const OVERLAP_PASSES: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphLayout {
+    // Sugiyama style layering for dependency trees and DAGs
+    Layered,
+    // Spring embedding for general graphs
+    ForceDirected,
+    // Compact tidy tree over a spanning forest of the graph
+    Tree,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutNode {
+    pub token: Token,
+    pub center: Point,
+    pub size: Size,
+}
+
+impl GraphLayout {
+    /// Computes new centers for the passed nodes. Nodes keep their current centroid so that
+    /// applying a layout doesn't throw the graph across the board.
+    pub fn compute(
+        &self,
+        nodes: &[LayoutNode],
+        connections: &[Connection],
+    ) -> HashMap<Token, Point> {
+        if nodes.is_empty() {
+            return HashMap::new();
+        }
+
+        let graph = FlowGraph::new(nodes, connections);
+        let centers = match self {
+            GraphLayout::Layered => graph.layered(),
+            GraphLayout::ForceDirected => force_directed(nodes, &graph),
+            GraphLayout::Tree => graph.tree(),
+        };
+
+        let previous_centroid = centroid(nodes.iter().map(|node| node.center));
+        let new_centroid = centroid(centers.iter().copied());
+        let offset = (previous_centroid - new_centroid).round();
+
+        nodes
+            .iter()
+            .zip(centers)
+            .map(|(node, center)| (node.token, center + offset))
+            .collect()
+    }
+}
+
+fn centroid(points: impl Iterator<Item = Point>) -> Point {
+    let mut count = 0.;
+    let mut sum = Vec2::ZERO;
+    for point in points {
+        sum += point.to_vec2();
+        count += 1.;
+    }
+
+    if count == 0. {
+        Point::ZERO
+    } else {
+        (sum / count).to_point()
+    }
+}
+
+// The graph normalized so that every connection either flows along the primary axis from
+// `from` to `to` or constrains the cross axis order of two nodes in the same layer.
+struct FlowGraph {
+    horizontal: bool,
+    // Extent of each node along the cross axis and along the flow axis.
+    cross_extents: Vec<f64>,
+    flow_extents: Vec<f64>,
+    // Current cross axis coordinate used to seed orderings
+    cross_positions: Vec<f64>,
+    flow_edges: Vec<(usize, usize)>,
+    // Whether each flow edge came from a connection with a direction hint
+    flow_hinted: Vec<bool>,
+    // (from, to, to_after_from)
+    cross_edges: Vec<(usize, usize, bool)>,
+}
+
+impl FlowGraph {
+    fn new(nodes: &[LayoutNode], connections: &[Connection]) -> Self {
+        let indices: HashMap<Token, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.token, index))
+            .collect();
+
+        // Pick the flow axis that the most direction hints agree with
+        let (horizontal_hints, vertical_hints) =
+            connections
+                .iter()
+                .fold((0, 0), |(h, v), connection| match connection.direction {
+                    Some(direction) if direction.is_horizontal() => (h + 1, v),
+                    Some(_) => (h, v + 1),
+                    None => (h, v),
+                });
+        let horizontal = horizontal_hints > vertical_hints;
+
+        let mut flow_edges = Vec::new();
+        let mut flow_hinted = Vec::new();
+        let mut cross_edges = Vec::new();
+        for connection in connections {
+            let (Some(&from), Some(&to)) =
+                (indices.get(&connection.from), indices.get(&connection.to))
+            else {
+                continue;
+            };
+            if from == to {
+                continue;
+            }
+
+            match connection.direction {
+                None => {
+                    flow_edges.push((from, to));
+                    flow_hinted.push(false);
+                }
+                Some(direction) if direction.is_horizontal() == horizontal => {
+                    if direction.is_forward() {
+                        flow_edges.push((from, to));
+                    } else {
+                        flow_edges.push((to, from));
+                    }
+                    flow_hinted.push(true);
+                }
+                Some(direction) => cross_edges.push((from, to, direction.is_forward())),
+            }
+        }
+
+        let (cross_extents, flow_extents, cross_positions) = if horizontal {
+            (
+                nodes.iter().map(|node| node.size.height).collect(),
+                nodes.iter().map(|node| node.size.width).collect(),
+                nodes.iter().map(|node| node.center.y).collect(),
+            )
+        } else {
+            (
+                nodes.iter().map(|node| node.size.width).collect(),
+                nodes.iter().map(|node| node.size.height).collect(),
+                nodes.iter().map(|node| node.center.x).collect(),
+            )
+        };
+
+        Self {
+            horizontal,
+            cross_extents,
+            flow_extents,
+            cross_positions,
+            flow_edges,
+            flow_hinted,
+            cross_edges,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.cross_extents.len()
+    }
+
+    fn to_point(&self, cross: f64, flow: f64) -> Point {
+        if self.horizontal {
+            Point::new(flow, cross)
+        } else {
+            Point::new(cross, flow)
+        }
+    }
+
+    // Reverses the edges which close a cycle so that the flow edges form a DAG.
+    fn acyclic_flow_edges(&self) -> Vec<(usize, usize)> {
+        let mut successors = vec![Vec::new(); self.len()];
+        for (edge_index, (from, to)) in self.flow_edges.iter().enumerate() {
+            successors[*from].push((*to, edge_index));
+        }
+
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            Unvisited,
+            InProgress,
+            Done,
+        }
+
+        let mut marks = vec![Mark::Unvisited; self.len()];
+        let mut reversed = vec![false; self.flow_edges.len()];
+        for start in 0..self.len() {
+            if marks[start] != Mark::Unvisited {
+                continue;
+            }
+
+            let mut stack = vec![(start, 0)];
+            marks[start] = Mark::InProgress;
+            while let Some((node, next_successor)) = stack.last_mut() {
+                if let Some(&(successor, edge_index)) = successors[*node].get(*next_successor) {
+                    *next_successor += 1;
+                    match marks[successor] {
+                        Mark::Unvisited => {
+                            marks[successor] = Mark::InProgress;
+                            stack.push((successor, 0));
+                        }
+                        Mark::InProgress => reversed[edge_index] = true,
+                        Mark::Done => {}
+                    }
+                } else {
+                    marks[*node] = Mark::Done;
+                    stack.pop();
+                }
+            }
+        }
+
+        self.flow_edges
+            .iter()
+            .zip(reversed)
+            .map(|(&(from, to), reversed)| if reversed { (to, from) } else { (from, to) })
+            .collect()
+    }
+
+    fn ranks(&self, flow_edges: &[(usize, usize)]) -> Vec<usize> {
+        let mut ranks = vec![0; self.len()];
+        // Longest path layering. Cross edges are zero length constraints which pull the target
+        // into the same layer as the source.
+        for _ in 0..=self.len() {
+            let mut changed = false;
+            for &(from, to) in flow_edges {
+                if ranks[to] < ranks[from] + 1 {
+                    ranks[to] = ranks[from] + 1;
+                    changed = true;
+                }
+            }
+            for &(from, to, _) in self.cross_edges.iter() {
+                if ranks[to] < ranks[from] {
+                    ranks[to] = ranks[from];
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        ranks
+    }
+
+    fn layered(&self) -> Vec<Point> {
+        let flow_edges = self.acyclic_flow_edges();
+        let ranks = self.ranks(&flow_edges);
+
+        // Split long edges with dummy nodes so that every edge spans exactly one layer.
+        let mut item_ranks = ranks.clone();
+        let mut item_cross_extents = self.cross_extents.clone();
+        let mut item_positions = self.cross_positions.clone();
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); self.len()];
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); self.len()];
+        for &(from, to) in flow_edges.iter() {
+            let mut previous = from;
+            for rank in ranks[from] + 1..ranks[to] {
+                let dummy = item_ranks.len();
+                item_ranks.push(rank);
+                item_cross_extents.push(0.);
+                item_positions.push(self.cross_positions[from]);
+                predecessors.push(Vec::new());
+                successors.push(Vec::new());
+
+                successors[previous].push(dummy);
+                predecessors[dummy].push(previous);
+                previous = dummy;
+            }
+            successors[previous].push(to);
+            predecessors[to].push(previous);
+        }
+
+        let layer_count = ranks.iter().max().copied().unwrap_or_default() + 1;
+        let mut layers: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+        for (item, rank) in item_ranks.iter().enumerate() {
+            layers[*rank].push(item);
+        }
+
+        // Seed the ordering with the current arrangement so re-applying a layout is stable.
+        for layer in layers.iter_mut() {
+            layer.sort_by(|a, b| item_positions[*a].total_cmp(&item_positions[*b]));
+        }
+
+        // Barycenter crossing reduction alternating down and up sweeps
+        let mut order = vec![0.; item_ranks.len()];
+        let update_order = |layers: &Vec<Vec<usize>>, order: &mut Vec<f64>| {
+            for layer in layers {
+                for (index, item) in layer.iter().enumerate() {
+                    order[*item] = index as f64;
+                }
+            }
+        };
+        update_order(&layers, &mut order);
+        for sweep in 0..ORDERING_SWEEPS {
+            let downward = sweep % 2 == 0;
+            let neighbors = if downward { &predecessors } else { &successors };
+            let layer_indices: Vec<usize> = if downward {
+                (1..layer_count).collect()
+            } else {
+                (0..layer_count.saturating_sub(1)).rev().collect()
+            };
+
+            for layer_index in layer_indices {
+                let layer = &mut layers[layer_index];
+                let barycenters: HashMap<usize, f64> = layer
+                    .iter()
+                    .map(|item| {
+                        let adjacent = &neighbors[*item];
+                        let barycenter = if adjacent.is_empty() {
+                            order[*item]
+                        } else {
+                            adjacent.iter().map(|other| order[*other]).sum::<f64>()
+                                / adjacent.len() as f64
+                        };
+                        (*item, barycenter)
+                    })
+                    .collect();
+                layer.sort_by(|a, b| barycenters[a].total_cmp(&barycenters[b]));
+                for (index, item) in layer.iter().enumerate() {
+                    order[*item] = index as f64;
+                }
+            }
+        }
+
+        // Respect cross axis hints between nodes which landed in the same layer
+        for &(from, to, after) in self.cross_edges.iter() {
+            if item_ranks[from] != item_ranks[to] {
+                continue;
+            }
+
+            let layer = &mut layers[item_ranks[from]];
+            let from_index = layer.iter().position(|item| *item == from).unwrap();
+            let to_index = layer.iter().position(|item| *item == to).unwrap();
+            if after != (to_index > from_index) {
+                layer.remove(to_index);
+                let from_index = layer.iter().position(|item| *item == from).unwrap();
+                layer.insert(if after { from_index + 1 } else { from_index }, to);
+            }
+        }
+
+        // Initial packing of each layer followed by a few passes pulling nodes toward the
+        // average position of their neighbors while keeping order and spacing.
+        let separation = |a: usize, b: usize| {
+            (item_cross_extents[a] + item_cross_extents[b]) / 2. + NODE_SPACING
+        };
+        let mut cross = vec![0.; item_ranks.len()];
+        for layer in layers.iter() {
+            let mut position = 0.;
+            for (index, item) in layer.iter().enumerate() {
+                if index > 0 {
+                    position += separation(layer[index - 1], *item);
+                }
+                cross[*item] = position;
+            }
+
+            let offset = position / 2.;
+            for item in layer {
+                cross[*item] -= offset;
+            }
+        }
+
+        for _ in 0..PLACEMENT_SWEEPS {
+            for layer in layers.iter() {
+                let desired: Vec<f64> = layer
+                    .iter()
+                    .map(|item| {
+                        let adjacent: Vec<usize> = predecessors[*item]
+                            .iter()
+                            .chain(successors[*item].iter())
+                            .copied()
+                            .collect();
+                        if adjacent.is_empty() {
+                            cross[*item]
+                        } else {
+                            adjacent.iter().map(|other| cross[*other]).sum::<f64>()
+                                / adjacent.len() as f64
+                        }
+                    })
+                    .collect();
+
+                // Average the leftmost and rightmost feasible placements. Both satisfy the
+                // spacing constraints so their average does as well.
+                let mut left = desired.clone();
+                for index in 1..layer.len() {
+                    left[index] = left[index]
+                        .max(left[index - 1] + separation(layer[index - 1], layer[index]));
+                }
+                let mut right = desired;
+                for index in (0..layer.len().saturating_sub(1)).rev() {
+                    right[index] = right[index]
+                        .min(right[index + 1] - separation(layer[index], layer[index + 1]));
+                }
+                for (index, item) in layer.iter().enumerate() {
+                    cross[*item] = (left[index] + right[index]) / 2.;
+                }
+            }
+        }
+
+        let mut layer_flow_positions = Vec::with_capacity(layer_count);
+        let mut flow_position = 0.;
+        for layer in layers.iter() {
+            let extent = layer
+                .iter()
+                .filter(|item| **item < self.len())
+                .map(|item| self.flow_extents[*item])
+                .fold(0., f64::max);
+            layer_flow_positions.push(flow_position + extent / 2.);
+            flow_position += extent + LAYER_SPACING;
+        }
+
+        (0..self.len())
+            .map(|node| self.to_point(cross[node], layer_flow_positions[ranks[node]]))
+            .collect()
+    }
+
+    fn tree(&self) -> Vec<Point> {
+        let flow_edges = self.acyclic_flow_edges();
+
+        let mut successors = vec![Vec::new(); self.len()];
+        let mut has_parent = vec![false; self.len()];
+        for &(from, to) in flow_edges.iter() {
+            successors[from].push(to);
+            has_parent[to] = true;
+        }
+
+        // Breadth first spanning forest so that every node has a single parent.
+        let mut parents: Vec<Option<usize>> = vec![None; self.len()];
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); self.len()];
+        let mut visited = vec![false; self.len()];
+        let mut roots = Vec::new();
+        // Start from true roots, then nodes placed beside another through a cross axis hint, then
+        // whatever is left inside of cycles.
+        let is_cross_target = |node: usize| self.cross_edges.iter().any(|(_, to, _)| *to == node);
+        let starts = (0..self.len())
+            .filter(|node| !has_parent[*node] && !is_cross_target(*node))
+            .chain((0..self.len()).filter(|node| !has_parent[*node]))
+            .chain(0..self.len())
+            .collect::<Vec<_>>();
+        for start in starts {
+            if visited[start] {
+                continue;
+            }
+
+            // Nodes only connected through a cross axis hint sit beside their partner instead
+            // of starting a tree of their own.
+            if let Some(&(from, _, after)) = self
+                .cross_edges
+                .iter()
+                .find(|(from, to, _)| *to == start && visited[*from])
+            {
+                visited[start] = true;
+                let siblings = match parents[from] {
+                    Some(parent) => &mut children[parent],
+                    None => &mut roots,
+                };
+                let index = siblings.iter().position(|node| *node == from).unwrap();
+                siblings.insert(if after { index + 1 } else { index }, start);
+                parents[start] = parents[from];
+            } else {
+                visited[start] = true;
+                roots.push(start);
+            }
+
+            let mut queue = VecDeque::from([start]);
+            while let Some(node) = queue.pop_front() {
+                let mut discovered = Vec::new();
+                for successor in successors[node].iter() {
+                    if !visited[*successor] {
+                        visited[*successor] = true;
+                        parents[*successor] = Some(node);
+                        discovered.push(*successor);
+                        queue.push_back(*successor);
+                    }
+                }
+                discovered
+                    .sort_by(|a, b| self.cross_positions[*a].total_cmp(&self.cross_positions[*b]));
+                children[node].extend(discovered);
+            }
+        }
+
+        let mut depths = vec![0; self.len()];
+        let mut cross = vec![0.; self.len()];
+        let mut forest = Subtree::default();
+        for root in roots.iter() {
+            let subtree = self.subtree(*root, 0, &children, &mut depths);
+            forest.append(subtree);
+        }
+        forest.center();
+        for (node, offset) in forest.offsets {
+            cross[node] = offset;
+        }
+
+        let depth_count = depths.iter().max().copied().unwrap_or_default() + 1;
+        let mut depth_extents = vec![0.; depth_count];
+        for node in 0..self.len() {
+            depth_extents[depths[node]] =
+                f64::max(depth_extents[depths[node]], self.flow_extents[node]);
+        }
+        let mut depth_positions = Vec::with_capacity(depth_count);
+        let mut flow_position = 0.;
+        for extent in depth_extents {
+            depth_positions.push(flow_position + extent / 2.);
+            flow_position += extent + LAYER_SPACING;
+        }
+
+        (0..self.len())
+            .map(|node| self.to_point(cross[node], depth_positions[depths[node]]))
+            .collect()
+    }
+
+    fn subtree(
+        &self,
+        node: usize,
+        depth: usize,
+        children: &Vec<Vec<usize>>,
+        depths: &mut Vec<usize>,
+    ) -> Subtree {
+        depths[node] = depth;
+
+        let mut combined = Subtree::default();
+        for child in children[node].iter() {
+            let subtree = self.subtree(*child, depth + 1, children, depths);
+            combined.append(subtree);
+        }
+        combined.center();
+
+        let half_extent = self.cross_extents[node] / 2.;
+        let mut contour = vec![(-half_extent, half_extent)];
+        contour.extend(combined.contour);
+        let mut offsets = vec![(node, 0.)];
+        offsets.extend(combined.offsets);
+
+        Subtree { contour, offsets }
+    }
+}
+
+// A laid out subtree relative to its root. The contour stores the leftmost and rightmost
+// extent of the subtree at each depth below the root.
+#[derive(Default)]
+struct Subtree {
+    contour: Vec<(f64, f64)>,
+    offsets: Vec<(usize, f64)>,
+}
+
+impl Subtree {
+    // Places the other subtree as close to the right of this one as the contours allow.
+    fn append(&mut self, mut other: Subtree) {
+        if self.offsets.is_empty() {
+            *self = other;
+            return;
+        }
+
+        let shift = self
+            .contour
+            .iter()
+            .zip(other.contour.iter())
+            .map(|((_, right), (left, _))| right - left + NODE_SPACING)
+            .fold(f64::MIN, f64::max);
+
+        for (_, offset) in other.offsets.iter_mut() {
+            *offset += shift;
+        }
+        for (depth, (left, right)) in other.contour.into_iter().enumerate() {
+            if let Some((_, existing_right)) = self.contour.get_mut(depth) {
+                *existing_right = right + shift;
+            } else {
+                self.contour.push((left + shift, right + shift));
+            }
+        }
+        self.offsets.extend(other.offsets);
+    }
+
+    // Centers the top level of the subtree around zero.
+    fn center(&mut self) {
+        let Some((left, right)) = self.contour.first().copied() else {
+            return;
+        };
+
+        let middle = (left + right) / 2.;
+        for (_, offset) in self.offsets.iter_mut() {
+            *offset -= middle;
+        }
+        for (left, right) in self.contour.iter_mut() {
+            *left -= middle;
+            *right -= middle;
+        }
+    }
+}
+
+fn force_directed(nodes: &[LayoutNode], graph: &FlowGraph) -> Vec<Point> {
+    let count = nodes.len();
+    let mut positions: Vec<Vec2> = nodes.iter().map(|node| node.center.to_vec2()).collect();
+
+    // Spread out nodes which share a position so that forces have a direction to act along.
+    for index in 0..count {
+        for other in 0..index {
+            if (positions[index] - positions[other]).hypot2() < 1. {
+                let angle = index as f64 * 2.399963;
+                positions[index] += Vec2::from_angle(angle) * (index as f64 + 1.) * NODE_SPACING;
+                break;
+            }
+        }
+    }
+
+    let average_diagonal = nodes
+        .iter()
+        .map(|node| node.size.to_vec2().hypot())
+        .sum::<f64>()
+        / count as f64;
+    let ideal_length = average_diagonal + NODE_SPACING;
+
+    let hints = hinted_offsets(nodes, graph, ideal_length);
+
+    let mut temperature = ideal_length;
+    let cooling = temperature / FORCE_ITERATIONS as f64;
+    for _ in 0..FORCE_ITERATIONS {
+        let mut displacements = vec![Vec2::ZERO; count];
+
+        for index in 0..count {
+            for other in index + 1..count {
+                let delta = positions[index] - positions[other];
+                let distance = delta.hypot().max(1.);
+                let force = ideal_length * ideal_length / distance;
+                let push = delta / distance * force;
+                displacements[index] += push;
+                displacements[other] -= push;
+            }
+        }
+
+        for &(from, to) in graph.flow_edges.iter() {
+            let delta = positions[to] - positions[from];
+            let distance = delta.hypot().max(1.);
+            let pull = delta / distance * (distance * distance / ideal_length);
+            displacements[from] += pull;
+            displacements[to] -= pull;
+        }
+
+        // Hinted connections additionally spring toward their preferred relative offset.
+        for &(from, to, offset) in hints.iter() {
+            let error = positions[from] + offset - positions[to];
+            displacements[to] += error;
+            displacements[from] -= error;
+        }
+
+        for (position, displacement) in positions.iter_mut().zip(displacements) {
+            let length = displacement.hypot();
+            if length > 0. {
+                *position += displacement / length * length.min(temperature);
+            }
+        }
+
+        temperature = (temperature - cooling).max(1.);
+    }
+
+    // Springs only approximate spacing, so push apart any nodes which still overlap.
+    for _ in 0..OVERLAP_PASSES {
+        let mut moved = false;
+        for index in 0..count {
+            for other in index + 1..count {
+                let delta = positions[other] - positions[index];
+                let overlap_x = (nodes[index].size.width + nodes[other].size.width) / 2.
+                    + NODE_SPACING / 2.
+                    - delta.x.abs();
+                let overlap_y = (nodes[index].size.height + nodes[other].size.height) / 2.
+                    + NODE_SPACING / 2.
+                    - delta.y.abs();
+                if overlap_x <= 0. || overlap_y <= 0. {
+                    continue;
+                }
+
+                let push = if overlap_x < overlap_y {
+                    Vec2::new(overlap_x.copysign(delta.x) / 2., 0.)
+                } else {
+                    Vec2::new(0., overlap_y.copysign(delta.y) / 2.)
+                };
+                positions[index] -= push;
+                positions[other] += push;
+                moved = true;
+            }
+        }
+
+        if !moved {
+            break;
+        }
+    }
+
+    positions
+        .into_iter()
+        .map(|position| position.to_point())
+        .collect()
+}
+
+// Preferred offsets from source to target for every connection with a direction hint.
+fn hinted_offsets(
+    nodes: &[LayoutNode],
+    graph: &FlowGraph,
+    ideal_length: f64,
+) -> Vec<(usize, usize, Vec2)> {
+    let offset = |from: usize, to: usize, direction: Direction| {
+        let half = (nodes[from].size.to_vec2() + nodes[to].size.to_vec2()) / 2.;
+        match direction {
+            Direction::Above => Vec2::new(0., -(half.y + ideal_length)),
+            Direction::Below => Vec2::new(0., half.y + ideal_length),
+            Direction::Before => Vec2::new(-(half.x + ideal_length), 0.),
+            Direction::After => Vec2::new(half.x + ideal_length, 0.),
+        }
+    };
+
+    // Flow edges were already normalized to point forward along the flow axis.
+    let (forward, cross_forward, cross_backward) = if graph.horizontal {
+        (Direction::After, Direction::Below, Direction::Above)
+    } else {
+        (Direction::Below, Direction::After, Direction::Before)
+    };
+
+    graph
+        .flow_edges
+        .iter()
+        .zip(graph.flow_hinted.iter())
+        .filter(|(_, hinted)| **hinted)
+        .map(|(&(from, to), _)| (from, to, offset(from, to, forward)))
+        .chain(graph.cross_edges.iter().map(|&(from, to, after)| {
+            let direction = if after { cross_forward } else { cross_backward };
+            (from, to, offset(from, to, direction))
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::Rect;
+
+    use super::*;
+
+    fn node(center: Point) -> LayoutNode {
+        LayoutNode {
+            token: Token::new::<LayoutNode>(),
+            center,
+            size: Size::new(100., 40.),
+        }
+    }
+
+    #[test]
+    fn layered_layout_respects_direction_hints() {
+        let nodes = [node(Point::ZERO), node(Point::ZERO), node(Point::ZERO)];
+        let connections = [
+            Connection::new(nodes[0].token, nodes[1].token, Some(Direction::Below)),
+            Connection::new(nodes[0].token, nodes[2].token, Some(Direction::Above)),
+        ];
+
+        let centers = GraphLayout::Layered.compute(&nodes, &connections);
+        assert!(centers[&nodes[1].token].y > centers[&nodes[0].token].y);
+        assert!(centers[&nodes[2].token].y < centers[&nodes[0].token].y);
+    }
+
+    #[test]
+    fn layouts_do_not_overlap_nodes() {
+        let nodes: Vec<LayoutNode> = (0..6).map(|_| node(Point::ZERO)).collect();
+        let connections: Vec<Connection> = (1..nodes.len())
+            .map(|index| Connection::new(nodes[(index - 1) / 2].token, nodes[index].token, None))
+            .collect();
+
+        for graph_layout in [
+            GraphLayout::Layered,
+            GraphLayout::ForceDirected,
+            GraphLayout::Tree,
+        ] {
+            let centers = graph_layout.compute(&nodes, &connections);
+            for (index, a) in nodes.iter().enumerate() {
+                for b in nodes.iter().skip(index + 1) {
+                    let a_rect = Rect::from_center_size(centers[&a.token], a.size);
+                    let b_rect = Rect::from_center_size(centers[&b.token], b.size);
+                    assert!(
+                        !a_rect.overlaps(b_rect),
+                        "{:?} overlapped nodes {:?} and {:?}",
+                        graph_layout,
+                        a_rect,
+                        b_rect
+                    );
+                }
+            }
+        }
+    }
+}
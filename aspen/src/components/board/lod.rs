@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use parley::{style::StyleProperty, Layout};
+use vello::{
+    kurbo::{Affine, Circle, Point, Rect, Shape, Vec2},
+    peniko::Brush,
+};
+
+use super::{Board, BoardState, CameraMove};
+use crate::{context_stack::DrawContext, theme::ThemeColor, token::Token};
+
+const CARD_RADIUS: f64 = 4.;
+// Fraction of a card's width its title may take up
+const CARD_TITLE_WIDTH: f64 = 0.85;
+const TITLE_FONT_SIZE: f32 = 16.;
+// Screen space size of the cells pins are grouped into when clustered
+const CLUSTER_CELL_SIZE: f64 = 48.;
+const BADGE_RADIUS: f64 = 12.;
+
+/// How much of a pin is drawn, picked from how far the pin is scaled down on screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetailLevel {
+    Full,
+    // Rounded rectangle showing only the pin's title
+    Card,
+    // Plain colored rectangle
+    Block,
+    // Grouped with nearby pins into a single badge showing how many pins it covers
+    Clustered,
+}
+
+/// Scales below which pins are drawn with less detail. A scale is the size a pin is drawn at on
+/// screen relative to its own size, so a pin at half a board's zoom is drawn at 0.5.
+#[derive(Clone, Debug)]
+pub struct LevelOfDetail {
+    pub card_below: f64,
+    pub block_below: f64,
+    pub cluster_below: f64,
+    pub fill: ThemeColor,
+    pub text: ThemeColor,
+}
+
+impl Default for LevelOfDetail {
+    fn default() -> Self {
+        Self {
+            card_below: 0.5,
+            block_below: 0.2,
+            cluster_below: 0.05,
+            fill: ThemeColor::Background(5),
+            text: ThemeColor::Foreground,
+        }
+    }
+}
+
+impl LevelOfDetail {
+    pub fn level(&self, scale: f64) -> DetailLevel {
+        if scale < self.cluster_below {
+            DetailLevel::Clustered
+        } else if scale < self.block_below {
+            DetailLevel::Block
+        } else if scale < self.card_below {
+            DetailLevel::Card
+        } else {
+            DetailLevel::Full
+        }
+    }
+}
+
+impl Board {
+    fn title_layout(&self, text: &str, cx: &DrawContext) -> Layout<Brush> {
+        cx.shaper.borrow_mut().layout(
+            text,
+            &vec![
+                StyleProperty::FontSize(TITLE_FONT_SIZE),
+                StyleProperty::Brush(Brush::Solid(self.level_of_detail.text.resolve(&cx.theme()))),
+            ],
+        )
+    }
+
+    // Draws the layout centered in the rect, scaled down to fit if needed.
+    fn draw_fitted_layout(&self, layout: &Layout<Brush>, rect: Rect, cx: &mut DrawContext) {
+        let (width, height) = (layout.full_width() as f64, layout.height() as f64);
+        if width <= 0. || height <= 0. {
+            return;
+        }
+
+        let scale = (rect.width() * CARD_TITLE_WIDTH / width)
+            .min(rect.height() * CARD_TITLE_WIDTH / height)
+            .min(1.);
+        let offset = rect.center().to_vec2() - Vec2::new(width, height) * scale / 2.;
+        let transform = Affine::translate(offset) * Affine::scale(scale);
+        cx.transform(transform);
+        cx.draw_layout_at(layout, Point::ZERO);
+        cx.transform(transform.inverse());
+    }
+
+    pub(super) fn draw_card(&self, title: Option<&str>, rect: Rect, cx: &mut DrawContext) {
+        cx.set_fill_brush(Brush::Solid(self.level_of_detail.fill.resolve(&cx.theme())));
+        cx.fill(&rect.to_rounded_rect(CARD_RADIUS));
+
+        if let Some(title) = title {
+            let layout = self.title_layout(title, cx);
+            self.draw_fitted_layout(&layout, rect, cx);
+        }
+    }
+
+    pub(super) fn draw_block(&self, rect: Rect, cx: &mut DrawContext) {
+        cx.set_fill_brush(Brush::Solid(self.level_of_detail.fill.resolve(&cx.theme())));
+        cx.fill(&rect);
+    }
+
+    // Groups pins by screen space cell and draws a count badge for each cell holding several.
+    // Clicking a badge frames the pins it covers.
+    pub(super) fn draw_clusters(&self, pins: &[(Token, Rect)], zoom: f64, cx: &mut DrawContext) {
+        let cell_size = CLUSTER_CELL_SIZE / zoom;
+        let mut cells: HashMap<(i64, i64), Vec<(Token, Rect)>> = HashMap::new();
+        for (token, rect) in pins.iter() {
+            let center = rect.center();
+            let cell = (
+                (center.x / cell_size).floor() as i64,
+                (center.y / cell_size).floor() as i64,
+            );
+            cells.entry(cell).or_default().push((*token, *rect));
+        }
+
+        for cell in cells.into_values() {
+            if let [(_, rect)] = cell.as_slice() {
+                self.draw_block(*rect, cx);
+                continue;
+            }
+
+            let centroid = cell
+                .iter()
+                .fold(Vec2::ZERO, |sum, (_, rect)| sum + rect.center().to_vec2())
+                / cell.len() as f64;
+            let badge = Circle::new(centroid.to_point(), BADGE_RADIUS / zoom);
+
+            let tokens: Vec<Token> = cell.iter().map(|(token, _)| *token).collect();
+            cx.mouse_region(badge.bounding_box()).on_down(move |cx| {
+                cx.with_state(|state: &mut BoardState, _| {
+                    state.selection = tokens.iter().copied().collect();
+                    state.pending_camera_move = Some(CameraMove::FitSelection);
+                });
+                cx.focus();
+            });
+
+            cx.set_fill_brush(Brush::Solid(self.level_of_detail.fill.resolve(&cx.theme())));
+            cx.fill(&badge);
+            let layout = self.title_layout(&cell.len().to_string(), cx);
+            self.draw_fitted_layout(&layout, badge.bounding_box(), cx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashSet, rc::Rc, time::Duration};
+
+    use vello::{kurbo::Size, peniko::Color, Scene};
+    use winit::window::{Cursor, CursorIcon};
+
+    use super::*;
+    use crate::{
+        components::board::{Blank, Camera, PinWrapper},
+        context_stack::LayoutContext,
+        element::{Element, ElementPointer},
+        test_runner::TestRunner,
+    };
+
+    const FILL: Color = Color::from_rgb8(255, 0, 255);
+
+    struct Empty;
+
+    impl Element for Empty {
+        fn layout(&mut self, min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+            min
+        }
+    }
+
+    #[test]
+    fn levels_follow_the_scale_pins_are_drawn_at() {
+        let level_of_detail = LevelOfDetail::default();
+        assert_eq!(level_of_detail.level(2.), DetailLevel::Full);
+        assert_eq!(level_of_detail.level(0.5), DetailLevel::Full);
+        assert_eq!(level_of_detail.level(0.3), DetailLevel::Card);
+        assert_eq!(level_of_detail.level(0.1), DetailLevel::Block);
+        assert_eq!(level_of_detail.level(0.01), DetailLevel::Clustered);
+    }
+
+    #[test]
+    fn cards_show_the_current_title_once_zoomed_out() {
+        let title = Rc::new(RefCell::new("First".to_string()));
+        let shown = Rc::new(RefCell::new(Vec::new()));
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            let (title, shown) = (title.clone(), shown.clone());
+            let pin = PinWrapper::new_sized(
+                Point::ZERO,
+                Size::new(200., 100.),
+                ElementPointer::new(Empty),
+                cx,
+            );
+            board.add_child(PinWrapper::with_title_from(pin, move |_, _| {
+                let title = title.borrow().clone();
+                shown.borrow_mut().push(title.clone());
+                title
+            }));
+            board
+        });
+        let draw_at_zoom = |test_runner: &mut TestRunner<Board>, zoom: f64| {
+            test_runner.with_root(|_, cx| {
+                cx.with_state(|state: &mut BoardState, _| state.transform = Affine::scale(zoom))
+            });
+            let child_lookup = test_runner.layout();
+            test_runner.draw(child_lookup);
+        };
+
+        // Large pins and small pins switch at the same zoom
+        draw_at_zoom(&mut test_runner, 0.6);
+        assert!(shown.borrow().is_empty());
+
+        draw_at_zoom(&mut test_runner, 0.3);
+        *title.borrow_mut() = "Second".to_string();
+        draw_at_zoom(&mut test_runner, 0.35);
+        assert_eq!(*shown.borrow(), vec!["First", "Second"]);
+
+        draw_at_zoom(&mut test_runner, 0.1);
+        assert_eq!(shown.borrow().len(), 2);
+    }
+
+    // Board zoomed out far enough to cluster its 40 by 40 pins, drawn into a 400 by 400 window
+    // and filling them with `FILL`.
+    fn clustered_board(centers: &[Point]) -> (TestRunner<Board>, Vec<Token>, Scene) {
+        let mut pins = Vec::new();
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let board = Board::new(Affine::scale(0.03), Blank::new(Color::BLACK), cx);
+            let mut board = Board::with_level_of_detail(
+                board,
+                LevelOfDetail {
+                    fill: FILL.into(),
+                    ..LevelOfDetail::default()
+                },
+            );
+            for center in centers {
+                let pin = PinWrapper::new_sized(
+                    *center,
+                    Size::new(40., 40.),
+                    ElementPointer::new(Empty),
+                    cx,
+                );
+                pins.push(board.add_child(pin));
+            }
+            board
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        let scene = test_runner.draw(child_lookup);
+        (test_runner, pins, scene)
+    }
+
+    // Window space bounds of the count badges, told apart from other regions by their size.
+    fn badges(test_runner: &TestRunner<Board>) -> Vec<Rect> {
+        let manager = test_runner.mouse_region_manager.borrow();
+        manager
+            .mouse_regions
+            .iter()
+            .map(|region| region.region.bounding_box())
+            .filter(|bounds| (bounds.width() - BADGE_RADIUS * 2.).abs() < 1e-6)
+            .collect()
+    }
+
+    fn draws_color(scene: &Scene, color: Color) -> bool {
+        let rgba = color.premultiply().to_rgba8().to_u32();
+        scene.encoding().draw_data.contains(&rgba)
+    }
+
+    #[test]
+    fn nearby_pins_share_badges_and_lone_pins_are_blocks() {
+        // The first two pins fall in the same 1600 wide cell at a zoom of 0.03
+        let (test_runner, _, _) = clustered_board(&[
+            Point::new(100., 100.),
+            Point::new(300., 300.),
+            Point::new(3000., 3000.),
+        ]);
+        let shown = badges(&test_runner);
+        assert_eq!(shown.len(), 1);
+        assert!((shown[0].center() - Point::new(206., 206.)).hypot() < 1e-6);
+
+        let (test_runner, _, scene) = clustered_board(&[Point::ZERO]);
+        assert!(badges(&test_runner).is_empty());
+        assert!(draws_color(&scene, FILL));
+    }
+
+    #[test]
+    fn clicking_badges_selects_and_frames_their_pins() {
+        let (mut test_runner, pins, _) =
+            clustered_board(&[Point::new(100., 100.), Point::new(300., 300.)]);
+
+        test_runner.move_mouse(Point::new(206., 206.));
+        test_runner.press_mouse();
+        test_runner.release_mouse();
+        test_runner.advance_time(Duration::from_secs(1));
+
+        test_runner.with_root(|_, cx| {
+            let (selection, camera) = cx.read_state(|state: &BoardState, _| {
+                (
+                    state.selection.clone(),
+                    Camera::from_transform(state.transform),
+                )
+            });
+            assert_eq!(selection, pins.iter().copied().collect::<HashSet<_>>());
+            // The pins span 240 across and the window leaves 320 within its padding
+            assert!((camera.center - Point::new(200., 200.)).hypot() < 1e-6);
+            assert!((camera.zoom - 320. / 240.).abs() < 1e-6);
+        });
+    }
+}
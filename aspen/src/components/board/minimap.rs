@@ -0,0 +1,208 @@
+use vello::{
+    kurbo::{Affine, Point, Rect, Size, Stroke},
+    peniko::Brush,
+};
+
+use crate::{
+    context_stack::{DrawContext, EventContext, LayoutContext},
+    element::{Element, ElementPointer},
+    theme::ThemeColor,
+};
+
+const PADDING: f64 = 8.;
+const RADIUS: f64 = 5.;
+
+/// Overview of a board's pins and the currently visible part of it. Owned by the board it
+/// summarizes, which keeps the pin and viewport rectangles up to date during layout.
+pub struct Minimap {
+    size: Size,
+    background: ThemeColor,
+    pin_fill: ThemeColor,
+    viewport_stroke: ThemeColor,
+
+    // Board space rectangles recorded by the owning board
+    pub(crate) pins: Vec<Rect>,
+    pub(crate) viewport: Rect,
+}
+
+#[derive(Default)]
+pub struct MinimapState {
+    // Board space point the owning board should center on
+    pub(crate) requested_center: Option<Point>,
+}
+
+impl Minimap {
+    pub fn new(
+        size: Size,
+        background: impl Into<ThemeColor>,
+        pin_fill: impl Into<ThemeColor>,
+        viewport_stroke: impl Into<ThemeColor>,
+    ) -> ElementPointer<Self> {
+        ElementPointer::new(Self {
+            size,
+            background: background.into(),
+            pin_fill: pin_fill.into(),
+            viewport_stroke: viewport_stroke.into(),
+
+            pins: Vec::new(),
+            viewport: Rect::ZERO,
+        })
+    }
+
+    // Maps board space into the minimap's region, fitting every pin and the viewport.
+    fn board_to_minimap(&self, region: Rect) -> Affine {
+        let bounds = self
+            .pins
+            .iter()
+            .fold(self.viewport, |bounds, pin| bounds.union(*pin));
+        let available = region.inset(-PADDING);
+        if bounds.width() <= 0. || bounds.height() <= 0. || available.area() <= 0. {
+            return Affine::translate(region.center().to_vec2());
+        }
+
+        let scale = (available.width() / bounds.width()).min(available.height() / bounds.height());
+        Affine::translate(available.center().to_vec2())
+            * Affine::scale(scale)
+            * Affine::translate(-bounds.center().to_vec2())
+    }
+}
+
+impl Element for Minimap {
+    fn layout(&mut self, _min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+        self.size
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        let region = cx.region();
+        let board_to_minimap = self.board_to_minimap(region);
+        let minimap_to_board = board_to_minimap.inverse();
+
+        let recenter = move |cx: &mut EventContext| {
+            if let Some(position) = cx.mouse_position() {
+                cx.with_state(|state: &mut MinimapState, _| {
+                    state.requested_center = Some(minimap_to_board * position);
+                });
+                cx.request_redraw();
+            }
+        };
+        cx.mouse_region(region).on_down(recenter).on_drag(recenter);
+
+        let theme = cx.theme();
+        let shape = region.to_rounded_rect(RADIUS);
+        cx.set_fill_brush(Brush::Solid(self.background.resolve(&theme)));
+        cx.fill(&shape);
+
+        cx.push_layer(&shape);
+        cx.set_fill_brush(Brush::Solid(self.pin_fill.resolve(&theme)));
+        for pin in self.pins.iter() {
+            cx.fill(&board_to_minimap.transform_rect_bbox(*pin));
+        }
+
+        cx.set_stroke_style(Stroke::new(1.));
+        cx.set_stroke_brush(Brush::Solid(self.viewport_stroke.resolve(&theme)));
+        cx.stroke(&board_to_minimap.transform_rect_bbox(self.viewport));
+        cx.pop_layer();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::peniko::Color;
+    use winit::window::{Cursor, CursorIcon};
+
+    use super::*;
+    use crate::{
+        components::{
+            board::{Blank, Board, BoardState, Camera, PinWrapper},
+            layout::SizedBox,
+        },
+        context_stack::Context,
+        test_runner::TestRunner,
+        token::Token,
+    };
+
+    fn board_with_minimap(centers: &[Point], cx: &Context) -> ElementPointer<Board> {
+        let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+        for center in centers {
+            board.add_child(PinWrapper::new(
+                *center,
+                SizedBox::new(Size::new(40., 40.)),
+                cx,
+            ));
+        }
+        let minimap = Minimap::new(
+            Size::new(80., 80.),
+            Color::BLACK,
+            Color::WHITE,
+            Color::WHITE,
+        );
+        Board::with_minimap(board, minimap)
+    }
+
+    #[test]
+    fn minimaps_show_pins_and_the_viewport() {
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            board_with_minimap(&[Point::new(-300., 0.), Point::new(300., 0.)], cx)
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        test_runner.layout();
+
+        test_runner.with_root(|board, _| {
+            let minimap = board.minimap.as_ref().unwrap();
+            assert_eq!(minimap.viewport, Rect::new(-200., -200., 200., 200.));
+            let mut pins = minimap.pins.clone();
+            pins.sort_by(|a, b| a.x0.total_cmp(&b.x0));
+            assert_eq!(
+                pins,
+                vec![
+                    Rect::new(-320., -20., -280., 20.),
+                    Rect::new(280., -20., 320., 20.)
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn clicking_minimaps_recenters_nested_boards() {
+        let mut nested = None;
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let inner = board_with_minimap(&[Point::new(500., 500.)], cx);
+            let pin = PinWrapper::new_sized(Point::ZERO, Size::new(300., 300.), inner, cx);
+            nested = Some(pin.token());
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            board.add_child(pin);
+            board
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        let nested: Token = nested.unwrap();
+
+        // The nested board covers the window from 50 to 350, leaving its minimap in the bottom
+        // right corner from 258 to 338
+        let minimap_region = Rect::new(258., 258., 338., 338.);
+        let target = test_runner.with_root(|board, _| {
+            let minimap = board.board_at(&[nested]).unwrap().minimap.as_ref().unwrap();
+            minimap.board_to_minimap(minimap_region) * Point::new(500., 500.)
+        });
+        test_runner.move_mouse(target);
+        test_runner.press_mouse();
+        test_runner.release_mouse();
+
+        test_runner.with_root(|board, cx| {
+            let pin = board
+                .children
+                .iter()
+                .find(|pin| pin.token() == nested)
+                .unwrap();
+            let inner = pin.nested_board().unwrap();
+            let camera = inner.read_state(cx, |state: &BoardState, _| {
+                Camera::from_transform(state.transform)
+            });
+            assert!(
+                (camera.center - Point::new(500., 500.)).hypot() < 1e-6,
+                "{camera:?}"
+            );
+        });
+    }
+}
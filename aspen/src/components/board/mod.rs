@@ -0,0 +1,1030 @@
+pub mod annotations;
+pub mod arrange;
+pub mod background;
+pub mod camera;
+pub mod commands;
+mod coordinates;
+pub mod graph;
+pub mod graph_layout;
+pub mod lod;
+pub mod minimap;
+pub mod navigation;
+pub mod snapping;
+pub mod subgraph;
+mod transfer;
+
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    ops::Deref,
+    rc::Rc,
+    time::Instant,
+};
+
+use vello::{
+    kurbo::{Affine, BezPath, Line, Point, Rect, Shape, Size, Stroke, Vec2},
+    peniko::Brush,
+};
+use winit::keyboard::{Key, NamedKey};
+
+pub use annotations::*;
+pub use arrange::*;
+pub use background::*;
+pub use camera::*;
+pub use commands::*;
+pub use graph::*;
+pub use graph_layout::*;
+pub use lod::*;
+pub use minimap::*;
+pub use navigation::*;
+pub use snapping::*;
+pub use subgraph::*;
+use transfer::PinDrop;
+
+use crate::{
+    animation::Tween,
+    context_stack::{Context, DrawContext, LayoutContext, UpdateContext},
+    element::{Element, ElementPointer},
+    history::History,
+    spatial::SpatialGrid,
+    theme::ThemeColor,
+    token::Token,
+    util::*,
+};
+
+const LAYOUT_ANIM_LENGTH: f64 = 0.35;
+const WIRE_THICKNESS: f64 = 2.;
+const MINIMAP_MARGIN: f64 = 12.;
+const SELECTION_THICKNESS: f64 = 2.;
+const SELECTION_OFFSET: f64 = 4.;
+// Board space size of the cells pins are bucketed into for culling
+const PIN_INDEX_CELL_SIZE: f64 = 256.;
+
+pub trait Pinnable: Element {
+    fn center(&self, cx: &Context) -> Point;
+    fn set_center(&self, center: Point, cx: &Context);
+
+    // Text shown in place of the pin when it is too small on screen to be drawn fully.
+    fn title(&self, _cx: &Context) -> Option<String> {
+        None
+    }
+
+    // Board shown inside the pin, which pins can be dragged into and out of.
+    fn nested_board(&self) -> Option<&ElementPointer<Board>> {
+        None
+    }
+
+    fn nested_board_mut(&mut self) -> Option<&mut ElementPointer<Board>> {
+        None
+    }
+}
+
+// Pin as stored on a board, whatever element it holds.
+type PinPointer = ElementPointer<Box<dyn Pinnable>>;
+// Reads a pin's title from the element it holds each time the title is shown.
+type TitleSource<Child> = Rc<dyn Fn(&ElementPointer<Child>, &Context) -> String>;
+
+pub struct Board {
+    background: Rc<dyn Background>,
+    children: Vec<PinPointer>,
+    connections: Vec<Connection>,
+    // Wires leaving this board when it holds a subgraph
+    ports: Vec<Port>,
+    layout_data: LayoutData,
+    wire_color: ThemeColor,
+    selection_color: ThemeColor,
+    minimap: Option<ElementPointer<Minimap>>,
+    rulers: Option<Rulers>,
+    grid_snap: GridSnap,
+    smart_guides: bool,
+    level_of_detail: LevelOfDetail,
+    navigation: Navigation,
+
+    // Board space rects of the pins recorded during layout, indexed by position in children
+    pin_rects: HashMap<Token, Rect>,
+    pin_index: SpatialGrid<usize>,
+    // Board space positions of the ports of the subgraphs on this board
+    port_positions: HashMap<Token, Point>,
+}
+
+#[derive(Default)]
+pub struct BoardState {
+    transform: Affine,
+    selection: HashSet<Token>,
+    pending_graph_layout: Option<GraphLayout>,
+    pin_animation: Option<PinAnimation>,
+    pending_camera_move: Option<CameraMove>,
+    camera_animation: Option<Tween<Camera>>,
+    pin_drag: Option<PinDrag>,
+    guides: Vec<Line>,
+    pending_arrange: Option<Arrange>,
+    history: History<Board>,
+    pin_drop: Option<PinDrop>,
+    annotation_drag: Option<AnnotationDrag>,
+    drop_highlight: bool,
+    space_held: bool,
+    camera_motion: CameraMotion,
+    // Board space to window transform and window space region from the last draw
+    screen_transform: Affine,
+    screen_region: Rect,
+}
+
+// Eases pins from their current centers to the centers computed by a graph layout.
+struct PinAnimation {
+    start: Instant,
+    from: HashMap<Token, Point>,
+    to: HashMap<Token, Point>,
+}
+
+impl Board {
+    pub fn new<'a>(
+        transform: Affine,
+        background: impl Background + 'static,
+        cx: &Context<'a>,
+    ) -> ElementPointer<Self> {
+        ElementPointer::new(Self {
+            background: Rc::new(background),
+            children: Vec::new(),
+            connections: Vec::new(),
+            ports: Vec::new(),
+            layout_data: LayoutData::default(),
+            wire_color: ThemeColor::Muted,
+            selection_color: ThemeColor::Accent,
+            minimap: None,
+            rulers: None,
+            grid_snap: GridSnap::Off,
+            smart_guides: false,
+            level_of_detail: LevelOfDetail::default(),
+            navigation: Navigation::default(),
+
+            pin_rects: HashMap::new(),
+            pin_index: SpatialGrid::new(PIN_INDEX_CELL_SIZE),
+            port_positions: HashMap::new(),
+        })
+        .insert_state(
+            BoardState {
+                transform,
+                ..Default::default()
+            },
+            cx,
+        )
+    }
+
+    pub fn with_wire_color(
+        mut this: ElementPointer<Self>,
+        wire_color: impl Into<ThemeColor>,
+    ) -> ElementPointer<Self> {
+        this.wire_color = wire_color.into();
+        this
+    }
+
+    pub fn with_selection_color(
+        mut this: ElementPointer<Self>,
+        selection_color: impl Into<ThemeColor>,
+    ) -> ElementPointer<Self> {
+        this.selection_color = selection_color.into();
+        this
+    }
+
+    // Snaps dragged pins to the finest visible grid level. Only boards with a grid background
+    // have anything to snap to.
+    pub fn with_grid_snap(
+        mut this: ElementPointer<Self>,
+        grid_snap: GridSnap,
+    ) -> ElementPointer<Self> {
+        this.grid_snap = grid_snap;
+        this
+    }
+
+    // Shows guides and snaps dragged pins when their edges or centers line up with another pin.
+    pub fn with_smart_guides(
+        mut this: ElementPointer<Self>,
+        smart_guides: bool,
+    ) -> ElementPointer<Self> {
+        this.smart_guides = smart_guides;
+        this
+    }
+
+    pub fn with_level_of_detail(
+        mut this: ElementPointer<Self>,
+        level_of_detail: LevelOfDetail,
+    ) -> ElementPointer<Self> {
+        this.level_of_detail = level_of_detail;
+        this
+    }
+
+    // Shows the minimap in the bottom right corner of the board.
+    pub fn with_minimap(
+        mut this: ElementPointer<Self>,
+        minimap: ElementPointer<Minimap>,
+    ) -> ElementPointer<Self> {
+        this.minimap = Some(minimap);
+        this
+    }
+
+    // Board with a dot grid in the theme's colors
+    pub fn new_dotgrid<'a>(transform: Affine, cx: &Context<'a>) -> ElementPointer<Self> {
+        Self::new(transform, DotGrid::default(), cx)
+    }
+
+    pub fn add_child(&mut self, child: ElementPointer<impl Pinnable + 'static>) -> Token {
+        let token = child.token();
+        self.children
+            .push(child.map(|element| Box::new(element) as Box<dyn Pinnable + 'static>));
+        token
+    }
+
+    pub fn connect(&mut self, from: Token, to: Token, direction: Option<Direction>) {
+        self.connections.push(Connection::new(from, to, direction));
+    }
+
+    pub fn connections(&self) -> &[Connection] {
+        &self.connections
+    }
+
+    // Board space rectangles of every pin which has been laid out.
+    fn pin_rects(&self, cx: &UpdateContext) -> Vec<(Token, Rect)> {
+        self.children
+            .iter()
+            .filter_map(|child| {
+                let size = cx.size_of(child)?;
+                let center = child.with_context(cx, |cx| child.center(cx));
+                Some((child.token(), Rect::from_center_size(center, size)))
+            })
+            .collect()
+    }
+
+    fn handle_shortcuts(&mut self, cx: &mut UpdateContext) {
+        if !cx.is_directly_focused() {
+            return;
+        }
+
+        let key_events: Vec<_> = cx.key_events().into();
+        for key_event in key_events {
+            if !key_event.state.is_pressed() {
+                continue;
+            }
+
+            let modifiers = cx.modifiers().state();
+            let camera_move = match key_event.key {
+                Key::Character(c) if modifiers.control_key() && c.eq_ignore_ascii_case("z") => {
+                    if self.step_history(!modifiers.shift_key(), cx) {
+                        cx.request_redraw();
+                    }
+                    continue;
+                }
+                Key::Character(c) if modifiers.control_key() && c.eq_ignore_ascii_case("g") => {
+                    self.group_or_expand_selection(modifiers.shift_key(), cx);
+                    cx.request_redraw();
+                    continue;
+                }
+                Key::Character(c) if c.eq_ignore_ascii_case("f") => CameraMove::FitSelection,
+                Key::Named(NamedKey::Home) => CameraMove::FitAll,
+                _ => continue,
+            };
+            cx.with_state(|state: &mut BoardState, _| {
+                state.pending_camera_move = Some(camera_move)
+            });
+        }
+    }
+
+    fn start_graph_layout(&mut self, graph_layout: GraphLayout, cx: &mut UpdateContext) {
+        let mut nodes = Vec::with_capacity(self.children.len());
+        for child in self.children.iter() {
+            let Some(size) = cx.size_of(child) else {
+                // Sizes aren't known until the children have been laid out once, so try again
+                // next frame.
+                cx.with_state(|state: &mut BoardState, _| {
+                    state.pending_graph_layout = Some(graph_layout);
+                });
+                cx.request_redraw();
+                return;
+            };
+
+            nodes.push(LayoutNode {
+                token: child.token(),
+                center: child.with_context(cx, |cx| child.center(cx)),
+                size,
+            });
+        }
+
+        // Wires to subgraphs end at their ports, which the layout only knows as the subgraph's pin
+        let connections: Vec<Connection> = self
+            .connections
+            .iter()
+            .map(|connection| {
+                Connection::new(
+                    self.port_owner(connection.from).unwrap_or(connection.from),
+                    self.port_owner(connection.to).unwrap_or(connection.to),
+                    connection.direction,
+                )
+            })
+            .collect();
+        let to = graph_layout.compute(&nodes, &connections);
+        let from: HashMap<Token, Point> =
+            nodes.iter().map(|node| (node.token, node.center)).collect();
+        let moves = from
+            .iter()
+            .filter_map(|(token, from)| Some((*token, *from, *to.get(token)?)))
+            .collect();
+        self.with_history(cx, |history, _| history.record(MovePins::new(moves)));
+        let start = cx.frame_time();
+        cx.with_state(|state: &mut BoardState, _| {
+            state.pin_animation = Some(PinAnimation {
+                start,
+                from,
+                to,
+            });
+        });
+        cx.request_redraw();
+    }
+
+    fn animate_pins(&mut self, cx: &mut UpdateContext) {
+        // Taken out of the state so that the children's states can be borrowed while moving them.
+        let Some(animation) = cx.with_state(|state: &mut BoardState, _| state.pin_animation.take())
+        else {
+            return;
+        };
+
+        let elapsed = cx.frame_time().saturating_duration_since(animation.start);
+        let t = (elapsed.as_secs_f64() / LAYOUT_ANIM_LENGTH).min(1.);
+        let eased = t * t * (3. - 2. * t);
+        for child in self.children.iter() {
+            let token = child.token();
+            if let Some((from, to)) = animation.from.get(&token).zip(animation.to.get(&token)) {
+                child.with_context(cx, |cx| child.set_center(from.lerp(*to, eased), cx));
+            }
+        }
+
+        cx.request_redraw();
+        if t < 1. {
+            cx.request_animation_frame();
+            cx.with_state(|state: &mut BoardState, _| {
+                // A newer layout may have started while the children were being moved
+                state.pin_animation.get_or_insert(animation);
+            });
+        }
+    }
+
+    // Rect a wire attached to the pin or port ends at.
+    fn wire_end(&self, token: Token) -> Option<Rect> {
+        self.pin_rects.get(&token).copied().or_else(|| {
+            let position = self.port_positions.get(&token)?;
+            Some(Rect::from_origin_size(*position, Size::ZERO))
+        })
+    }
+
+    fn draw_wires(&self, visible: Rect, cx: &mut DrawContext) {
+        cx.set_stroke_style(Stroke::new(WIRE_THICKNESS));
+        cx.set_stroke_brush(Brush::Solid(self.wire_color.resolve(&cx.theme())));
+        for connection in self.connections.iter() {
+            if let Some((from, to)) = self
+                .wire_end(connection.from)
+                .zip(self.wire_end(connection.to))
+            {
+                let path = wire_path(from, to, connection.direction);
+                if path.bounding_box().overlaps(visible) {
+                    cx.stroke(&path);
+                }
+            }
+        }
+    }
+}
+
+// Curve from the side of one pin to the facing side of another, leaving and entering along the
+// connection's direction.
+fn wire_path(from: Rect, to: Rect, direction: Option<Direction>) -> BezPath {
+    let direction = direction.unwrap_or_else(|| {
+        let delta = to.center() - from.center();
+        match (delta.x.abs() > delta.y.abs(), delta.x > 0., delta.y > 0.) {
+            (true, true, _) => Direction::After,
+            (true, false, _) => Direction::Before,
+            (false, _, true) => Direction::Below,
+            (false, _, false) => Direction::Above,
+        }
+    });
+
+    let (start, end, tangent) = match direction {
+        Direction::Above => (from.center_top(), to.center_bottom(), Vec2::new(0., -1.)),
+        Direction::Below => (from.center_bottom(), to.center_top(), Vec2::new(0., 1.)),
+        Direction::Before => (from.center_left(), to.center_right(), Vec2::new(-1., 0.)),
+        Direction::After => (from.center_right(), to.center_left(), Vec2::new(1., 0.)),
+    };
+    let handle = ((end - start).dot(tangent).abs() / 2.).max(20.);
+
+    let mut path = BezPath::new();
+    path.move_to(start);
+    path.curve_to(start + tangent * handle, end - tangent * handle, end);
+    path
+}
+
+impl ElementPointer<Board> {
+    pub fn transform<'a>(&self, cx: &impl Deref<Target = Context<'a>>) -> Affine {
+        self.read_state(cx, |state: &BoardState, _| state.transform)
+    }
+
+    pub fn selection(&self, cx: &Context) -> Vec<Token> {
+        self.read_state(cx, |state: &BoardState, _| {
+            self.children
+                .iter()
+                .map(|child| child.token())
+                .filter(|token| state.selection.contains(token))
+                .collect()
+        })
+    }
+
+    pub fn set_selection(&self, selection: impl IntoIterator<Item = Token>, cx: &Context) {
+        self.with_state(cx, |state: &mut BoardState, _| {
+            state.selection = selection.into_iter().collect();
+        });
+    }
+
+    // Moves the pins to positions computed from the connections between them. Pins animate to
+    // their new positions over the following frames.
+    pub fn apply_graph_layout(&self, graph_layout: GraphLayout, cx: &Context) {
+        self.with_state(cx, |state: &mut BoardState, _| {
+            state.pending_graph_layout = Some(graph_layout);
+        });
+    }
+}
+
+impl Element for Board {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        // Drops are reported again every frame for as long as they are hovering
+        cx.with_state(|state: &mut BoardState, _| {
+            state.pin_drop = None;
+            state.drop_highlight = false;
+        });
+        for child in self.children.iter_mut() {
+            child.update(cx);
+        }
+
+        if let Some(graph_layout) =
+            cx.with_state(|state: &mut BoardState, _| state.pending_graph_layout.take())
+        {
+            self.start_graph_layout(graph_layout, cx);
+        }
+        self.animate_pins(cx);
+        self.drag_pins(cx);
+        self.drag_annotations(cx);
+        self.drop_pins(cx);
+        if let Some(arrange) =
+            cx.with_state(|state: &mut BoardState, _| state.pending_arrange.take())
+        {
+            self.apply_arrange(arrange, cx);
+        }
+        self.handle_shortcuts(cx);
+        self.track_space(cx);
+
+        if let Some(minimap) = self.minimap.as_mut() {
+            minimap.update(cx);
+            if let Some(center) =
+                minimap.read_state(cx, |state: &MinimapState, _| state.requested_center)
+            {
+                minimap.with_state(cx, |state: &mut MinimapState, _| {
+                    state.requested_center = None
+                });
+                cx.with_state(|state: &mut BoardState, _| {
+                    state.transform = state
+                        .transform
+                        .then_translate(-(state.transform * center).to_vec2());
+                    state.camera_animation = None;
+                });
+                cx.request_redraw();
+            }
+        }
+
+        if let Some(camera_move) =
+            cx.with_state(|state: &mut BoardState, _| state.pending_camera_move.take())
+        {
+            self.start_camera_move(camera_move, cx);
+        }
+        self.animate_camera(cx);
+        self.animate_camera_motion(cx);
+    }
+
+    fn layout(&mut self, _min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        let transform = cx.with_state(|state: &mut BoardState, _| {
+            Affine::translate((max / 2.).to_vec2()) * state.transform
+        });
+        self.pin_rects.clear();
+        self.pin_index.clear();
+        for (index, child) in self.children.iter_mut().enumerate() {
+            let result = child.layout(Size::ZERO, Size::INFINITY, cx);
+            let pin_rect = Rect::from_center_size(
+                child.with_context(cx, |cx| child.center(cx)),
+                result.size(),
+            );
+            result.position(transform.pre_translate(pin_rect.origin().to_vec2()), cx);
+            self.pin_rects.insert(child.token(), pin_rect);
+            self.pin_index.insert(pin_rect, index);
+        }
+
+        self.port_positions.clear();
+        for child in self.children.iter() {
+            if let Some((board, pin_rect)) =
+                child.nested_board().zip(self.pin_rects.get(&child.token()))
+            {
+                self.port_positions
+                    .extend(port_positions(&board.ports, *pin_rect));
+            }
+        }
+
+        if let Some(minimap) = self.minimap.as_mut() {
+            minimap.pins = self.pin_rects.values().copied().collect();
+            minimap.viewport = transform
+                .inverse()
+                .transform_rect_bbox(Rect::from_origin_size(Point::ZERO, max));
+
+            let result = minimap.layout(Size::ZERO, max, cx);
+            let offset =
+                max.to_vec2() - result.size().to_vec2() - Vec2::new(MINIMAP_MARGIN, MINIMAP_MARGIN);
+            result.position(Affine::translate(offset), cx);
+        }
+
+        max
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        let region = cx.region();
+        let center = region.center().to_vec2();
+        let navigation = self.navigation;
+        let space_held = cx.with_state(|state: &mut BoardState, _| state.space_held);
+        let mouse_region = cx
+            .mouse_region(region)
+            .on_down(|cx| {
+                if !cx.modifiers().state().shift_key() {
+                    cx.with_state(|state: &mut BoardState, _| state.selection.clear());
+                }
+                cx.focus();
+            })
+            .on_scroll(move |cx| {
+                let control = cx.modifiers().state().control_key();
+                match navigation.scroll_action(cx.precise_scroll(), control) {
+                    ScrollAction::Zoom if cx.precise_scroll() => {
+                        zoom(scroll_zoom_factor(cx.scroll_delta().y), center, cx)
+                    }
+                    ScrollAction::Zoom => {
+                        smooth_zoom(scroll_zoom_factor(cx.scroll_delta().y), center, cx)
+                    }
+                    ScrollAction::Pan => pan(cx.scroll_delta(), cx),
+                    ScrollAction::Ignore => {}
+                }
+            });
+        // Only take left drags while space is held, so that drags of pins on boards nested in
+        // this one still reach them
+        if space_held {
+            mouse_region.on_drag(|cx| {
+                space_drag_pan(cx);
+            });
+        }
+        if navigation.right_drag_pan {
+            mouse_region.on_right_drag(|cx| {
+                if let Some(delta) = cx.mouse_delta() {
+                    drag_pan(delta, cx);
+                }
+            });
+        }
+        if navigation.middle_drag_pan {
+            mouse_region.on_middle_drag(|cx| {
+                if let Some(delta) = cx.mouse_delta() {
+                    drag_pan(delta, cx);
+                }
+            });
+        }
+        if navigation.pinch_zoom {
+            mouse_region.on_pinch(move |cx| zoom(cx.pinch_delta().exp(), center, cx));
+        }
+
+        let window_region = cx
+            .current_transform()
+            .inverse()
+            .transform_rect_bbox(Rect::from_origin_size(Point::ZERO, cx.window_size));
+        let adjusted_transform =
+            cx.with_state(|state: &mut BoardState, _| Affine::translate(center) * state.transform);
+        let inverse_transform = adjusted_transform.inverse();
+        let background = inverse_transform
+            .transform_rect_bbox(region)
+            .intersect(inverse_transform.transform_rect_bbox(window_region));
+
+        let screen_region = cx
+            .current_transform()
+            .transform_rect_bbox(region)
+            .intersect(Rect::from_origin_size(Point::ZERO, cx.window_size));
+        cx.push_layer(&region);
+        cx.transform(adjusted_transform);
+        let screen_transform = cx.current_transform();
+        let drop_highlight = cx.with_state(|state: &mut BoardState, _| {
+            state.screen_transform = screen_transform;
+            state.screen_region = screen_region;
+            state.drop_highlight
+        });
+
+        self.background.draw(background, cx);
+        self.draw_frames(background, cx);
+        self.draw_notes(background, cx);
+
+        self.draw_wires(background, cx);
+
+        let selection = cx.with_state(|state: &mut BoardState, _| state.selection.clone());
+        // Selected pins and pins with interactions in progress are always drawn so that the
+        // regions registered for them stay stable while they are dragged out of view.
+        let mut visible: Vec<usize> = self
+            .pin_index
+            .query(background)
+            .map(|(_, index)| *index)
+            .chain(
+                self.children
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, child)| {
+                        selection.contains(&child.token())
+                            || cx.child_has_in_progress_mouse_regions(child.token())
+                    })
+                    .map(|(index, _)| index),
+            )
+            .collect();
+        visible.sort_unstable();
+        visible.dedup();
+
+        let zoom = cx.current_transform().unskewed_scale().length() / 2.0f64.sqrt();
+        let mut clustered = Vec::new();
+        for child in visible.into_iter().map(|index| &self.children[index]) {
+            let token = child.token();
+            let Some(pin_rect) = self.pin_rects.get(&token).copied() else {
+                continue;
+            };
+
+            // Registered before the child draws so that the child's own regions take priority.
+            let pin_tokens = child.tokens();
+            cx.mouse_region(pin_rect)
+                .on_down(move |cx| {
+                    let shift = cx.modifiers().state().shift_key();
+                    cx.with_state(|state: &mut BoardState, _| {
+                        if shift {
+                            if !state.selection.remove(&token) {
+                                state.selection.insert(token);
+                            }
+                        } else if !state.selection.contains(&token) {
+                            state.selection = HashSet::from([token]);
+                        }
+                    });
+
+                    // Don't steal focus from the pin's own contents
+                    if !cx
+                        .focused_token()
+                        .is_some_and(|focused| pin_tokens.contains(&focused))
+                    {
+                        cx.focus();
+                    }
+                    cx.request_redraw();
+                })
+                .on_drag(move |cx| {
+                    if space_drag_pan(cx) {
+                        return;
+                    }
+                    if let Some(delta) = cx.mouse_delta() {
+                        cx.with_state(|state: &mut BoardState, _| {
+                            let drag = state.pin_drag.get_or_insert(PinDrag {
+                                pin: token,
+                                unsnapped: None,
+                                delta: Vec2::ZERO,
+                            });
+                            drag.delta += delta;
+                        });
+                        cx.request_redraw();
+                    }
+                });
+
+            match self.level_of_detail.level(zoom) {
+                DetailLevel::Full => child.draw(cx),
+                DetailLevel::Card => {
+                    let title = child.with_context(cx, |cx| child.title(cx));
+                    self.draw_card(title.as_deref(), pin_rect, cx);
+                }
+                DetailLevel::Block => self.draw_block(pin_rect, cx),
+                DetailLevel::Clustered => clustered.push((token, pin_rect)),
+            }
+
+            if selection.contains(&token) {
+                cx.set_stroke_style(Stroke::new(SELECTION_THICKNESS));
+                cx.set_stroke_brush(Brush::Solid(self.selection_color.resolve(&cx.theme())));
+                cx.stroke(&pin_rect.inflate(SELECTION_OFFSET, SELECTION_OFFSET));
+            }
+        }
+
+        self.draw_ports(cx);
+        self.draw_clusters(&clustered, zoom, cx);
+        self.draw_guides(cx);
+
+        if let Some(minimap) = self.minimap.as_ref() {
+            minimap.draw(cx);
+        }
+
+        cx.transform(adjusted_transform.inverse());
+        if let Some(rulers) = self.rulers.as_ref() {
+            rulers.draw(region, adjusted_transform, cx);
+        }
+
+        if drop_highlight {
+            cx.set_stroke_style(Stroke::new(SELECTION_THICKNESS * 2.));
+            cx.set_stroke_brush(Brush::Solid(self.selection_color.resolve(&cx.theme())));
+            cx.stroke(&region.inset(-SELECTION_THICKNESS));
+        }
+
+        cx.pop_layer();
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.children
+            .iter()
+            .flat_map(|c| c.tokens())
+            .chain(self.minimap.iter().flat_map(|minimap| minimap.tokens()))
+            .collect()
+    }
+}
+
+pub struct PinWrapper<Child: Element> {
+    child: ElementPointer<Child>,
+    size: Option<Size>,
+    title: Option<TitleSource<Child>>,
+    // Reuse the pin's drawing while the board is panned
+    retain_drawing: bool,
+}
+
+impl<Child: Element> PinWrapper<Child> {
+    pub fn new(center: Point, child: ElementPointer<Child>, cx: &Context) -> ElementPointer<Self> {
+        ElementPointer::new(Self {
+            child,
+            size: None,
+            title: None,
+            retain_drawing: true,
+        })
+        .insert_state(center, cx)
+    }
+
+    pub fn new_sized(
+        center: Point,
+        size: Size,
+        child: ElementPointer<Child>,
+        cx: &Context,
+    ) -> ElementPointer<Self> {
+        ElementPointer::new(Self {
+            child,
+            size: Some(size),
+            title: None,
+            retain_drawing: true,
+        })
+        .insert_state(center, cx)
+    }
+
+    pub fn sized(mut this: ElementPointer<Self>, size: Size) -> ElementPointer<Self> {
+        this.size = Some(size);
+        this
+    }
+
+    pub fn with_title(
+        mut this: ElementPointer<Self>,
+        title: impl Into<String>,
+    ) -> ElementPointer<Self> {
+        let title = title.into();
+        this.title = Some(Rc::new(move |_, _| title.clone()));
+        this
+    }
+
+    // Titles taken from the pin's contents, such as the text being edited in it, stay up to
+    // date as the contents change.
+    pub fn with_title_from(
+        mut this: ElementPointer<Self>,
+        title: impl Fn(&ElementPointer<Child>, &Context) -> String + 'static,
+    ) -> ElementPointer<Self> {
+        this.title = Some(Rc::new(title));
+        this
+    }
+
+    // Pins whose drawing depends on more than their state and layout, such as the mouse
+    // position, should opt out of having it reused.
+    pub fn with_retained_drawing(
+        mut this: ElementPointer<Self>,
+        retain_drawing: bool,
+    ) -> ElementPointer<Self> {
+        this.retain_drawing = retain_drawing;
+        this
+    }
+}
+
+impl<Child: Element> Element for PinWrapper<Child> {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        self.child.update(cx)
+    }
+
+    fn layout(&mut self, min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        if let Some(size) = self.size {
+            self.child
+                .layout(size, size, cx)
+                .position(Affine::IDENTITY, cx)
+        } else {
+            self.child
+                .layout(min, max, cx)
+                .position(Affine::IDENTITY, cx)
+        }
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        self.child.draw(cx);
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.child.tokens()
+    }
+
+    fn retain_drawing(&self) -> bool {
+        self.retain_drawing
+    }
+}
+
+impl<Child: Element + 'static> Pinnable for PinWrapper<Child> {
+    fn center(&self, cx: &Context) -> Point {
+        cx.read_state(|center: &Point, _| *center)
+    }
+
+    fn set_center(&self, center: Point, cx: &Context) {
+        cx.with_state(|current: &mut Point, _| *current = center);
+    }
+
+    fn title(&self, cx: &Context) -> Option<String> {
+        self.title.as_ref().map(|title| title(&self.child, cx))
+    }
+
+    fn nested_board(&self) -> Option<&ElementPointer<Board>> {
+        (&self.child as &dyn Any).downcast_ref()
+    }
+
+    fn nested_board_mut(&mut self) -> Option<&mut ElementPointer<Board>> {
+        (&mut self.child as &mut dyn Any).downcast_mut()
+    }
+}
+
+pub trait ElementPinExt<This: Element + Sized> {
+    fn as_pinnable(self, center: Point, cx: &Context) -> ElementPointer<PinWrapper<This>>;
+}
+
+impl<This: Element + Sized> ElementPinExt<This> for ElementPointer<This> {
+    fn as_pinnable(self, center: Point, cx: &Context) -> ElementPointer<PinWrapper<This>> {
+        PinWrapper::new(center, self, cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use vello::{peniko::Color, Scene};
+    use winit::window::{Cursor, CursorIcon};
+
+    use super::*;
+    use crate::{components::layout::SizedBox, test_runner::TestRunner, theme::Theme};
+
+    // Counts the drags which start on it.
+    struct Dragged {
+        drags: Rc<Cell<usize>>,
+    }
+
+    impl Element for Dragged {
+        fn layout(&mut self, min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+            min
+        }
+
+        fn draw(&self, cx: &mut DrawContext) {
+            let drags = self.drags.clone();
+            let region = cx.region();
+            cx.mouse_region(region)
+                .on_down(|_| {})
+                .on_drag(move |_| drags.set(drags.get() + 1));
+        }
+    }
+
+    // Pin content which counts its draws.
+    struct Drawn {
+        draws: Rc<Cell<usize>>,
+    }
+
+    impl Element for Drawn {
+        fn layout(&mut self, _min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+            Size::new(100., 100.)
+        }
+
+        fn draw(&self, cx: &mut DrawContext) {
+            self.draws.set(self.draws.get() + 1);
+            let region = cx.region();
+            cx.fill(&region);
+        }
+    }
+
+    fn draws_color(scene: &Scene, color: Color) -> bool {
+        let rgba = color.premultiply().to_rgba8().to_u32();
+        scene.encoding().draw_data.contains(&rgba)
+    }
+
+    #[test]
+    fn board_colors_follow_theme_switches() {
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(ThemeColor::Background(0)), cx);
+            let pin =
+                |center: Point| PinWrapper::new(center, SizedBox::new(Size::new(40., 40.)), cx);
+            let from = board.add_child(pin(Point::new(-80., 0.)));
+            let to = board.add_child(pin(Point::new(80., 0.)));
+            board.connect(from, to, None);
+            board.add_frame(Frame::new(
+                "Frame",
+                Rect::new(-150., -100., 150., 100.),
+                ThemeColor::Accent,
+            ));
+            board
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let (dark, light) = (Theme::dark(), Theme::light());
+
+        let child_lookup = test_runner.layout();
+        let scene = test_runner.draw(child_lookup);
+        assert!(draws_color(&scene, dark.muted));
+        assert!(draws_color(&scene, dark.accent));
+
+        test_runner.with_root(|_, cx| cx.set_theme(light.clone()));
+        let scene = test_runner.tick().unwrap();
+        assert!(draws_color(&scene, light.muted));
+        assert!(draws_color(&scene, light.accent));
+        assert!(!draws_color(&scene, dark.muted));
+        assert!(!draws_color(&scene, dark.accent));
+    }
+
+    #[test]
+    fn pins_being_dragged_in_stay_drawn_when_panned_out_of_view() {
+        let drags = Rc::new(Cell::new(0));
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            let dragged = ElementPointer::new(Dragged {
+                drags: drags.clone(),
+            });
+            board.add_child(PinWrapper::new_sized(
+                Point::ZERO,
+                Size::new(40., 40.),
+                dragged,
+                cx,
+            ));
+            board
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+
+        test_runner.move_mouse(Point::new(200., 200.));
+        test_runner.press_mouse();
+        test_runner.move_mouse(Point::new(210., 200.));
+        let dragged = drags.get();
+        assert!(dragged > 0);
+
+        test_runner.with_root(|_, cx| {
+            cx.with_state(|state: &mut BoardState, _| {
+                state.transform = Affine::translate((1000., 0.))
+            })
+        });
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        test_runner.move_mouse(Point::new(220., 200.));
+        assert!(drags.get() > dragged);
+    }
+
+    #[test]
+    fn panning_replays_pin_drawings() {
+        let draws = Rc::new(Cell::new(0));
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            let drawn = ElementPointer::new(Drawn {
+                draws: draws.clone(),
+            });
+            board.add_child(PinWrapper::new(Point::ZERO, drawn, cx));
+            board
+        });
+        let redraw = |test_runner: &mut TestRunner<Board>| {
+            let child_lookup = test_runner.layout();
+            test_runner.draw(child_lookup);
+        };
+
+        for _ in 0..5 {
+            redraw(&mut test_runner);
+        }
+        assert_eq!(draws.get(), 1);
+
+        // Panning only translates the pin
+        for step in 1..=3 {
+            test_runner.with_root(|_, cx| {
+                cx.with_state(|state: &mut BoardState, _| {
+                    state.transform = Affine::translate((step as f64 * 25., 0.))
+                })
+            });
+            redraw(&mut test_runner);
+        }
+        assert_eq!(draws.get(), 1);
+    }
+}
@@ -0,0 +1,511 @@
+use std::time::Instant;
+
+use vello::kurbo::{Point, Vec2};
+use winit::keyboard::{Key, NamedKey};
+
+use super::{Board, BoardState, Camera, MAX_ZOOM, MIN_ZOOM};
+use crate::{
+    context_stack::{EventContext, UpdateContext},
+    element::ElementPointer,
+    util::*,
+};
+
+// Rate at which pan momentum decays per second after a drag is released
+const PAN_FRICTION: f64 = 6.;
+// Screen space speed in pixels per second below which momentum stops
+const MIN_PAN_SPEED: f64 = 10.;
+// Pausing longer than this in seconds before releasing a drag stops it dead
+const RELEASE_WINDOW: f64 = 0.05;
+// Weight given to the newest sample when smoothing drag velocity
+const VELOCITY_SMOOTHING: f64 = 0.5;
+// Rate at which zoom closes the gap to its target per second
+const ZOOM_RATE: f64 = 18.;
+// Log zoom left to apply below which the zoom snaps to its target
+const ZOOM_EPSILON: f64 = 0.001;
+// Longest step taken at once, so that stalled frames don't throw the camera
+const MAX_STEP: f64 = 0.1;
+// Scroll distance in pixels which zooms by a factor of e
+const SCROLL_ZOOM_DISTANCE: f64 = 100.;
+
+/// What scrolling over a board does to its camera.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrollAction {
+    Zoom,
+    Pan,
+    Ignore,
+}
+
+/// Mouse, keyboard and trackpad bindings used to move around a board. The presets follow the
+/// bindings of common node editors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Navigation {
+    pub right_drag_pan: bool,
+    pub middle_drag_pan: bool,
+    // Dragging the background with the left mouse button while space is held
+    pub space_drag_pan: bool,
+    // Scrolling in lines, as mouse wheels do
+    pub wheel: ScrollAction,
+    // Scrolling in pixels, as two fingers on a trackpad do
+    pub trackpad: ScrollAction,
+    // Scrolling of either kind while control is held. Some platforms report trackpad pinches
+    // this way.
+    pub control_scroll: ScrollAction,
+    pub pinch_zoom: bool,
+}
+
+impl Default for Navigation {
+    fn default() -> Self {
+        Self {
+            right_drag_pan: true,
+            middle_drag_pan: true,
+            space_drag_pan: true,
+            wheel: ScrollAction::Zoom,
+            trackpad: ScrollAction::Pan,
+            control_scroll: ScrollAction::Zoom,
+            pinch_zoom: true,
+        }
+    }
+}
+
+impl Navigation {
+    pub fn blender() -> Self {
+        Self {
+            right_drag_pan: false,
+            middle_drag_pan: true,
+            space_drag_pan: false,
+            wheel: ScrollAction::Zoom,
+            trackpad: ScrollAction::Pan,
+            control_scroll: ScrollAction::Pan,
+            pinch_zoom: true,
+        }
+    }
+
+    pub fn unreal() -> Self {
+        Self {
+            right_drag_pan: true,
+            middle_drag_pan: true,
+            space_drag_pan: false,
+            wheel: ScrollAction::Zoom,
+            trackpad: ScrollAction::Zoom,
+            control_scroll: ScrollAction::Zoom,
+            pinch_zoom: true,
+        }
+    }
+
+    // Pans with plain scrolling and zooms with pinches or control scrolling, as design tools do.
+    pub fn trackpad() -> Self {
+        Self {
+            right_drag_pan: false,
+            middle_drag_pan: true,
+            space_drag_pan: true,
+            wheel: ScrollAction::Pan,
+            trackpad: ScrollAction::Pan,
+            control_scroll: ScrollAction::Zoom,
+            pinch_zoom: true,
+        }
+    }
+
+    pub fn scroll_action(&self, precise: bool, control: bool) -> ScrollAction {
+        if control {
+            self.control_scroll
+        } else if precise {
+            self.trackpad
+        } else {
+            self.wheel
+        }
+    }
+}
+
+// Camera movement carried on over the following frames: momentum left over from a drag and zoom
+// easing toward its target.
+#[derive(Default)]
+pub(crate) struct CameraMotion {
+    // Screen space velocity in pixels per second
+    velocity: Vec2,
+    last_sample: Option<Instant>,
+    dragging: bool,
+    coasting: bool,
+    // Log of the zoom factor left to apply and the point it is applied about
+    zoom_remaining: f64,
+    zoom_about: Point,
+    last_step: Option<Instant>,
+}
+
+impl CameraMotion {
+    fn track(&mut self, delta: Vec2, now: Instant) {
+        if !self.dragging {
+            self.velocity = Vec2::ZERO;
+            self.last_sample = None;
+        }
+        if let Some(last) = self.last_sample {
+            let dt = (now - last).as_secs_f64();
+            if dt > 0. {
+                self.velocity = self.velocity.lerp(delta / dt, VELOCITY_SMOOTHING);
+            }
+        }
+        self.last_sample = Some(now);
+        self.dragging = true;
+        self.coasting = false;
+    }
+
+    fn release(&mut self, now: Instant) {
+        self.dragging = false;
+        let recent = self
+            .last_sample
+            .is_some_and(|last| (now - last).as_secs_f64() < RELEASE_WINDOW);
+        self.coasting = recent && self.velocity.length() > MIN_PAN_SPEED;
+    }
+
+    // Advances the motion by the given number of seconds, returning the screen space translation
+    // and log zoom to apply.
+    fn step(&mut self, dt: f64) -> (Vec2, f64) {
+        let mut translation = Vec2::ZERO;
+        if self.coasting {
+            translation = self.velocity * dt;
+            self.velocity *= (-PAN_FRICTION * dt).exp();
+            self.coasting = self.velocity.length() > MIN_PAN_SPEED;
+        }
+
+        let mut zoom = self.zoom_remaining;
+        if zoom.abs() > ZOOM_EPSILON {
+            zoom *= 1. - (-ZOOM_RATE * dt).exp();
+        }
+        self.zoom_remaining -= zoom;
+        (translation, zoom)
+    }
+
+    fn is_settled(&self) -> bool {
+        !self.coasting && self.zoom_remaining == 0.
+    }
+
+    pub fn stop(&mut self) {
+        *self = Self::default();
+    }
+}
+
+// Moves the board's camera by the given screen space delta.
+pub(super) fn pan(delta: Vec2, cx: &mut EventContext) {
+    cx.with_state(|state: &mut BoardState, _| {
+        state.transform = state.transform.then_translate(delta);
+        state.camera_animation = None;
+    });
+    cx.request_redraw();
+}
+
+// Pans along with a drag, tracking its velocity so that the camera keeps moving once released.
+pub(super) fn drag_pan(delta: Vec2, cx: &mut EventContext) {
+    let now = cx.frame_time();
+    cx.with_state(|state: &mut BoardState, _| state.camera_motion.track(delta, now));
+    pan(delta, cx);
+}
+
+// Zoom factor for a scroll, which stays positive however far a single event scrolls.
+pub(super) fn scroll_zoom_factor(scroll: f64) -> f64 {
+    (scroll / SCROLL_ZOOM_DISTANCE).exp()
+}
+
+// Pans along with a left drag while space is held. Returns false if space isn't held, in which
+// case the drag is left to the region's own handling.
+pub(super) fn space_drag_pan(cx: &mut EventContext) -> bool {
+    if !cx.with_state(|state: &mut BoardState, _| state.space_held) {
+        return false;
+    }
+    if let Some(delta) = cx.mouse_delta() {
+        drag_pan(delta, cx);
+    }
+    true
+}
+
+// Eases the board's camera toward the zoom over the following frames.
+pub(super) fn smooth_zoom(factor: f64, center: Vec2, cx: &mut EventContext) {
+    let Some(pos) = cx.mouse_position() else {
+        return;
+    };
+    cx.with_state(|state: &mut BoardState, _| {
+        let motion = &mut state.camera_motion;
+        motion.zoom_remaining += factor.ln();
+        motion.zoom_about = pos - center;
+        state.camera_animation = None;
+    });
+    cx.request_redraw();
+}
+
+// Scales the board's camera about the mouse, staying within the zoom limits. The center is the
+// center of the board's region, which the board's transform is relative to.
+pub(super) fn zoom(factor: f64, center: Vec2, cx: &mut EventContext) {
+    let Some(pos) = cx.mouse_position() else {
+        return;
+    };
+    let new_transform = cx.with_state(|state: &mut BoardState, _| {
+        state.transform.then_scale_about(factor, pos - center)
+    });
+
+    let test_length = new_transform.unskewed_scale().length() / 2.0f64.sqrt();
+    if test_length < MAX_ZOOM && test_length > MIN_ZOOM {
+        cx.with_state(|state: &mut BoardState, _| {
+            state.transform = new_transform;
+            state.camera_animation = None;
+        });
+        cx.request_redraw();
+    }
+}
+
+impl Board {
+    pub fn navigation(&self) -> Navigation {
+        self.navigation
+    }
+
+    pub fn with_navigation(
+        mut this: ElementPointer<Self>,
+        navigation: Navigation,
+    ) -> ElementPointer<Self> {
+        this.navigation = navigation;
+        this
+    }
+
+    // Applies momentum and eased zoom, requesting redraws until both settle.
+    pub(super) fn animate_camera_motion(&self, cx: &mut UpdateContext) {
+        let dragging = cx.mouse_down() || cx.right_mouse_down() || cx.middle_mouse_down();
+        let now = cx.frame_time();
+        let moving = cx.with_state(|state: &mut BoardState, _| {
+            let motion = &mut state.camera_motion;
+            if motion.dragging && !dragging {
+                motion.release(now);
+            }
+            if motion.is_settled() {
+                motion.last_step = None;
+                return false;
+            }
+
+            let dt = motion
+                .last_step
+                .map_or(0., |last| (now - last).as_secs_f64())
+                .min(MAX_STEP);
+            motion.last_step = Some(now);
+            let (translation, zoom) = motion.step(dt);
+            let about = motion.zoom_about;
+
+            let mut transform = state.transform.then_translate(translation);
+            let zoomed = transform.then_scale_about(zoom.exp(), about);
+            if (MIN_ZOOM..MAX_ZOOM).contains(&Camera::from_transform(zoomed).zoom) {
+                transform = zoomed;
+            } else {
+                state.camera_motion.zoom_remaining = 0.;
+            }
+            state.transform = transform;
+            true
+        });
+
+        if moving {
+            cx.request_redraw();
+            cx.request_animation_frame();
+        }
+    }
+
+    // Remembers whether space is held so that dragging anywhere on the board pans. Only boards
+    // with focus see space, so that typing into pins doesn't start panning.
+    pub(super) fn track_space(&self, cx: &mut UpdateContext) {
+        let was_held = cx.with_state(|state: &mut BoardState, _| state.space_held);
+        let mut held = was_held;
+        if !self.navigation.space_drag_pan || !cx.is_directly_focused() {
+            held = false;
+        } else {
+            for key_event in cx.key_events() {
+                if key_event.key == Key::Named(NamedKey::Space) {
+                    held = key_event.state.is_pressed();
+                }
+            }
+        }
+
+        // The board's region only takes drags while space is held, so draw it again
+        if held != was_held {
+            cx.with_state(|state: &mut BoardState, _| state.space_held = held);
+            cx.request_redraw();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use vello::{
+        kurbo::{Affine, Size},
+        peniko::Color,
+    };
+    use winit::{
+        event::ElementState,
+        window::{Cursor, CursorIcon},
+    };
+
+    use super::*;
+    use crate::{
+        components::{
+            board::{Blank, PinWrapper},
+            layout::SizedBox,
+        },
+        context_stack::KeyEvent,
+        test_runner::TestRunner,
+    };
+
+    #[test]
+    fn space_drags_pan_over_pins_which_otherwise_drag() {
+        let mut pin = None;
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            let sized = SizedBox::new(Size::new(100., 100.));
+            pin = Some(board.add_child(PinWrapper::new(Point::ZERO, sized, cx)));
+            board
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        test_runner.with_root(|_, cx| cx.focus());
+        let pin = pin.unwrap();
+        let drag = |test_runner: &mut TestRunner<Board>, from: Point, to: Point| {
+            test_runner.move_mouse(from);
+            test_runner.press_mouse();
+            test_runner.move_mouse(to);
+            test_runner.release_mouse();
+        };
+        let camera_and_pin = |test_runner: &TestRunner<Board>| {
+            test_runner.with_root(|board, cx| {
+                let translation =
+                    cx.read_state(|state: &BoardState, _| state.transform.translation());
+                let child = &board.children[0];
+                assert_eq!(child.token(), pin);
+                (translation, child.with_context(cx, |cx| child.center(cx)))
+            })
+        };
+
+        drag(
+            &mut test_runner,
+            Point::new(200., 200.),
+            Point::new(230., 200.),
+        );
+        assert_eq!(
+            camera_and_pin(&test_runner),
+            (Vec2::ZERO, Point::new(30., 0.))
+        );
+
+        test_runner
+            .application
+            .event_state
+            .key_events
+            .push(KeyEvent {
+                key: Key::Named(NamedKey::Space),
+                state: ElementState::Pressed,
+            });
+        test_runner.tick();
+        drag(
+            &mut test_runner,
+            Point::new(230., 200.),
+            Point::new(260., 200.),
+        );
+        drag(&mut test_runner, Point::new(50., 50.), Point::new(50., 70.));
+        assert_eq!(
+            camera_and_pin(&test_runner),
+            (Vec2::new(30., 20.), Point::new(30., 0.))
+        );
+    }
+
+    #[test]
+    fn long_scrolls_zoom_out_without_flipping() {
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx)
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        test_runner.move_mouse(Point::new(200., 200.));
+
+        test_runner.application.event_state.scroll_delta = Vec2::new(0., -500.);
+        test_runner.tick();
+        for _ in 0..60 {
+            test_runner.advance_time(Duration::from_millis(16));
+        }
+        test_runner.with_root(|_, cx| {
+            let zoom =
+                cx.read_state(|state: &BoardState, _| Camera::from_transform(state.transform).zoom);
+            assert!(zoom.is_finite());
+            assert!(zoom > 0. && zoom < 1.);
+        });
+        assert!(scroll_zoom_factor(-500.) > 0.);
+        assert!((scroll_zoom_factor(10.) - 1.1).abs() < 0.01);
+    }
+
+    #[test]
+    fn pinches_zoom_about_the_mouse_without_mirroring() {
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx)
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        test_runner.move_mouse(Point::new(200., 200.));
+        let transform = |test_runner: &TestRunner<Board>| {
+            test_runner.with_root(|_, cx| cx.read_state(|state: &BoardState, _| state.transform))
+        };
+
+        test_runner.application.event_state.pinch_delta = 0.5;
+        test_runner.tick();
+        let zoomed = Camera::from_transform(transform(&test_runner)).zoom;
+        assert!((zoomed - 0.5f64.exp()).abs() < 1e-9);
+
+        // Fast pinches add up to deltas below -1 within a single frame
+        test_runner.application.event_state.pinch_delta = -3.;
+        test_runner.tick();
+        let transform = transform(&test_runner);
+        assert!(transform.determinant() > 0.);
+        assert!(transform.as_coeffs()[0] > 0.);
+        let zoom = Camera::from_transform(transform).zoom;
+        assert!((zoom - zoomed * (-3f64).exp()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scroll_action_depends_on_device_and_modifier() {
+        let navigation = Navigation::default();
+        assert_eq!(navigation.scroll_action(false, false), ScrollAction::Zoom);
+        assert_eq!(navigation.scroll_action(true, false), ScrollAction::Pan);
+        assert_eq!(navigation.scroll_action(true, true), ScrollAction::Zoom);
+
+        let navigation = Navigation::trackpad();
+        assert_eq!(navigation.scroll_action(false, false), ScrollAction::Pan);
+        assert_eq!(navigation.scroll_action(false, true), ScrollAction::Zoom);
+    }
+
+    #[test]
+    fn camera_motion_settles() {
+        let start = Instant::now();
+        let mut motion = CameraMotion::default();
+        motion.track(Vec2::new(10., 0.), start);
+        motion.track(Vec2::new(10., 0.), start + Duration::from_millis(10));
+        motion.release(start + Duration::from_millis(20));
+        motion.zoom_remaining = 2.0f64.ln();
+
+        let mut translation = Vec2::ZERO;
+        let mut zoom = 0.;
+        let mut previous = f64::INFINITY;
+        for _ in 0..120 {
+            let (step_translation, step_zoom) = motion.step(1. / 60.);
+            assert!(step_translation.x <= previous);
+            previous = step_translation.x;
+            translation += step_translation;
+            zoom += step_zoom;
+        }
+
+        assert!(motion.is_settled());
+        assert!(translation.x > 10.);
+        assert!((zoom.exp() - 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pausing_before_release_stops_momentum() {
+        let start = Instant::now();
+        let mut motion = CameraMotion::default();
+        motion.track(Vec2::new(10., 0.), start);
+        motion.track(Vec2::new(10., 0.), start + Duration::from_millis(10));
+        motion.release(start + Duration::from_millis(500));
+        assert!(motion.is_settled());
+    }
+}
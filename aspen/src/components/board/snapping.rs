@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+
+use vello::{
+    kurbo::{Line, Point, Rect, Stroke, Vec2},
+    peniko::Brush,
+};
+
+use super::{Board, BoardState, Camera, MovePins, PinDrop};
+use crate::{
+    context_stack::{DrawContext, UpdateContext},
+    token::Token,
+};
+
+// Screen space distance within which a dragged pin snaps to another pin's edge or center
+const GUIDE_SNAP_DISTANCE: f64 = 6.;
+const GUIDE_THICKNESS: f64 = 1.;
+// Tolerance used when collecting pins that share a guide after snapping
+const ALIGNED_EPSILON: f64 = 1e-6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridSnap {
+    #[default]
+    Off,
+    Centers,
+    Edges,
+}
+
+// Pin being dragged along with where it would be without any snapping applied.
+pub(crate) struct PinDrag {
+    pub pin: Token,
+    pub unsnapped: Option<Point>,
+    pub delta: Vec2,
+}
+
+// Offset which moves one of the features of the dragged pin onto the given position.
+#[derive(Clone, Copy)]
+struct Alignment {
+    offset: f64,
+    position: f64,
+}
+
+// Min, center and max of a rect along one axis.
+fn features(min: f64, max: f64) -> [f64; 3] {
+    [min, (min + max) / 2., max]
+}
+
+fn closest_alignment(
+    moving: [f64; 3],
+    others: impl Iterator<Item = [f64; 3]>,
+    threshold: f64,
+) -> Option<Alignment> {
+    let mut closest: Option<Alignment> = None;
+    for other in others {
+        for position in other {
+            for feature in moving {
+                let offset = position - feature;
+                if offset.abs() <= threshold
+                    && closest.is_none_or(|closest| offset.abs() < closest.offset.abs())
+                {
+                    closest = Some(Alignment { offset, position });
+                }
+            }
+        }
+    }
+    closest
+}
+
+fn grid_offset(min: f64, max: f64, spacing: f64, grid_snap: GridSnap) -> f64 {
+    let snap = |value: f64| (value / spacing).round() * spacing - value;
+    match grid_snap {
+        GridSnap::Off => 0.,
+        GridSnap::Centers => snap((min + max) / 2.),
+        GridSnap::Edges => {
+            let (min_offset, max_offset) = (snap(min), snap(max));
+            if min_offset.abs() <= max_offset.abs() {
+                min_offset
+            } else {
+                max_offset
+            }
+        }
+    }
+}
+
+impl Board {
+    // Moves the dragged pin, and the rest of the selection if it is part of it, snapping it to
+    // the grid and to the other pins.
+    pub(super) fn drag_pins(&mut self, cx: &mut UpdateContext) {
+        let Some(drag) = cx.with_state(|state: &mut BoardState, _| state.pin_drag.take()) else {
+            return;
+        };
+
+        let (zoom, selection) = cx.with_state(|state: &mut BoardState, _| {
+            (
+                Camera::from_transform(state.transform).zoom,
+                state.selection.clone(),
+            )
+        });
+        let moving = if selection.contains(&drag.pin) {
+            selection
+        } else {
+            HashSet::from([drag.pin])
+        };
+
+        // Pins which have been moved may be dropped onto other boards
+        if drag.unsnapped.is_some() {
+            let pins = self
+                .children
+                .iter()
+                .map(|child| child.token())
+                .filter(|token| moving.contains(token))
+                .collect();
+            cx.with_state(|state: &mut BoardState, cx| {
+                state.pin_drop = Some(PinDrop {
+                    pins,
+                    released: !cx.mouse_down(),
+                });
+            });
+        }
+
+        if !cx.mouse_down() {
+            cx.with_state(|state: &mut BoardState, _| state.guides.clear());
+            cx.request_redraw();
+            return;
+        }
+
+        let pin_rects = self.pin_rects(cx);
+        let Some(rect) = pin_rects
+            .iter()
+            .find(|(token, _)| *token == drag.pin)
+            .map(|(_, rect)| *rect)
+        else {
+            return;
+        };
+
+        if drag.delta == Vec2::ZERO {
+            cx.with_state(|state: &mut BoardState, _| state.pin_drag = Some(drag));
+            return;
+        }
+
+        let unsnapped = drag.unsnapped.unwrap_or(rect.center()) + drag.delta;
+        let unsnapped_rect = Rect::from_center_size(unsnapped, rect.size());
+        let others: Vec<Rect> = pin_rects
+            .iter()
+            .filter(|(token, _)| !moving.contains(token))
+            .map(|(_, rect)| *rect)
+            .collect();
+
+        // Holding alt drags pins freely
+        let (snapped_rect, guides) = if cx.modifiers().state().alt_key() {
+            (unsnapped_rect, Vec::new())
+        } else {
+            self.snap(unsnapped_rect, &others, zoom)
+        };
+
+        let offset = snapped_rect.center() - rect.center();
+        let moves = self
+            .children
+            .iter()
+            .filter(|child| moving.contains(&child.token()))
+            .map(|child| {
+                let center = child.with_context(cx, |cx| child.center(cx));
+                (child.token(), center, center + offset)
+            })
+            .collect();
+        self.with_history(cx, |history, board| {
+            // Each drag is its own undo step
+            if drag.unsnapped.is_none() {
+                history.seal();
+            }
+            history.execute(MovePins::dragging(moves), board, cx);
+        });
+
+        cx.with_state(|state: &mut BoardState, _| {
+            state.pin_drag = Some(PinDrag {
+                pin: drag.pin,
+                unsnapped: Some(unsnapped),
+                delta: Vec2::ZERO,
+            });
+            state.guides = guides;
+        });
+        cx.request_redraw();
+    }
+
+    // Snaps each axis to the closest aligned pin if there is one and to the grid otherwise.
+    fn snap(&self, rect: Rect, others: &[Rect], zoom: f64) -> (Rect, Vec<Line>) {
+        let threshold = GUIDE_SNAP_DISTANCE / zoom;
+        let alignments = if self.smart_guides {
+            (
+                closest_alignment(
+                    features(rect.x0, rect.x1),
+                    others.iter().map(|other| features(other.x0, other.x1)),
+                    threshold,
+                ),
+                closest_alignment(
+                    features(rect.y0, rect.y1),
+                    others.iter().map(|other| features(other.y0, other.y1)),
+                    threshold,
+                ),
+            )
+        } else {
+            (None, None)
+        };
+
+        let spacing = self.background.grid_spacing(zoom);
+        let axis_offset = |alignment: Option<Alignment>, min: f64, max: f64| match alignment {
+            Some(alignment) => alignment.offset,
+            None => spacing.map_or(0., |spacing| grid_offset(min, max, spacing, self.grid_snap)),
+        };
+        let snapped = rect
+            + Vec2::new(
+                axis_offset(alignments.0, rect.x0, rect.x1),
+                axis_offset(alignments.1, rect.y0, rect.y1),
+            );
+
+        let aligned = |position: f64, features: [f64; 3]| {
+            features
+                .iter()
+                .any(|feature| (feature - position).abs() < ALIGNED_EPSILON)
+        };
+        let mut guides = Vec::new();
+        if let Some(Alignment { position, .. }) = alignments.0 {
+            let (y0, y1) = others
+                .iter()
+                .filter(|other| aligned(position, features(other.x0, other.x1)))
+                .fold((snapped.y0, snapped.y1), |(y0, y1), other| {
+                    (y0.min(other.y0), y1.max(other.y1))
+                });
+            guides.push(Line::new((position, y0), (position, y1)));
+        }
+        if let Some(Alignment { position, .. }) = alignments.1 {
+            let (x0, x1) = others
+                .iter()
+                .filter(|other| aligned(position, features(other.y0, other.y1)))
+                .fold((snapped.x0, snapped.x1), |(x0, x1), other| {
+                    (x0.min(other.x0), x1.max(other.x1))
+                });
+            guides.push(Line::new((x0, position), (x1, position)));
+        }
+
+        (snapped, guides)
+    }
+
+    pub(super) fn draw_guides(&self, cx: &mut DrawContext) {
+        let (guides, zoom) = cx.with_state(|state: &mut BoardState, _| {
+            (
+                state.guides.clone(),
+                Camera::from_transform(state.transform).zoom,
+            )
+        });
+        if guides.is_empty() {
+            return;
+        }
+
+        cx.set_stroke_style(Stroke::new(GUIDE_THICKNESS / zoom));
+        cx.set_stroke_brush(Brush::Solid(self.selection_color.resolve(&cx.theme())));
+        for guide in guides.iter() {
+            cx.stroke(guide);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::{Affine, Size};
+    use winit::{
+        keyboard::ModifiersState,
+        window::{Cursor, CursorIcon},
+    };
+
+    use super::*;
+    use crate::{
+        components::{
+            board::{DotGrid, PinWrapper},
+            layout::{Align, SizedBox},
+        },
+        test_runner::TestRunner,
+    };
+
+    #[test]
+    fn snapping_prefers_closest_feature() {
+        let alignment = closest_alignment(
+            features(0., 100.),
+            [features(103., 200.), features(-40., 148.)].into_iter(),
+            5.,
+        )
+        .unwrap();
+        assert_eq!(alignment.position, 103.);
+        assert_eq!(alignment.offset, 3.);
+
+        assert!(
+            closest_alignment(features(0., 100.), [features(20., 30.)].into_iter(), 5.).is_none()
+        );
+
+        assert_eq!(grid_offset(3., 50., 16., GridSnap::Edges), -2.);
+        assert_eq!(grid_offset(3., 50., 16., GridSnap::Centers), 5.5);
+        assert_eq!(grid_offset(3., 50., 16., GridSnap::Off), 0.);
+    }
+
+    // Drags a pin at the board's origin 10 to the right, next to a pin sitting 3 lower, and
+    // returns where it ends up and the guides shown while the mouse is still down.
+    fn drag_beside_neighbor(modifiers: ModifiersState) -> (Point, Vec<Line>) {
+        let mut dragged = None;
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let board = Board::new(Affine::IDENTITY, DotGrid::default(), cx);
+            let board = Board::with_smart_guides(board, true);
+            let mut board = Board::with_grid_snap(board, GridSnap::Edges);
+            for center in [Point::ZERO, Point::new(100., 3.)] {
+                let pin = PinWrapper::new(center, SizedBox::new(Size::new(40., 40.)), cx);
+                dragged.get_or_insert(board.add_child(pin));
+            }
+            Align::center(board)
+        });
+        let dragged = dragged.unwrap();
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+
+        // The board's origin is at the center of the window
+        test_runner.application.event_state.modifiers = modifiers.into();
+        test_runner.move_mouse(Point::new(200., 200.));
+        test_runner.press_mouse();
+        test_runner.move_mouse(Point::new(210., 200.));
+        test_runner.with_root(|root, cx| {
+            root.child.with_context(cx, |cx| {
+                let pin = root
+                    .child
+                    .children
+                    .iter()
+                    .find(|child| child.token() == dragged)
+                    .unwrap();
+                let center = pin.with_context(cx, |cx| pin.center(cx));
+                let guides = cx.read_state(|state: &BoardState, _| state.guides.clone());
+                (center, guides)
+            })
+        })
+    }
+
+    #[test]
+    fn dragged_pins_snap_to_neighbors_and_the_grid() {
+        let (center, guides) = drag_beside_neighbor(ModifiersState::empty());
+        // Its top lines up with the neighbor's and its left edge with the grid line at 0
+        assert_eq!(center, Point::new(20., 3.));
+        assert_eq!(guides, vec![Line::new((0., -17.), (120., -17.))]);
+
+        let (center, guides) = drag_beside_neighbor(ModifiersState::ALT);
+        assert_eq!(center, Point::new(10., 0.));
+        assert!(guides.is_empty());
+    }
+}
@@ -0,0 +1,508 @@
+use std::{collections::HashSet, mem};
+
+use vello::{
+    kurbo::{Affine, Circle, Point, Rect},
+    peniko::{Brush, Color},
+};
+
+use super::{
+    Blank, Board, BoardState, Connection, Direction, ExpandSubgraph, GroupSubgraph, PinPointer,
+    PinWrapper, Pinnable,
+};
+use crate::{
+    context_stack::{Context, DrawContext},
+    element::ElementPointer,
+    token::Token,
+};
+
+// Board space margin left around the grouped pins inside a subgraph's pin
+const SUBGRAPH_PADDING: f64 = 40.;
+const PORT_RADIUS: f64 = 4.;
+
+/// Wire crossing the boundary of a subgraph. On the outer board the wire ends at the port on the
+/// subgraph's pin, and inside the subgraph it continues to the inner pin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Port {
+    pub token: Token,
+    pub inner: Token,
+    // True if the wire leads from outside of the subgraph into it
+    pub incoming: bool,
+    pub direction: Option<Direction>,
+}
+
+impl Port {
+    // Side of the subgraph's pin the port sits on, facing the pin on the other end of the wire.
+    pub fn side(&self) -> Direction {
+        match (self.incoming, self.direction) {
+            (true, Some(direction)) => direction.opposite(),
+            (true, None) => Direction::Before,
+            (false, Some(direction)) => direction,
+            (false, None) => Direction::After,
+        }
+    }
+}
+
+// Board space positions of the ports spread out along the sides of a subgraph's pin.
+pub(super) fn port_positions(ports: &[Port], rect: Rect) -> Vec<(Token, Point)> {
+    let mut positions = Vec::with_capacity(ports.len());
+    for side in [
+        Direction::Above,
+        Direction::Below,
+        Direction::Before,
+        Direction::After,
+    ] {
+        let (start, end) = match side {
+            Direction::Above => (Point::new(rect.x0, rect.y0), Point::new(rect.x1, rect.y0)),
+            Direction::Below => (Point::new(rect.x0, rect.y1), Point::new(rect.x1, rect.y1)),
+            Direction::Before => (Point::new(rect.x0, rect.y0), Point::new(rect.x0, rect.y1)),
+            Direction::After => (Point::new(rect.x1, rect.y0), Point::new(rect.x1, rect.y1)),
+        };
+        let on_side: Vec<&Port> = ports.iter().filter(|port| port.side() == side).collect();
+        for (index, port) in on_side.iter().enumerate() {
+            let t = (index + 1) as f64 / (on_side.len() + 1) as f64;
+            positions.push((port.token, start.lerp(end, t)));
+        }
+    }
+    positions
+}
+
+impl Board {
+    // Empty board sharing this board's look, used to hold grouped pins.
+    fn new_subgraph(&self, cx: &Context) -> ElementPointer<Board> {
+        let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::TRANSPARENT), cx);
+        board.background = self.background.clone();
+        board.wire_color = self.wire_color;
+        board.selection_color = self.selection_color;
+        board.rulers = self.rulers;
+        board.grid_snap = self.grid_snap;
+        board.smart_guides = self.smart_guides;
+        board.level_of_detail = self.level_of_detail.clone();
+        board.navigation = self.navigation;
+        board
+    }
+
+    // Pin for a subgraph sized to fit the laid out pins, or None if none of them have been laid
+    // out yet.
+    pub(super) fn new_subgraph_pin(&self, pins: &[Token], cx: &Context) -> Option<PinPointer> {
+        let bounds = pins
+            .iter()
+            .filter_map(|pin| self.pin_rects.get(pin))
+            .copied()
+            .reduce(|bounds, rect| bounds.union(rect))?
+            .inflate(SUBGRAPH_PADDING, SUBGRAPH_PADDING);
+        let pin = PinWrapper::new_sized(bounds.center(), bounds.size(), self.new_subgraph(cx), cx);
+        Some(pin.map(|element| Box::new(element) as Box<dyn Pinnable>))
+    }
+
+    // Pin holding the subgraph which exposes the port.
+    pub(super) fn port_owner(&self, port: Token) -> Option<Token> {
+        self.children
+            .iter()
+            .find(|child| {
+                child
+                    .nested_board()
+                    .is_some_and(|board| board.ports.iter().any(|other| other.token == port))
+            })
+            .map(|child| child.token())
+    }
+
+    // Moves the pins into the subgraph held by the given pin. Wires between grouped pins move
+    // along with them and wires leaving the group are routed through ports. Ports the subgraph
+    // held before it was expanded are reused so that regrouping keeps their tokens.
+    pub(super) fn group_subgraph(
+        &mut self,
+        pins: &[Token],
+        mut subgraph: PinPointer,
+        cx: &Context,
+    ) -> Token {
+        let token = subgraph.token();
+        let offset = subgraph
+            .with_context(cx, |cx| subgraph.center(cx))
+            .to_vec2();
+        let grouped = |board: &Board, endpoint: Token| {
+            pins.contains(&board.port_owner(endpoint).unwrap_or(endpoint))
+        };
+        let mut previous_ports = subgraph
+            .nested_board_mut()
+            .map(|board| mem::take(&mut board.ports))
+            .unwrap_or_default();
+
+        let mut inner_connections = Vec::new();
+        let mut ports = Vec::new();
+        let mut outer_connections = Vec::new();
+        for connection in mem::take(&mut self.connections) {
+            let Connection {
+                from,
+                to,
+                direction,
+            } = connection;
+            match (grouped(self, from), grouped(self, to)) {
+                (true, true) => inner_connections.push(connection),
+                (false, false) => outer_connections.push(connection),
+                (inside, _) => {
+                    let port = Port {
+                        token: Token::new::<Port>(),
+                        inner: if inside { from } else { to },
+                        incoming: !inside,
+                        direction,
+                    };
+                    // Wires are visited in the same order as when the ports were first made
+                    let port = match previous_ports.iter().position(|previous| {
+                        (previous.inner, previous.incoming, previous.direction)
+                            == (port.inner, port.incoming, port.direction)
+                    }) {
+                        Some(index) => previous_ports.remove(index),
+                        None => port,
+                    };
+                    outer_connections.push(if inside {
+                        Connection::new(port.token, to, direction)
+                    } else {
+                        Connection::new(from, port.token, direction)
+                    });
+                    ports.push(port);
+                }
+            }
+        }
+        self.connections = outer_connections;
+
+        let (moved, kept): (Vec<_>, Vec<_>) = mem::take(&mut self.children)
+            .into_iter()
+            .partition(|child| pins.contains(&child.token()));
+        self.children = kept;
+        if let Some(board) = subgraph.nested_board_mut() {
+            for child in moved {
+                let center = child.with_context(cx, |cx| child.center(cx));
+                child.with_context(cx, |cx| child.set_center(center - offset, cx));
+                board.children.push(child);
+            }
+            board.connections.extend(inner_connections);
+            board.ports = ports;
+        }
+
+        self.children.push(subgraph);
+        cx.with_state(|state: &mut BoardState, _| {
+            state.selection = HashSet::from([token]);
+        });
+        token
+    }
+
+    // Moves the pins of the subgraph back onto this board, reconnecting wires which went through
+    // its ports. Returns the emptied subgraph pin, which keeps its ports for regrouping, along
+    // with the pins it held.
+    pub(super) fn expand_subgraph(
+        &mut self,
+        token: Token,
+        cx: &Context,
+    ) -> Option<(PinPointer, Vec<Token>)> {
+        let index = self
+            .children
+            .iter()
+            .position(|child| child.token() == token && child.nested_board().is_some())?;
+        let mut subgraph = self.children.remove(index);
+        let offset = subgraph
+            .with_context(cx, |cx| subgraph.center(cx))
+            .to_vec2();
+        let board = subgraph.nested_board_mut()?;
+        let children = mem::take(&mut board.children);
+        let inner_connections = mem::take(&mut board.connections);
+        let ports = board.ports.clone();
+
+        let endpoint = |token: Token| {
+            ports
+                .iter()
+                .find(|port| port.token == token)
+                .map_or(token, |port| port.inner)
+        };
+        for connection in self.connections.iter_mut() {
+            connection.from = endpoint(connection.from);
+            connection.to = endpoint(connection.to);
+        }
+        self.connections.extend(inner_connections);
+
+        let mut pins = Vec::with_capacity(children.len());
+        for child in children {
+            let center = child.with_context(cx, |cx| child.center(cx));
+            child.with_context(cx, |cx| child.set_center(center + offset, cx));
+            pins.push(child.token());
+            self.children.push(child);
+        }
+
+        cx.with_state(|state: &mut BoardState, _| {
+            state.selection = pins.iter().copied().collect();
+        });
+        Some((subgraph, pins))
+    }
+
+    pub(super) fn group_pins(&mut self, pins: Vec<Token>, cx: &Context) -> Option<Token> {
+        if pins.is_empty() {
+            return None;
+        }
+
+        let subgraph = self.new_subgraph_pin(&pins, cx)?;
+        let token = subgraph.token();
+        self.with_history(cx, |history, board| {
+            history.execute(GroupSubgraph::new(pins, subgraph), board, cx)
+        });
+        Some(token)
+    }
+
+    // Groups the selection into a subgraph, or expands the selected subgraphs.
+    pub(super) fn group_or_expand_selection(&mut self, expand: bool, cx: &Context) {
+        let selection = cx.read_state(|state: &BoardState, _| state.selection.clone());
+        let pins: Vec<Token> = self
+            .children
+            .iter()
+            .map(|child| child.token())
+            .filter(|token| selection.contains(token))
+            .collect();
+        if !expand {
+            self.group_pins(pins, cx);
+            return;
+        }
+
+        self.with_history(cx, |history, board| {
+            history.begin_transaction();
+            for pin in pins {
+                history.execute(ExpandSubgraph::new(pin), board, cx);
+            }
+            history.end_transaction();
+        });
+    }
+
+    // Follows ports down into subgraphs until reaching the pin the wire actually ends at.
+    fn resolve_endpoint(&self, endpoint: Token) -> Token {
+        self.children
+            .iter()
+            .filter_map(|child| child.nested_board())
+            .find_map(|board| {
+                let port = board.ports.iter().find(|port| port.token == endpoint)?;
+                Some(board.resolve_endpoint(port.inner))
+            })
+            .unwrap_or(endpoint)
+    }
+
+    /// Pins of the board's graph with subgraphs replaced by the pins inside of them.
+    pub fn nodes(&self) -> Vec<Token> {
+        self.children
+            .iter()
+            .flat_map(|child| match child.nested_board() {
+                Some(board) => board.nodes(),
+                None => vec![child.token()],
+            })
+            .collect()
+    }
+
+    /// Connections of the board's graph and of every subgraph in it, with wires through ports
+    /// connecting the pins on either side directly.
+    pub fn flattened_connections(&self) -> Vec<Connection> {
+        let mut connections: Vec<Connection> = self
+            .connections
+            .iter()
+            .map(|connection| {
+                Connection::new(
+                    self.resolve_endpoint(connection.from),
+                    self.resolve_endpoint(connection.to),
+                    connection.direction,
+                )
+            })
+            .collect();
+        for child in self.children.iter() {
+            if let Some(board) = child.nested_board() {
+                connections.extend(board.flattened_connections());
+            }
+        }
+        connections
+    }
+
+    pub fn ports(&self) -> &[Port] {
+        &self.ports
+    }
+
+    pub(super) fn draw_ports(&self, cx: &mut DrawContext) {
+        cx.set_fill_brush(Brush::Solid(self.wire_color.resolve(&cx.theme())));
+        for position in self.port_positions.values() {
+            cx.fill(&Circle::new(*position, PORT_RADIUS));
+        }
+    }
+}
+
+impl ElementPointer<Board> {
+    // Groups the selected pins into a subgraph shown in a nested board. Returns the pin holding
+    // the subgraph.
+    pub fn group_selection(&mut self, cx: &Context) -> Option<Token> {
+        let pins = self.selection(cx);
+        self.with_board_context(cx, |board, cx| board.group_pins(pins, cx))
+    }
+
+    pub fn expand_subgraph(&mut self, subgraph: Token, cx: &Context) {
+        self.execute(ExpandSubgraph::new(subgraph), cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::Size;
+
+    use super::*;
+    use crate::{
+        context_stack::LayoutContext, element::Element, history::History, test_runner::TestRunner,
+    };
+
+    struct Empty;
+
+    impl Element for Empty {
+        fn layout(&mut self, min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+            min
+        }
+    }
+
+    fn leaf(center: Point, cx: &Context) -> ElementPointer<PinWrapper<Empty>> {
+        PinWrapper::new_sized(center, Size::new(20., 20.), ElementPointer::new(Empty), cx)
+    }
+
+    fn subgraph_pin(center: Point, cx: &Context) -> PinPointer {
+        let board = Board::new(Affine::IDENTITY, Blank::new(Color::TRANSPARENT), cx);
+        PinWrapper::new_sized(center, Size::new(100., 100.), board, cx)
+            .map(|element| Box::new(element) as Box<dyn Pinnable>)
+    }
+
+    fn pins(board: &Board) -> Vec<Token> {
+        board.children.iter().map(|child| child.token()).collect()
+    }
+
+    fn nested(board: &Board, pin: Token) -> &Board {
+        board
+            .children
+            .iter()
+            .find(|child| child.token() == pin)
+            .and_then(|child| child.nested_board())
+            .unwrap()
+    }
+
+    fn wires(connections: Vec<Connection>) -> HashSet<(Token, Token)> {
+        connections
+            .into_iter()
+            .map(|connection| (connection.from, connection.to))
+            .collect()
+    }
+
+    // Runs the callback with a board holding the pins a, b and c wired a to b to c, along with
+    // a context for the board.
+    fn with_chain(callback: impl FnOnce(&mut Board, [Token; 3], &Context)) {
+        let test_runner = TestRunner::new(Size::new(100., 100.), |_| ElementPointer::new(Empty));
+        test_runner.with_root(|_, cx| {
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::TRANSPARENT), cx);
+            let a = board.add_child(leaf(Point::new(0., 0.), cx));
+            let b = board.add_child(leaf(Point::new(40., 0.), cx));
+            let c = board.add_child(leaf(Point::new(80., 0.), cx));
+            board.connect(a, b, Some(Direction::After));
+            board.connect(b, c, Some(Direction::After));
+            board.with_board_context(cx, |board, cx| callback(board, [a, b, c], cx));
+        });
+    }
+
+    #[test]
+    fn grouping_routes_wires_through_ports_and_expanding_restores_them() {
+        with_chain(|board, [a, b, c], cx| {
+            let subgraph = board.group_subgraph(&[a, b], subgraph_pin(Point::new(20., 0.), cx), cx);
+            assert_eq!(pins(board), vec![c, subgraph]);
+            assert_eq!(pins(nested(board, subgraph)), vec![a, b]);
+            let port = nested(board, subgraph).ports()[0];
+            assert_eq!(nested(board, subgraph).ports().len(), 1);
+            assert_eq!((port.inner, port.incoming), (b, false));
+            assert_eq!(
+                wires(board.connections.clone()),
+                HashSet::from([(port.token, c)])
+            );
+
+            assert_eq!(board.nodes(), vec![c, a, b]);
+            assert_eq!(
+                wires(board.flattened_connections()),
+                HashSet::from([(a, b), (b, c)])
+            );
+
+            // Pins keep their board space position across the move
+            let inner = &nested(board, subgraph).children[0];
+            assert_eq!(
+                inner.with_context(cx, |cx| inner.center(cx)),
+                Point::new(-20., 0.)
+            );
+
+            let (_, expanded) = board.expand_subgraph(subgraph, cx).unwrap();
+            assert_eq!(expanded, vec![a, b]);
+            assert_eq!(pins(board), vec![c, a, b]);
+            assert_eq!(
+                wires(board.connections.clone()),
+                HashSet::from([(a, b), (b, c)])
+            );
+            let a_pin = &board.children[1];
+            assert_eq!(a_pin.with_context(cx, |cx| a_pin.center(cx)), Point::ZERO);
+        });
+    }
+
+    #[test]
+    fn undoing_and_redoing_groups_keeps_their_tokens() {
+        with_chain(|board, [a, b, c], cx| {
+            let mut history = History::default();
+            let subgraph_pin = subgraph_pin(Point::new(20., 0.), cx);
+            let subgraph = subgraph_pin.token();
+            history.execute(GroupSubgraph::new(vec![a, b], subgraph_pin), board, cx);
+            let port = nested(board, subgraph).ports()[0].token;
+            let grouped = wires(board.flattened_connections());
+
+            assert!(history.undo(board, cx));
+            assert_eq!(pins(board), vec![c, a, b]);
+            assert!(history.redo(board, cx));
+            assert_eq!(pins(board), vec![c, subgraph]);
+            assert_eq!(nested(board, subgraph).ports()[0].token, port);
+            assert_eq!(wires(board.connections.clone()), HashSet::from([(port, c)]));
+            assert_eq!(wires(board.flattened_connections()), grouped);
+
+            history.execute(ExpandSubgraph::new(subgraph), board, cx);
+            assert_eq!(pins(board), vec![c, a, b]);
+            assert!(history.undo(board, cx));
+            assert_eq!(pins(board), vec![c, subgraph]);
+            assert_eq!(nested(board, subgraph).ports()[0].token, port);
+            assert!(history.redo(board, cx));
+            assert_eq!(pins(board), vec![c, a, b]);
+
+            // Undoing both steps leaves the board as it started
+            assert!(history.undo(board, cx));
+            assert!(history.undo(board, cx));
+            assert_eq!(pins(board), vec![c, a, b]);
+            assert_eq!(
+                wires(board.connections.clone()),
+                HashSet::from([(a, b), (b, c)])
+            );
+        });
+    }
+
+    #[test]
+    fn ports_spread_along_facing_sides() {
+        let port = |incoming, direction| Port {
+            token: Token::new::<Port>(),
+            inner: Token::new::<Port>(),
+            incoming,
+            direction,
+        };
+        let ports = [
+            port(true, Some(Direction::Below)),
+            port(true, Some(Direction::Below)),
+            port(false, Some(Direction::Below)),
+            port(false, None),
+        ];
+
+        let positions = port_positions(&ports, Rect::new(0., 0., 90., 60.));
+        let position = |port: &Port| {
+            positions
+                .iter()
+                .find(|(token, _)| *token == port.token)
+                .unwrap()
+                .1
+        };
+        assert_eq!(position(&ports[0]), Point::new(30., 0.));
+        assert_eq!(position(&ports[1]), Point::new(60., 0.));
+        assert_eq!(position(&ports[2]), Point::new(45., 60.));
+        assert_eq!(position(&ports[3]), Point::new(90., 30.));
+    }
+}
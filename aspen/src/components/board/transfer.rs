@@ -0,0 +1,441 @@
+use std::mem;
+
+use vello::kurbo::{Affine, Point, Rect};
+
+use super::{Board, BoardState, Connection, DetailLevel, PinPointer};
+use crate::{
+    context_stack::{Context, UpdateContext},
+    history::Command,
+    token::Token,
+    util::*,
+};
+
+// Pins being dragged on a board. Boards report their drops up to the boards they are nested in
+// until one of them contains the mouse, which then moves the pins to the board under the mouse.
+#[derive(Clone)]
+pub(crate) struct PinDrop {
+    pub pins: Vec<Token>,
+    pub released: bool,
+}
+
+// Maps board space of a board nested in a pin to screen space, given the screen transform of the
+// board holding the pin.
+fn nested_screen_transform(screen: Affine, pin_rect: Rect, board_transform: Affine) -> Affine {
+    screen * Affine::translate(pin_rect.center().to_vec2()) * board_transform
+}
+
+/// Moves pins from a nested board to another, cutting the wires attached to them on the board
+/// they leave. Boards are addressed by the path of pins holding them.
+pub struct TransferPins {
+    source: Vec<Token>,
+    target: Vec<Token>,
+    // Each pin along with its center on the source board and on the target board
+    pins: Vec<(Token, Point, Point)>,
+    // Held while the transfer is applied so that undoing it restores them
+    cut: Vec<Connection>,
+}
+
+impl TransferPins {
+    pub(super) fn new(
+        source: Vec<Token>,
+        target: Vec<Token>,
+        pins: Vec<(Token, Point, Point)>,
+    ) -> Self {
+        Self {
+            source,
+            target,
+            pins,
+            cut: Vec::new(),
+        }
+    }
+}
+
+impl Command<Board> for TransferPins {
+    fn apply(&mut self, board: &mut Board, cx: &Context) {
+        let pins: Vec<_> = self.pins.iter().map(|(pin, _, to)| (*pin, *to)).collect();
+        self.cut = board.move_pins(&self.source, &self.target, &pins, cx);
+    }
+
+    fn revert(&mut self, board: &mut Board, cx: &Context) {
+        let pins: Vec<_> = self
+            .pins
+            .iter()
+            .map(|(pin, from, _)| (*pin, *from))
+            .collect();
+        board.move_pins(&self.target, &self.source, &pins, cx);
+        let cut = mem::take(&mut self.cut);
+        board.with_board_at(&self.source, cx, |board, _| board.connections.extend(cut));
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of_val(self)
+            + mem::size_of_val(self.source.as_slice())
+            + mem::size_of_val(self.target.as_slice())
+            + mem::size_of_val(self.pins.as_slice())
+            + mem::size_of_val(self.cut.as_slice())
+    }
+}
+
+impl Board {
+    // Path of pins leading from this board to the board holding the pin.
+    pub(super) fn path_to_pin(&self, pin: Token) -> Option<Vec<Token>> {
+        if self.children.iter().any(|child| child.token() == pin) {
+            return Some(Vec::new());
+        }
+
+        self.children.iter().find_map(|child| {
+            let mut path = child.nested_board()?.path_to_pin(pin)?;
+            path.insert(0, child.token());
+            Some(path)
+        })
+    }
+
+    // Path to the innermost board fully drawn under the point, skipping boards held by the given
+    // pins so that pins can't be dropped into themselves.
+    pub(super) fn path_to_board_at(
+        &self,
+        point: Point,
+        screen: Affine,
+        region: Rect,
+        excluded: &[Token],
+        cx: &Context,
+    ) -> Vec<Token> {
+        let zoom = screen.unskewed_scale().length() / 2.0f64.sqrt();
+        // Later pins are drawn on top
+        for child in self.children.iter().rev() {
+            if excluded.contains(&child.token()) {
+                continue;
+            }
+            let Some((board, pin_rect)) = child
+                .nested_board()
+                .zip(self.pin_rects.get(&child.token()).copied())
+            else {
+                continue;
+            };
+
+            let pin_region = screen.transform_rect_bbox(pin_rect).intersect(region);
+            if self.level_of_detail.level(zoom) != DetailLevel::Full || !pin_region.contains(point)
+            {
+                continue;
+            }
+
+            let board_transform = board.read_state(cx, |state: &BoardState, _| state.transform);
+            let mut path = vec![child.token()];
+            path.extend(board.path_to_board_at(
+                point,
+                nested_screen_transform(screen, pin_rect, board_transform),
+                pin_region,
+                excluded,
+                cx,
+            ));
+            return path;
+        }
+        Vec::new()
+    }
+
+    pub(super) fn screen_transform_at(
+        &self,
+        path: &[Token],
+        screen: Affine,
+        cx: &Context,
+    ) -> Option<Affine> {
+        let Some((pin, rest)) = path.split_first() else {
+            return Some(screen);
+        };
+
+        let child = self.children.iter().find(|child| child.token() == *pin)?;
+        let board = child.nested_board()?;
+        let pin_rect = self.pin_rects.get(&child.token())?;
+        let board_transform = board.read_state(cx, |state: &BoardState, _| state.transform);
+        board.screen_transform_at(
+            rest,
+            nested_screen_transform(screen, *pin_rect, board_transform),
+            cx,
+        )
+    }
+
+    // Runs the callback with the board at the end of the path along with a context for it.
+    fn with_board_at<Result>(
+        &mut self,
+        path: &[Token],
+        cx: &Context,
+        callback: impl FnOnce(&mut Board, &Context) -> Result,
+    ) -> Option<Result> {
+        let Some((pin, rest)) = path.split_first() else {
+            return Some(callback(self, cx));
+        };
+
+        let board = self
+            .children
+            .iter_mut()
+            .find(|child| child.token() == *pin)?
+            .nested_board_mut()?;
+        board.with_board_context(cx, |board, cx| board.with_board_at(rest, cx, callback))
+    }
+
+    // Takes the pins out of the board, returning them along with the wires attached to them.
+    fn remove_pins(&mut self, pins: &[Token], cx: &Context) -> (Vec<PinPointer>, Vec<Connection>) {
+        let attached = |board: &Board, endpoint: Token| {
+            pins.contains(&board.port_owner(endpoint).unwrap_or(endpoint))
+        };
+        let (cut, kept): (Vec<_>, Vec<_>) =
+            mem::take(&mut self.connections)
+                .into_iter()
+                .partition(|connection| {
+                    attached(self, connection.from) || attached(self, connection.to)
+                });
+        self.connections = kept;
+        let (removed, kept): (Vec<_>, Vec<_>) = self
+            .children
+            .drain(..)
+            .partition(|child| pins.contains(&child.token()));
+        self.children = kept;
+        cx.with_state(|state: &mut BoardState, _| {
+            state.selection.retain(|token| !pins.contains(token));
+        });
+        (removed, cut)
+    }
+
+    // Moves the pins from the board at one path to the board at the other, placing them at the
+    // given centers and selecting them. Returns the wires cut on the board they left.
+    fn move_pins(
+        &mut self,
+        source: &[Token],
+        target: &[Token],
+        pins: &[(Token, Point)],
+        cx: &Context,
+    ) -> Vec<Connection> {
+        // Pins are only taken out once the target is known to exist so that they can't get lost
+        if self.board_at(target).is_none() {
+            return Vec::new();
+        }
+        let tokens: Vec<Token> = pins.iter().map(|(pin, _)| *pin).collect();
+        let Some((removed, cut)) =
+            self.with_board_at(source, cx, |board, cx| board.remove_pins(&tokens, cx))
+        else {
+            return Vec::new();
+        };
+
+        self.with_board_at(target, cx, |board, cx| {
+            board.children.extend(removed);
+            for (pin, center) in pins {
+                board.set_pin_center(*pin, *center, cx);
+            }
+            cx.with_state(|state: &mut BoardState, _| {
+                state.selection = tokens.iter().copied().collect();
+            });
+        });
+        cut
+    }
+
+    // Moves or highlights the board under the mouse for drops reported by this board or the
+    // boards nested in it. Drops outside of this board are passed on to the board it is nested in.
+    pub(super) fn drop_pins(&mut self, cx: &mut UpdateContext) {
+        let mut drops: Vec<PinDrop> = cx
+            .with_state(|state: &mut BoardState, _| state.pin_drop.take())
+            .into_iter()
+            .collect();
+        for child in self.children.iter() {
+            // Only take drops which are there, since taking state invalidates the board's layout
+            let Some(board) = child.nested_board() else {
+                continue;
+            };
+            if board.read_state(cx, |state: &BoardState, _| state.pin_drop.is_some()) {
+                drops.extend(
+                    board.with_state(cx, |state: &mut BoardState, _| state.pin_drop.take()),
+                );
+            }
+        }
+
+        let (screen, region) = cx
+            .with_state(|state: &mut BoardState, _| (state.screen_transform, state.screen_region));
+        for drop in drops {
+            let Some(point) = cx
+                .actual_mouse_position()
+                .filter(|point| region.contains(*point))
+            else {
+                cx.with_state(|state: &mut BoardState, _| state.pin_drop = Some(drop));
+                continue;
+            };
+
+            let Some(source) = drop.pins.first().and_then(|pin| self.path_to_pin(*pin)) else {
+                continue;
+            };
+            let target = self.path_to_board_at(point, screen, region, &drop.pins, cx);
+            if source == target {
+                continue;
+            }
+
+            if !drop.released {
+                self.with_board_at(&target, cx, |_, cx| {
+                    cx.with_state(|state: &mut BoardState, _| state.drop_highlight = true)
+                });
+                continue;
+            }
+
+            let Some((source_screen, target_screen)) = self
+                .screen_transform_at(&source, screen, cx)
+                .zip(self.screen_transform_at(&target, screen, cx))
+            else {
+                continue;
+            };
+            let to_target = target_screen.inverse() * source_screen;
+
+            let pins = self
+                .with_board_at(&source, cx, |board, cx| {
+                    board
+                        .children
+                        .iter()
+                        .filter(|child| drop.pins.contains(&child.token()))
+                        .map(|child| {
+                            let center = child.with_context(cx, |cx| child.center(cx));
+                            (child.token(), center, to_target * center)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            self.with_history(cx, |history, board| {
+                history.execute(TransferPins::new(source, target, pins), board, cx);
+            });
+            cx.request_redraw();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::{kurbo::Size, peniko::Color};
+    use winit::{
+        keyboard::{Key, ModifiersState, SmolStr},
+        window::{Cursor, CursorIcon},
+    };
+
+    use super::*;
+    use crate::{
+        components::board::{Blank, PinWrapper},
+        context_stack::LayoutContext,
+        element::{Element, ElementPointer},
+        test_runner::TestRunner,
+    };
+
+    struct Empty;
+
+    impl Element for Empty {
+        fn layout(&mut self, min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+            min
+        }
+    }
+
+    fn nested_board(center: Point, size: Size, cx: &Context) -> ElementPointer<PinWrapper<Board>> {
+        let board = Board::new(Affine::IDENTITY, Blank::new(Color::WHITE), cx);
+        PinWrapper::new_sized(center, size, board, cx)
+    }
+
+    fn leaf(center: Point, cx: &Context) -> ElementPointer<PinWrapper<Empty>> {
+        PinWrapper::new_sized(center, Size::new(40., 40.), ElementPointer::new(Empty), cx)
+    }
+
+    // Root board filling a 400 by 400 window holding a nested board which covers the middle of
+    // the window from 100 to 300, along with a pin on each of them. Returns the tokens of the
+    // nested board's pin, the root's pin and the nested board's own pin.
+    fn nested_boards() -> (TestRunner<Board>, Token, Token, Token) {
+        let mut tokens = None;
+        let mut test_runner = TestRunner::new(Size::new(400., 400.), |cx| {
+            let mut outer = nested_board(Point::ZERO, Size::new(200., 200.), cx);
+            let inner = leaf(Point::new(-50., -50.), cx);
+            let pin = leaf(Point::new(-150., -150.), cx);
+            tokens = Some((outer.token(), pin.token(), inner.token()));
+            outer.child.add_child(inner);
+
+            let mut board = Board::new(Affine::IDENTITY, Blank::new(Color::BLACK), cx);
+            board.add_child(outer);
+            board.add_child(pin);
+            board
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        let (outer, pin, inner) = tokens.unwrap();
+        (test_runner, outer, pin, inner)
+    }
+
+    fn drag(test_runner: &mut TestRunner<Board>, from: Point, to: Point) {
+        test_runner.move_mouse(from);
+        test_runner.press_mouse();
+        test_runner.move_mouse(to);
+        test_runner.release_mouse();
+    }
+
+    fn pins(board: &Board) -> Vec<Token> {
+        board.children.iter().map(|child| child.token()).collect()
+    }
+
+    fn nested_pins(board: &Board, outer: Token) -> Vec<Token> {
+        board.board_at(&[outer]).map(pins).unwrap_or_default()
+    }
+
+    #[test]
+    fn pins_dropped_on_nested_boards_move_into_them() {
+        let (mut test_runner, outer, pin, inner) = nested_boards();
+        drag(
+            &mut test_runner,
+            Point::new(50., 50.),
+            Point::new(250., 250.),
+        );
+
+        test_runner.with_root(|board, cx| {
+            assert_eq!(pins(board), vec![outer]);
+            assert_eq!(nested_pins(board, outer), vec![inner, pin]);
+            // The nested board's origin is drawn at the middle of the window
+            let moved = &board.board_at(&[outer]).unwrap().children[1];
+            let center = moved.with_context(cx, |cx| moved.center(cx));
+            assert!((center - Point::new(50., 50.)).hypot() < 20.);
+        });
+    }
+
+    #[test]
+    fn pins_dropped_outside_nested_boards_pass_up() {
+        let (mut test_runner, outer, pin, inner) = nested_boards();
+        drag(
+            &mut test_runner,
+            Point::new(150., 150.),
+            Point::new(350., 350.),
+        );
+
+        test_runner.with_root(|board, _| {
+            assert_eq!(pins(board), vec![outer, pin, inner]);
+            assert!(nested_pins(board, outer).is_empty());
+        });
+    }
+
+    #[test]
+    fn transfers_are_undone_and_redone() {
+        let (mut test_runner, outer, pin, inner) = nested_boards();
+        drag(
+            &mut test_runner,
+            Point::new(50., 50.),
+            Point::new(250., 250.),
+        );
+        test_runner.with_root(|board, _| {
+            assert_eq!(nested_pins(board, outer), vec![inner, pin]);
+        });
+
+        test_runner.application.event_state.modifiers = ModifiersState::CONTROL.into();
+        test_runner.input_key(Key::Character(SmolStr::new_inline("z")));
+        test_runner.with_root(|board, cx| {
+            assert_eq!(pins(board), vec![outer, pin]);
+            assert_eq!(nested_pins(board, outer), vec![inner]);
+            let moved = &board.children[1];
+            let center = moved.with_context(cx, |cx| moved.center(cx));
+            assert!((center - Point::new(50., 50.)).hypot() < 20.);
+        });
+
+        test_runner.application.event_state.modifiers =
+            (ModifiersState::CONTROL | ModifiersState::SHIFT).into();
+        test_runner.input_key(Key::Character(SmolStr::new_inline("z")));
+        test_runner.with_root(|board, _| {
+            assert_eq!(pins(board), vec![outer]);
+            assert_eq!(nested_pins(board, outer), vec![inner, pin]);
+        });
+    }
+}
@@ -6,6 +6,7 @@ use vello::{
 use crate::{
     context_stack::{DrawContext, LayoutContext, UpdateContext},
     element::{Element, ElementPointer},
+    theme::ThemeColor,
     token::Token,
 };
 
@@ -13,32 +14,38 @@ pub struct Border<Child: Element> {
     pub child: ElementPointer<Child>,
 
     pub padding: f64,
-    pub stroke: Brush,
-    pub fill: Brush,
+    pub stroke: ThemeColor,
+    pub fill: ThemeColor,
     pub thickness: f64,
     pub radius: f64,
     pub background_separation: f64,
 }
 
 impl<Child: Element> Border<Child> {
-    pub fn new(
-        child: ElementPointer<Child>,
-        padding: f64,
-        stroke: Brush,
-        fill: Brush,
-    ) -> ElementPointer<Self> {
+    // Border in the theme's colors for raised surfaces
+    pub fn new(child: ElementPointer<Child>, padding: f64) -> ElementPointer<Self> {
         ElementPointer::new(Self {
             child,
 
             padding,
-            stroke,
-            fill,
+            stroke: ThemeColor::Background(5),
+            fill: ThemeColor::Background(1),
             thickness: 2.,
             radius: 5.,
             background_separation: 2.,
         })
     }
 
+    pub fn with_colors(
+        mut this: ElementPointer<Self>,
+        stroke: impl Into<ThemeColor>,
+        fill: impl Into<ThemeColor>,
+    ) -> ElementPointer<Self> {
+        this.stroke = stroke.into();
+        this.fill = fill.into();
+        this
+    }
+
     pub fn with_thickness(mut this: ElementPointer<Self>, thickness: f64) -> ElementPointer<Self> {
         this.thickness = thickness;
         this
@@ -84,8 +91,9 @@ impl<Child: Element> Element for Border<Child> {
             );
         }
 
-        cx.set_fill_brush(self.fill.clone());
-        cx.set_stroke_brush(self.stroke.clone());
+        let theme = cx.theme();
+        cx.set_fill_brush(Brush::Solid(self.fill.resolve(&theme)));
+        cx.set_stroke_brush(Brush::Solid(self.stroke.resolve(&theme)));
         cx.set_stroke_style(Stroke::new(self.thickness));
         cx.stroked_fill(&region);
 
@@ -98,11 +106,11 @@ impl<Child: Element> Element for Border<Child> {
 }
 
 pub trait ElementBorderExt<This: Element + Sized> {
-    fn with_border(self, padding: f64, stroke: Brush, fill: Brush) -> ElementPointer<Border<This>>;
+    fn with_border(self, padding: f64) -> ElementPointer<Border<This>>;
 }
 
 impl<This: Element + Sized> ElementBorderExt<This> for ElementPointer<This> {
-    fn with_border(self, padding: f64, stroke: Brush, fill: Brush) -> ElementPointer<Border<This>> {
-        Border::new(self, padding, stroke, fill)
+    fn with_border(self, padding: f64) -> ElementPointer<Border<This>> {
+        Border::new(self, padding)
     }
 }
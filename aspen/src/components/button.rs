@@ -1,22 +1,21 @@
-use std::{cell::RefCell, rc::Rc, time::Instant};
+use std::{cell::RefCell, rc::Rc, time::Duration};
 
-use vello::{
-    kurbo::Size,
-    peniko::{Brush, Color},
-};
+use vello::{kurbo::Size, peniko::Brush};
 
 use crate::{
+    animation::{Animated, Easing},
     context_stack::{DrawContext, EventContext, LayoutContext, UpdateContext},
     element::{Element, ElementPointer},
+    theme::ThemeColor,
     util::Mixable,
 };
 
-const ANIM_LENGTH: f64 = 0.1;
+const ANIM_LENGTH: Duration = Duration::from_millis(100);
 
 pub struct Button {
     size: Size,
-    idle_background: Color,
-    hover_background: Color,
+    idle_background: ThemeColor,
+    hover_background: ThemeColor,
     draw_contents: Box<dyn Fn(&mut DrawContext)>,
 
     state: Rc<RefCell<ButtonState>>,
@@ -24,29 +23,28 @@ pub struct Button {
 
 struct ButtonState {
     on_clicked: Box<dyn Fn(&mut EventContext)>,
-    hover_start: Instant,
-    hover_t: f64,
+    // 0 when idle and 1 when hovered
+    hover: Animated<f64>,
     hovered: bool,
 }
 
 impl Button {
     pub fn new<D: Fn(&mut DrawContext) + 'static, C: Fn(&mut EventContext) + 'static>(
         size: Size,
-        idle_background: Color,
-        hover_background: Color,
+        idle_background: impl Into<ThemeColor>,
+        hover_background: impl Into<ThemeColor>,
         draw_contents: D,
         on_clicked: C,
     ) -> ElementPointer<Self> {
         ElementPointer::new(Self {
             size,
-            idle_background,
-            hover_background,
+            idle_background: idle_background.into(),
+            hover_background: hover_background.into(),
             draw_contents: Box::new(draw_contents),
 
             state: Rc::new(RefCell::new(ButtonState {
                 on_clicked: Box::new(on_clicked),
-                hover_start: Instant::now(),
-                hover_t: 1.5,
+                hover: Animated::new(0.),
                 hovered: false,
             })),
         })
@@ -55,12 +53,7 @@ impl Button {
 
 impl Element for Button {
     fn update(&mut self, cx: &mut UpdateContext) {
-        let mut state = self.state.borrow_mut();
-        let was_drawing = state.hover_t < 1.5;
-        state.hover_t = state.hover_start.elapsed().as_secs_f64() / ANIM_LENGTH;
-        if was_drawing || state.hover_t < 1.5 {
-            cx.request_redraw();
-        }
+        self.state.borrow_mut().hover.update(cx);
     }
 
     fn layout(&mut self, _min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
@@ -72,21 +65,25 @@ impl Element for Button {
         cx.mouse_region(region)
             .on_hover({
                 let state = self.state.clone();
-                move |_cx| {
+                move |cx| {
                     let mut state = state.borrow_mut();
                     if !state.hovered {
                         state.hovered = true;
-                        state.hover_start = Instant::now();
+                        let now = cx.frame_time();
+                        state.hover.animate_to(1., ANIM_LENGTH, Easing::Linear, now);
+                        cx.request_redraw();
                     }
                 }
             })
             .on_leave({
                 let state = self.state.clone();
-                move |_cx| {
+                move |cx| {
                     let mut state = state.borrow_mut();
                     if state.hovered {
                         state.hovered = false;
-                        state.hover_start = Instant::now();
+                        let now = cx.frame_time();
+                        state.hover.animate_to(0., ANIM_LENGTH, Easing::Linear, now);
+                        cx.request_redraw();
                     }
                 }
             })
@@ -98,24 +95,12 @@ impl Element for Button {
                 }
             });
 
-        let state = self.state.borrow();
-        if cx
-            .mouse_position()
-            .map_or(false, |pos| region.contains(pos))
-        {
-            cx.set_fill_brush(Brush::Solid(
-                self.idle_background
-                    .mix(&self.hover_background, state.hover_t),
-            ));
-
-            cx.fill(&region);
-        } else {
-            cx.set_fill_brush(Brush::Solid(
-                self.hover_background
-                    .mix(&self.idle_background, state.hover_t),
-            ));
-            cx.fill(&region);
-        }
+        let hover = self.state.borrow().hover.value();
+        let theme = cx.theme();
+        let idle_background = self.idle_background.resolve(&theme);
+        let hover_background = self.hover_background.resolve(&theme);
+        cx.set_fill_brush(Brush::Solid(idle_background.mix(&hover_background, hover)));
+        cx.fill(&region);
 
         (self.draw_contents)(cx);
     }
@@ -1,4 +1,5 @@
 use core::num::NonZeroUsize;
+use std::{any::Any, mem};
 
 use parley::{
     editing::{Cursor, Selection},
@@ -18,13 +19,16 @@ use winit::{
 use crate::{
     context_stack::{Context, DrawContext, LayoutContext, UpdateContext},
     element::{Element, ElementPointer},
+    history::{Command, History},
+    theme::ThemeColor,
 };
 
 pub struct Editor {
-    text_stroke: Brush,
-    selection_fill: Brush,
-    cursor_stroke: Brush,
-    size: f32,
+    text_stroke: ThemeColor,
+    selection_fill: ThemeColor,
+    cursor_stroke: ThemeColor,
+    // Falls back to the theme's text size
+    size: Option<f32>,
 }
 
 #[derive(Default)]
@@ -33,15 +37,131 @@ pub struct EditorState {
     previous_max_width: f64,
     buffer: String,
     selection: Selection,
+    history: History<EditorState>,
+}
+
+/// Replacement of a range of the buffer, remembering the selection on either side of it.
+struct ReplaceText {
+    start: usize,
+    removed: String,
+    inserted: String,
+    selection_before: (usize, usize),
+    selection_after: (usize, usize),
+}
+
+impl ReplaceText {
+    // Finds the single replacement which turns the old buffer into the new one.
+    fn between(
+        old: &str,
+        new: &str,
+        selection_before: (usize, usize),
+        selection_after: (usize, usize),
+    ) -> Self {
+        let mut prefix = old
+            .bytes()
+            .zip(new.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+
+        let max_suffix = old.len().min(new.len()) - prefix;
+        let mut suffix = old
+            .bytes()
+            .rev()
+            .zip(new.bytes().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(old.len() - suffix)
+            || !new.is_char_boundary(new.len() - suffix)
+        {
+            suffix -= 1;
+        }
+
+        Self {
+            start: prefix,
+            removed: old[prefix..old.len() - suffix].to_string(),
+            inserted: new[prefix..new.len() - suffix].to_string(),
+            selection_before,
+            selection_after,
+        }
+    }
+}
+
+impl Command<EditorState> for ReplaceText {
+    fn apply(&mut self, state: &mut EditorState, cx: &Context) {
+        let range = self.start..self.start + self.removed.len();
+        state.buffer.replace_range(range, &self.inserted);
+        state.update_layout(cx);
+        state.select_byte_range(self.selection_after.0, self.selection_after.1);
+    }
+
+    fn revert(&mut self, state: &mut EditorState, cx: &Context) {
+        let range = self.start..self.start + self.inserted.len();
+        state.buffer.replace_range(range, &self.removed);
+        state.update_layout(cx);
+        state.select_byte_range(self.selection_before.0, self.selection_before.1);
+    }
+
+    // Typing and deleting runs of characters coalesce until a line break.
+    fn merge(&mut self, next: &dyn Any) -> bool {
+        let Some(next) = next.downcast_ref::<ReplaceText>() else {
+            return false;
+        };
+        if self.inserted.contains('\n') || next.inserted.contains('\n') {
+            return false;
+        }
+
+        let typing = self.removed.is_empty()
+            && next.removed.is_empty()
+            && next.start == self.start + self.inserted.len();
+        let backspacing = self.inserted.is_empty()
+            && next.inserted.is_empty()
+            && next.start + next.removed.len() == self.start;
+        let deleting = self.inserted.is_empty()
+            && next.inserted.is_empty()
+            && next.start == self.start;
+
+        if typing {
+            self.inserted.push_str(&next.inserted);
+        } else if backspacing {
+            self.start = next.start;
+            self.removed.insert_str(0, &next.removed);
+        } else if deleting {
+            self.removed.push_str(&next.removed);
+        } else {
+            return false;
+        }
+        self.selection_after = next.selection_after;
+        true
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of_val(self) + self.removed.len() + self.inserted.len()
+    }
 }
 
 impl Editor {
-    pub fn new<'a>(
+    // Editor in the theme's text size and colors
+    pub fn new<'a>(text: String, cx: &mut Context<'a>) -> ElementPointer<Self> {
+        Self::new_styled(
+            text,
+            None,
+            ThemeColor::Foreground,
+            ThemeColor::Selection,
+            ThemeColor::Foreground,
+            cx,
+        )
+    }
+
+    pub fn new_styled<'a>(
         text: String,
-        size: f32,
-        text_stroke: Brush,
-        selection_fill: Brush,
-        cursor_stroke: Brush,
+        size: Option<f32>,
+        text_stroke: impl Into<ThemeColor>,
+        selection_fill: impl Into<ThemeColor>,
+        cursor_stroke: impl Into<ThemeColor>,
         cx: &mut Context<'a>,
     ) -> ElementPointer<Self> {
         let mut state = EditorState {
@@ -49,23 +169,38 @@ impl Editor {
                 previous_max_width: f64::INFINITY,
                 buffer: text,
                 selection: Default::default(),
+                history: History::default(),
             };
 
-        cx.push_default_text_style(StyleProperty::FontSize(size));
-        cx.push_default_text_style(StyleProperty::Brush(text_stroke.clone()));
+        let editor = Self {
+            text_stroke: text_stroke.into(),
+            selection_fill: selection_fill.into(),
+            cursor_stroke: cursor_stroke.into(),
+            size,
+        };
+        editor.push_text_styles(cx);
         state.update_layout(cx);
 
-        ElementPointer::new(Self {
-            text_stroke,
-            selection_fill,
-            cursor_stroke,
-            size,
-        })
-        .insert_state(
+        ElementPointer::new(editor).insert_state(
             state,
             cx,
         )
     }
+
+    fn push_text_styles(&self, cx: &mut Context) {
+        let theme = cx.theme();
+        let size = self.size.unwrap_or(theme.text_size);
+        cx.push_default_text_style(StyleProperty::FontSize(size));
+        cx.push_default_text_style(StyleProperty::Brush(Brush::Solid(
+            self.text_stroke.resolve(&theme),
+        )));
+    }
+}
+
+impl ElementPointer<Editor> {
+    pub fn text(&self, cx: &Context) -> String {
+        self.read_state(cx, |state: &EditorState, _| state.buffer.clone())
+    }
 }
 
 impl EditorState {
@@ -509,8 +644,57 @@ impl EditorState {
         self.selection = self.selection.refresh(&self.layout);
     }
 
+    fn selection_range(&self) -> (usize, usize) {
+        (self.selection.anchor().index(), self.selection.focus().index())
+    }
+
+    pub fn undo(&mut self, cx: &Context) -> bool {
+        let mut history = mem::take(&mut self.history);
+        let undone = history.undo(self, cx);
+        self.history = history;
+        undone
+    }
+
+    pub fn redo(&mut self, cx: &Context) -> bool {
+        let mut history = mem::take(&mut self.history);
+        let redone = history.redo(self, cx);
+        self.history = history;
+        redone
+    }
+
+    // Handles the key and records any change it made to the buffer so it can be undone.
     pub fn handle_pressed_key(&mut self, key: Key, modifiers: Modifiers, cx: &Context) -> bool {
         let state = modifiers.state();
+        if let Key::Character(c) = &key {
+            if state.control_key() && c.eq_ignore_ascii_case("z") {
+                if state.shift_key() {
+                    self.redo(cx);
+                } else {
+                    self.undo(cx);
+                }
+                return true;
+            }
+        }
+
+        let buffer_before = self.buffer.clone();
+        let selection_before = self.selection_range();
+        let handled = self.apply_pressed_key(key, modifiers, cx);
+        if self.buffer != buffer_before {
+            self.history.record(ReplaceText::between(
+                &buffer_before,
+                &self.buffer,
+                selection_before,
+                self.selection_range(),
+            ));
+        } else if self.selection_range() != selection_before {
+            // Moving the cursor starts a new undo step
+            self.history.seal();
+        }
+        handled
+    }
+
+    fn apply_pressed_key(&mut self, key: Key, modifiers: Modifiers, cx: &Context) -> bool {
+        let state = modifiers.state();
 
         let action_mod = state.control_key();
         let shift = state.shift_key();
@@ -663,12 +847,20 @@ impl EditorState {
 
 impl Element for Editor {
     fn update(&mut self, cx: &mut UpdateContext) {
-        if !cx.is_directly_focused() {
+        let theme_changed = cx.theme_changed();
+        if !theme_changed && !cx.is_directly_focused() {
             return;
         }
 
-        cx.push_default_text_style(StyleProperty::FontSize(self.size));
-        cx.push_default_text_style(StyleProperty::Brush(self.text_stroke.clone()));
+        self.push_text_styles(cx);
+        if theme_changed {
+            // The text's size and color are baked into its shaped layout
+            cx.with_state(|state: &mut EditorState, cx| state.update_layout(cx));
+            cx.request_redraw();
+            if !cx.is_directly_focused() {
+                return;
+            }
+        }
 
         let mut request_redraw = false;
         cx.with_state(|state: &mut EditorState, cx| {
@@ -727,19 +919,20 @@ impl Element for Editor {
             });
         }
 
+        let theme = cx.theme();
         cx.with_state(|state: &mut EditorState, cx| {
             if cx.is_directly_focused() {
                 state.selection_geometry_with(|rect, _| {
-                    cx.set_fill_brush(self.selection_fill.clone());
+                    cx.set_fill_brush(Brush::Solid(self.selection_fill.resolve(&theme)));
                     cx.fill(&Rect::new(rect.x0, rect.y0, rect.x1, rect.y1));
                 });
 
                 let cursor = state.cursor_geometry(1.0);
-                cx.set_fill_brush(self.cursor_stroke.clone());
+                cx.set_fill_brush(Brush::Solid(self.cursor_stroke.resolve(&theme)));
                 cx.fill(&Rect::new(cursor.x0, cursor.y0, cursor.x1, cursor.y1));
             }
 
-            cx.set_stroke_brush(self.text_stroke.clone());
+            cx.set_stroke_brush(Brush::Solid(self.text_stroke.resolve(&theme)));
             let top_left = region.origin();
             cx.draw_layout_at(&state.layout, top_left);
         });
@@ -750,6 +943,7 @@ impl Element for Editor {
 #[cfg(test)]
 mod tests {
     use vello::peniko::Color;
+    use winit::keyboard::{ModifiersState, SmolStr};
 
     use super::*;
     use crate::{
@@ -758,12 +952,12 @@ mod tests {
 
     #[test]
     fn editor_inserts_text_at_end() {
-        let mut test_runner = TestRunner::new(Size::new(100., 100.), |cx| Editor::new(
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |cx| Editor::new_styled(
                 "Hello".to_string(),
-                12.0,
-                Brush::Solid(Color::BLACK),
-                Brush::Solid(Color::from_rgb8(125, 125, 125)),
-                Brush::Solid(Color::BLACK),
+                Some(12.0),
+                Color::BLACK,
+                Color::from_rgb8(125, 125, 125),
+                Color::BLACK,
                 cx
             ));
         test_runner.expect_cursor_icon(winit::window::Cursor::Icon(CursorIcon::Default));
@@ -784,4 +978,52 @@ mod tests {
         });
 
     }
+
+    #[test]
+    fn editor_undoes_and_redoes_typing() {
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |cx| Editor::new_styled(
+                "Hello".to_string(),
+                Some(12.0),
+                Color::BLACK,
+                Color::from_rgb8(125, 125, 125),
+                Color::BLACK,
+                cx
+            ));
+        test_runner.expect_cursor_icon(winit::window::Cursor::Icon(CursorIcon::Default));
+        test_runner.with_root(|_, cx| {
+            cx.focus();
+        });
+        test_runner.input_key(Key::Named(NamedKey::End));
+        test_runner.input_text(" World!");
+
+        test_runner.with_root(|_, cx| {
+            cx.with_state(|state: &mut EditorState, cx| {
+                assert!(state.undo(cx));
+                assert_eq!(state.buffer, "Hello");
+                assert_eq!(state.selection.text_range(), 5..5);
+                assert!(!state.undo(cx));
+
+                assert!(state.redo(cx));
+                assert_eq!(state.buffer, "Hello World!");
+                assert!(!state.redo(cx));
+            });
+        });
+
+        // The same steps through the keyboard shortcuts
+        test_runner.application.event_state.modifiers = ModifiersState::CONTROL.into();
+        test_runner.input_key(Key::Character(SmolStr::new_inline("z")));
+        test_runner.with_root(|_, cx| {
+            cx.with_state(|state: &mut EditorState, _| {
+                assert_eq!(state.buffer, "Hello");
+            });
+        });
+        test_runner.application.event_state.modifiers =
+            (ModifiersState::CONTROL | ModifiersState::SHIFT).into();
+        test_runner.input_key(Key::Character(SmolStr::new_inline("z")));
+        test_runner.with_root(|_, cx| {
+            cx.with_state(|state: &mut EditorState, _| {
+                assert_eq!(state.buffer, "Hello World!");
+            });
+        });
+    }
 }
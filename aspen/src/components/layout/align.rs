@@ -0,0 +1,92 @@
+use vello::kurbo::{Affine, Size};
+
+use super::Alignment;
+use crate::{
+    context_stack::{DrawContext, LayoutContext, UpdateContext},
+    element::{Element, ElementPointer},
+    token::Token,
+};
+
+/// Takes up all of the space available and places a child within it.
+pub struct Align<Child: Element> {
+    pub child: ElementPointer<Child>,
+    pub alignment: Alignment,
+}
+
+impl<Child: Element> Align<Child> {
+    pub fn new(child: ElementPointer<Child>, alignment: Alignment) -> ElementPointer<Self> {
+        ElementPointer::new(Self { child, alignment })
+    }
+
+    pub fn center(child: ElementPointer<Child>) -> ElementPointer<Self> {
+        Self::new(child, Alignment::CENTER)
+    }
+}
+
+impl<Child: Element> Element for Align<Child> {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        self.child.update(cx)
+    }
+
+    fn layout(&mut self, min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        let child_result = self.child.layout(Size::ZERO, max, cx);
+        let child_size = child_result.size();
+        // Unbounded axes shrink to fit the child instead
+        let size = Size::new(
+            if max.width.is_finite() {
+                max.width
+            } else {
+                child_size.width.max(min.width)
+            },
+            if max.height.is_finite() {
+                max.height
+            } else {
+                child_size.height.max(min.height)
+            },
+        );
+        child_result.position(
+            Affine::translate(self.alignment.offset(child_size, size)),
+            cx,
+        );
+        size
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        self.child.draw(cx)
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.child.tokens()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::Point;
+
+    use super::*;
+    use crate::{
+        components::layout::{ElementLayoutExt, Insets, SizedBox},
+        test_runner::TestRunner,
+    };
+
+    #[test]
+    fn centered_child_is_placed_inside_padding() {
+        let mut tokens = Vec::new();
+        let test_runner = TestRunner::new(Size::new(200., 100.), |_| {
+            let child = SizedBox::new(Size::new(40., 20.));
+            let centered = child.centered();
+            tokens = vec![centered.child.token(), centered.token()];
+            centered.with_padding(Insets::symmetric(20., 10.))
+        });
+        test_runner.layout();
+
+        let regions = test_runner.regions.borrow();
+        let (transform, size) = regions[&tokens[0]];
+        assert_eq!(size, Size::new(40., 20.));
+        assert_eq!(transform * Point::ZERO, Point::new(60., 30.));
+        let (transform, size) = regions[&tokens[1]];
+        assert_eq!(size, Size::new(160., 80.));
+        assert_eq!(transform * Point::ZERO, Point::new(20., 10.));
+    }
+}
@@ -0,0 +1,311 @@
+use std::marker::PhantomData;
+
+use vello::kurbo::{Affine, Size};
+
+use super::{boxed, ChildPointer, Expanded};
+use crate::{
+    context_stack::{DrawContext, LayoutContext, UpdateContext},
+    element::{Element, ElementPointer, LayoutResult},
+    token::Token,
+};
+
+/// Direction a flex container lays its children out along.
+pub trait Axis {
+    fn main(size: Size) -> f64;
+    fn cross(size: Size) -> f64;
+    fn size(main: f64, cross: f64) -> Size;
+}
+
+pub struct Horizontal;
+
+impl Axis for Horizontal {
+    fn main(size: Size) -> f64 {
+        size.width
+    }
+
+    fn cross(size: Size) -> f64 {
+        size.height
+    }
+
+    fn size(main: f64, cross: f64) -> Size {
+        Size::new(main, cross)
+    }
+}
+
+pub struct Vertical;
+
+impl Axis for Vertical {
+    fn main(size: Size) -> f64 {
+        size.height
+    }
+
+    fn cross(size: Size) -> f64 {
+        size.width
+    }
+
+    fn size(main: f64, cross: f64) -> Size {
+        Size::new(cross, main)
+    }
+}
+
+/// How free space along the main axis is distributed around the children.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MainAxisAlignment {
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrossAxisAlignment {
+    Start,
+    Center,
+    End,
+    // Children are given the full cross axis size of the container
+    Stretch,
+}
+
+struct FlexChild {
+    element: ChildPointer,
+    flex: f64,
+}
+
+/// Lays children out one after another along an axis. Expanded children split the space left
+/// over by the others in proportion to their flex.
+pub struct Flex<A: Axis> {
+    children: Vec<FlexChild>,
+    main_alignment: MainAxisAlignment,
+    cross_alignment: CrossAxisAlignment,
+    gap: f64,
+    axis: PhantomData<A>,
+}
+
+pub type Row = Flex<Horizontal>;
+pub type Column = Flex<Vertical>;
+
+impl<A: Axis> Flex<A> {
+    pub fn new() -> ElementPointer<Self> {
+        ElementPointer::new(Self {
+            children: Vec::new(),
+            main_alignment: MainAxisAlignment::Start,
+            cross_alignment: CrossAxisAlignment::Start,
+            gap: 0.,
+            axis: PhantomData,
+        })
+    }
+
+    pub fn with_child(
+        mut this: ElementPointer<Self>,
+        child: ElementPointer<impl Element + 'static>,
+    ) -> ElementPointer<Self> {
+        this.children.push(FlexChild {
+            element: boxed(child),
+            flex: 0.,
+        });
+        this
+    }
+
+    pub fn with_expanded(
+        mut this: ElementPointer<Self>,
+        child: ElementPointer<Expanded<impl Element + 'static>>,
+    ) -> ElementPointer<Self> {
+        let flex = child.flex;
+        this.children.push(FlexChild {
+            element: boxed(child),
+            flex,
+        });
+        this
+    }
+
+    pub fn with_main_alignment(
+        mut this: ElementPointer<Self>,
+        main_alignment: MainAxisAlignment,
+    ) -> ElementPointer<Self> {
+        this.main_alignment = main_alignment;
+        this
+    }
+
+    pub fn with_cross_alignment(
+        mut this: ElementPointer<Self>,
+        cross_alignment: CrossAxisAlignment,
+    ) -> ElementPointer<Self> {
+        this.cross_alignment = cross_alignment;
+        this
+    }
+
+    pub fn with_gap(mut this: ElementPointer<Self>, gap: f64) -> ElementPointer<Self> {
+        this.gap = gap;
+        this
+    }
+
+    // Offset of the first child and extra spacing between children for the free space.
+    fn distribute(&self, free: f64) -> (f64, f64) {
+        let count = self.children.len() as f64;
+        match self.main_alignment {
+            MainAxisAlignment::Start => (0., 0.),
+            MainAxisAlignment::Center => (free / 2., 0.),
+            MainAxisAlignment::End => (free, 0.),
+            MainAxisAlignment::SpaceBetween if count > 1. => (0., free / (count - 1.)),
+            MainAxisAlignment::SpaceBetween => (0., 0.),
+            MainAxisAlignment::SpaceAround => (free / count / 2., free / count),
+            MainAxisAlignment::SpaceEvenly => (free / (count + 1.), free / (count + 1.)),
+        }
+    }
+}
+
+impl<A: Axis> Element for Flex<A> {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        for child in self.children.iter_mut() {
+            child.element.update(cx);
+        }
+    }
+
+    fn layout(&mut self, min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        let max_main = A::main(max);
+        let max_cross = A::cross(max);
+        let stretch = self.cross_alignment == CrossAxisAlignment::Stretch && max_cross.is_finite();
+        let min_cross = if stretch { max_cross } else { 0. };
+        let total_flex: f64 = self.children.iter().map(|child| child.flex).sum();
+        // Without a bounded main axis there is no leftover space to split
+        let flexible = |child: &FlexChild| child.flex > 0. && max_main.is_finite();
+
+        let mut used = self.gap * self.children.len().saturating_sub(1) as f64;
+        let mut results: Vec<Option<LayoutResult>> = Vec::new();
+        for child in self.children.iter_mut() {
+            if flexible(child) {
+                results.push(None);
+                continue;
+            }
+            let result = child.element.layout(
+                A::size(0., min_cross),
+                A::size(f64::INFINITY, max_cross),
+                cx,
+            );
+            used += A::main(result.size());
+            results.push(Some(result));
+        }
+
+        let remaining = (max_main - used).max(0.);
+        for (child, result) in self.children.iter_mut().zip(results.iter_mut()) {
+            if result.is_none() {
+                let share = remaining * child.flex / total_flex;
+                let flexed =
+                    child
+                        .element
+                        .layout(A::size(share, min_cross), A::size(share, max_cross), cx);
+                used += A::main(flexed.size());
+                *result = Some(flexed);
+            }
+        }
+
+        let main = used.max(A::main(min)).min(max_main);
+        let cross = if stretch {
+            max_cross
+        } else {
+            results
+                .iter()
+                .flatten()
+                .map(|result| A::cross(result.size()))
+                .fold(A::cross(min), f64::max)
+                .min(max_cross)
+        };
+
+        let (mut position, spacing) = self.distribute((main - used).max(0.));
+        for result in results.into_iter().flatten() {
+            let size = result.size();
+            let offset = match self.cross_alignment {
+                CrossAxisAlignment::Start | CrossAxisAlignment::Stretch => 0.,
+                CrossAxisAlignment::Center => (cross - A::cross(size)) / 2.,
+                CrossAxisAlignment::End => cross - A::cross(size),
+            };
+            result.position(Affine::translate(A::size(position, offset).to_vec2()), cx);
+            position += A::main(size) + self.gap + spacing;
+        }
+
+        A::size(main, cross)
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        for child in self.children.iter() {
+            child.element.draw(cx);
+        }
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.children
+            .iter()
+            .flat_map(|child| child.element.tokens())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::Point;
+
+    use super::*;
+    use crate::{
+        components::layout::{ElementLayoutExt, SizedBox},
+        test_runner::TestRunner,
+    };
+
+    #[test]
+    fn row_splits_leftover_space_between_expanded_children() {
+        let mut tokens = Vec::new();
+        let test_runner = TestRunner::new(Size::new(300., 100.), |_| {
+            let fixed = SizedBox::new(Size::new(50., 20.));
+            let small = SizedBox::new(Size::new(0., 10.)).expanded(1.);
+            let large = SizedBox::new(Size::new(0., 10.)).expanded(3.);
+            tokens = vec![fixed.token(), small.token(), large.token()];
+
+            let row = Row::new();
+            let row = Row::with_gap(row, 10.);
+            let row = Row::with_cross_alignment(row, CrossAxisAlignment::Center);
+            let row = Row::with_child(row, fixed);
+            let row = Row::with_expanded(row, small);
+            Row::with_expanded(row, large)
+        });
+        test_runner.layout();
+
+        let regions = test_runner.regions.borrow();
+        let origin = |token: &Token| regions[token].0 * Point::ZERO;
+        let size = |token: &Token| regions[token].1;
+        assert_eq!(origin(&tokens[0]), Point::new(0., 40.));
+        assert_eq!(size(&tokens[1]), Size::new(57.5, 100.));
+        assert_eq!(origin(&tokens[1]), Point::new(60., 0.));
+        assert_eq!(size(&tokens[2]), Size::new(172.5, 100.));
+        assert_eq!(origin(&tokens[2]), Point::new(127.5, 0.));
+    }
+
+    #[test]
+    fn column_spaces_children_between_its_ends() {
+        let mut tokens = Vec::new();
+        let test_runner = TestRunner::new(Size::new(100., 300.), |_| {
+            let sizes = [
+                Size::new(40., 50.),
+                Size::new(20., 30.),
+                Size::new(40., 50.),
+            ];
+            let column = Column::new();
+            let column = Column::with_gap(column, 10.);
+            let column = Column::with_main_alignment(column, MainAxisAlignment::SpaceBetween);
+            let column = Column::with_cross_alignment(column, CrossAxisAlignment::End);
+            sizes.into_iter().fold(column, |column, size| {
+                let child = SizedBox::new(size);
+                tokens.push(child.token());
+                Column::with_child(column, child)
+            })
+        });
+        test_runner.layout();
+
+        // 150 of the column's 300 is left over after the children and gaps, 75 per space
+        let regions = test_runner.regions.borrow();
+        let origin = |token: &Token| regions[token].0 * Point::ZERO;
+        assert_eq!(origin(&tokens[0]), Point::new(60., 0.));
+        assert_eq!(origin(&tokens[1]), Point::new(80., 135.));
+        assert_eq!(origin(&tokens[2]), Point::new(60., 250.));
+    }
+}
@@ -0,0 +1,415 @@
+use vello::kurbo::{Affine, Point, Size};
+
+use super::{boxed, Alignment, ChildPointer};
+use crate::{
+    context_stack::{DrawContext, LayoutContext, UpdateContext},
+    element::{Element, ElementPointer},
+    token::Token,
+};
+
+/// Size of a row or column of a grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Track {
+    Fixed(f64),
+    // Fits the largest child in the track
+    Auto,
+    // Share of the space left over by the other tracks. Acts like auto when the grid is unbounded.
+    Fraction(f64),
+}
+
+/// Cell a child of a grid occupies along with the tracks it spans and where it sits within them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridPlacement {
+    pub row: usize,
+    pub column: usize,
+    pub row_span: usize,
+    pub column_span: usize,
+    pub alignment: Alignment,
+}
+
+impl GridPlacement {
+    pub fn at(row: usize, column: usize) -> Self {
+        Self {
+            row,
+            column,
+            row_span: 1,
+            column_span: 1,
+            alignment: Alignment::TOP_LEFT,
+        }
+    }
+
+    pub fn with_span(self, row_span: usize, column_span: usize) -> Self {
+        Self {
+            row_span: row_span.max(1),
+            column_span: column_span.max(1),
+            ..self
+        }
+    }
+
+    pub fn with_alignment(self, alignment: Alignment) -> Self {
+        Self { alignment, ..self }
+    }
+}
+
+struct GridCell {
+    element: ChildPointer,
+    placement: GridPlacement,
+    // Sizes the cell was measured at for each max it was measured with, kept until the cell is
+    // invalidated so that tracks can be resolved without laying the cell out again
+    measurements: Vec<(Size, Size)>,
+    measured_tokens: Vec<Token>,
+}
+
+impl GridCell {
+    fn measure(&mut self, max: Size, cx: &mut LayoutContext) -> Size {
+        let tokens = self.element.tokens();
+        if tokens != self.measured_tokens || cx.any_invalidated(&tokens) {
+            self.measurements.clear();
+            self.measured_tokens = tokens;
+        }
+        if let Some((_, size)) = self
+            .measurements
+            .iter()
+            .find(|(measured, _)| *measured == max)
+        {
+            return *size;
+        }
+
+        let size = self.element.layout(Size::ZERO, max, cx).size();
+        self.measurements.push((max, size));
+        size
+    }
+}
+
+/// Lays children out in rows and columns of tracks. Children may span several tracks and are
+/// placed within their cells by their alignment. Cells beyond the given tracks add auto tracks.
+pub struct Grid {
+    columns: Vec<Track>,
+    rows: Vec<Track>,
+    cells: Vec<GridCell>,
+    column_gap: f64,
+    row_gap: f64,
+}
+
+// Sizes of the tracks given the space available along their axis and the sizes of the items
+// placed in them as (first track, span, size).
+fn resolve_tracks(
+    tracks: &[Track],
+    available: f64,
+    gap: f64,
+    items: &[(usize, usize, f64)],
+) -> Vec<f64> {
+    let fits = |track: &Track| match track {
+        Track::Fixed(_) => false,
+        Track::Auto => true,
+        Track::Fraction(_) => !available.is_finite(),
+    };
+    let mut sizes: Vec<f64> = tracks
+        .iter()
+        .map(|track| match track {
+            Track::Fixed(size) => *size,
+            _ => 0.,
+        })
+        .collect();
+
+    for (start, _, size) in items.iter().filter(|(_, span, _)| *span == 1) {
+        if fits(&tracks[*start]) {
+            sizes[*start] = sizes[*start].max(*size);
+        }
+    }
+
+    // Items spanning several tracks grow the tracks which fit their contents evenly
+    for (start, span, size) in items.iter().filter(|(_, span, _)| *span > 1) {
+        let range = *start..start + span;
+        let covered = sizes[range.clone()].iter().sum::<f64>() + gap * (span - 1) as f64;
+        let growable: Vec<usize> = range.filter(|index| fits(&tracks[*index])).collect();
+        if *size > covered && !growable.is_empty() {
+            let extra = (size - covered) / growable.len() as f64;
+            for index in growable {
+                sizes[index] += extra;
+            }
+        }
+    }
+
+    if available.is_finite() {
+        let total_fraction: f64 = tracks
+            .iter()
+            .map(|track| match track {
+                Track::Fraction(fraction) => *fraction,
+                _ => 0.,
+            })
+            .sum();
+        let gaps = gap * tracks.len().saturating_sub(1) as f64;
+        let leftover = (available - sizes.iter().sum::<f64>() - gaps).max(0.);
+        for (track, size) in tracks.iter().zip(sizes.iter_mut()) {
+            if let Track::Fraction(fraction) = track {
+                *size = leftover * fraction / total_fraction;
+            }
+        }
+    }
+    sizes
+}
+
+// Start of each track and the total length of the tracks.
+fn track_offsets(sizes: &[f64], gap: f64) -> (Vec<f64>, f64) {
+    let mut offsets = Vec::with_capacity(sizes.len());
+    let mut offset = 0.;
+    for size in sizes {
+        offsets.push(offset);
+        offset += size + gap;
+    }
+    (offsets, (offset - gap).max(0.))
+}
+
+// Length covered by a span of tracks including the gaps between them.
+fn span_length(sizes: &[f64], start: usize, span: usize, gap: f64) -> f64 {
+    sizes[start..start + span].iter().sum::<f64>() + gap * (span - 1) as f64
+}
+
+impl Grid {
+    pub fn new(columns: Vec<Track>, rows: Vec<Track>) -> ElementPointer<Self> {
+        ElementPointer::new(Self {
+            columns,
+            rows,
+            cells: Vec::new(),
+            column_gap: 0.,
+            row_gap: 0.,
+        })
+    }
+
+    pub fn with_gap(
+        mut this: ElementPointer<Self>,
+        column_gap: f64,
+        row_gap: f64,
+    ) -> ElementPointer<Self> {
+        this.column_gap = column_gap;
+        this.row_gap = row_gap;
+        this
+    }
+
+    pub fn with_child(
+        mut this: ElementPointer<Self>,
+        child: ElementPointer<impl Element + 'static>,
+        placement: GridPlacement,
+    ) -> ElementPointer<Self> {
+        this.cells.push(GridCell {
+            element: boxed(child),
+            placement,
+            measurements: Vec::new(),
+            measured_tokens: Vec::new(),
+        });
+        this
+    }
+
+    // Tracks along each axis, padded with auto tracks to cover every cell.
+    fn tracks(&self) -> (Vec<Track>, Vec<Track>) {
+        let mut columns = self.columns.clone();
+        let mut rows = self.rows.clone();
+        for cell in self.cells.iter() {
+            let placement = cell.placement;
+            let column_end = placement.column + placement.column_span;
+            let row_end = placement.row + placement.row_span;
+            if columns.len() < column_end {
+                columns.resize(column_end, Track::Auto);
+            }
+            if rows.len() < row_end {
+                rows.resize(row_end, Track::Auto);
+            }
+        }
+        (columns, rows)
+    }
+}
+
+impl Element for Grid {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        for cell in self.cells.iter_mut() {
+            cell.element.update(cx);
+        }
+    }
+
+    fn layout(&mut self, _min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        let (columns, rows) = self.tracks();
+
+        // Columns are sized from the children's natural widths, then rows from their heights
+        // within those columns. Each cell is only laid out again for its final space.
+        let natural_sizes: Vec<Size> = self
+            .cells
+            .iter_mut()
+            .map(|cell| cell.measure(Size::INFINITY, cx))
+            .collect();
+        let column_items: Vec<_> = self
+            .cells
+            .iter()
+            .zip(natural_sizes.iter())
+            .map(|(cell, size)| {
+                (
+                    cell.placement.column,
+                    cell.placement.column_span,
+                    size.width,
+                )
+            })
+            .collect();
+        let widths = resolve_tracks(&columns, max.width, self.column_gap, &column_items);
+
+        let row_items: Vec<_> = self
+            .cells
+            .iter_mut()
+            .zip(natural_sizes)
+            .map(|(cell, natural_size)| {
+                let placement = cell.placement;
+                let width = span_length(
+                    &widths,
+                    placement.column,
+                    placement.column_span,
+                    self.column_gap,
+                );
+                // Children narrower than their columns keep their natural height
+                let height = if natural_size.width <= width {
+                    natural_size.height
+                } else {
+                    cell.measure(Size::new(width, f64::INFINITY), cx).height
+                };
+                (placement.row, placement.row_span, height)
+            })
+            .collect();
+        let heights = resolve_tracks(&rows, max.height, self.row_gap, &row_items);
+
+        let (xs, width) = track_offsets(&widths, self.column_gap);
+        let (ys, height) = track_offsets(&heights, self.row_gap);
+        for cell in self.cells.iter_mut() {
+            let placement = cell.placement;
+            let space = Size::new(
+                span_length(
+                    &widths,
+                    placement.column,
+                    placement.column_span,
+                    self.column_gap,
+                ),
+                span_length(&heights, placement.row, placement.row_span, self.row_gap),
+            );
+            let result = cell.element.layout(Size::ZERO, space, cx);
+            let origin = Point::new(xs[placement.column], ys[placement.row]);
+            let offset = placement.alignment.offset(result.size(), space);
+            result.position(Affine::translate(origin.to_vec2() + offset), cx);
+        }
+
+        Size::new(width, height)
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        for cell in self.cells.iter() {
+            cell.element.draw(cx);
+        }
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.cells
+            .iter()
+            .flat_map(|cell| cell.element.tokens())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::{
+        components::layout::{ElementLayoutExt, SizedBox},
+        test_runner::TestRunner,
+    };
+
+    // Counts how many times it is laid out.
+    struct Counted {
+        layouts: Rc<Cell<usize>>,
+    }
+
+    impl Element for Counted {
+        fn layout(&mut self, _min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+            self.layouts.set(self.layouts.get() + 1);
+            Size::new(30., 20.)
+        }
+    }
+
+    #[test]
+    fn spanning_items_grow_auto_tracks() {
+        let tracks = [Track::Fixed(20.), Track::Auto, Track::Fraction(1.)];
+        let items = [(1, 1, 30.), (0, 2, 70.)];
+        assert_eq!(
+            resolve_tracks(&tracks, 200., 10., &items),
+            vec![20., 40., 120.]
+        );
+        assert_eq!(
+            resolve_tracks(&tracks, f64::INFINITY, 10., &[(2, 1, 15.)]),
+            vec![20., 0., 15.]
+        );
+    }
+
+    #[test]
+    fn grid_places_children_in_cells() {
+        let mut tokens = Vec::new();
+        let test_runner = TestRunner::new(Size::new(200., 200.), |_| {
+            let centered = SizedBox::new(Size::new(30., 20.));
+            let spanning = SizedBox::height(40.).expanded(1.);
+            tokens = vec![centered.token(), spanning.token()];
+
+            let grid = Grid::new(
+                vec![Track::Fixed(50.), Track::Fraction(1.)],
+                vec![Track::Auto, Track::Auto],
+            );
+            let grid = Grid::with_gap(grid, 10., 10.);
+            let grid = Grid::with_child(
+                grid,
+                centered,
+                GridPlacement::at(0, 0).with_alignment(Alignment::CENTER),
+            );
+            Grid::with_child(grid, spanning, GridPlacement::at(1, 0).with_span(1, 2))
+        });
+        test_runner.layout();
+
+        let regions = test_runner.regions.borrow();
+        let (transform, size) = regions[&tokens[0]];
+        assert_eq!(transform * Point::ZERO, Point::new(10., 0.));
+        assert_eq!(size, Size::new(30., 20.));
+        let (transform, size) = regions[&tokens[1]];
+        assert_eq!(transform * Point::ZERO, Point::new(0., 30.));
+        assert_eq!(size, Size::new(200., 40.));
+    }
+
+    #[test]
+    fn nested_grids_lay_each_cell_out_once_per_space() {
+        let layouts = Rc::new(Cell::new(0));
+        let mut leaf = None;
+        let test_runner = TestRunner::new(Size::new(200., 200.), |_| {
+            let counted = ElementPointer::new(Counted {
+                layouts: layouts.clone(),
+            });
+            leaf = Some(counted.token());
+            let mut grid = Grid::with_child(
+                Grid::new(vec![Track::Auto], vec![Track::Auto]),
+                counted,
+                GridPlacement::at(0, 0),
+            );
+            for _ in 0..5 {
+                let outer = Grid::new(vec![Track::Auto, Track::Fixed(10.)], vec![Track::Auto]);
+                grid = Grid::with_child(outer, grid, GridPlacement::at(0, 0));
+            }
+            grid
+        });
+
+        // Measured once and then laid out in its cell, however deeply it is nested
+        test_runner.layout();
+        assert_eq!(layouts.get(), 2);
+        test_runner.layout();
+        assert_eq!(layouts.get(), 2);
+
+        test_runner
+            .application
+            .invalidated
+            .borrow_mut()
+            .insert(leaf.unwrap());
+        test_runner.layout();
+        assert_eq!(layouts.get(), 4);
+    }
+}
@@ -0,0 +1,118 @@
+pub mod align;
+pub mod flex;
+pub mod grid;
+pub mod padding;
+pub mod sized_box;
+pub mod stack;
+
+pub use align::*;
+pub use flex::*;
+pub use grid::*;
+pub use padding::*;
+pub use sized_box::*;
+pub use stack::*;
+
+use vello::kurbo::{Size, Vec2};
+
+use crate::element::{Element, ElementPointer};
+
+// Child of a layout container, whatever element it holds.
+type ChildPointer = ElementPointer<Box<dyn Element>>;
+
+fn boxed(child: ElementPointer<impl Element + 'static>) -> ChildPointer {
+    child.map(|element| Box::new(element) as Box<dyn Element>)
+}
+
+// Shrinks the size by the given amount without going negative.
+fn shrink(size: Size, by: Size) -> Size {
+    (size - by).max(Size::ZERO)
+}
+
+/// Position of a child within the space given to it, with 0 at the start of each axis and 1 at
+/// the end.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Alignment {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Alignment {
+    pub const TOP_LEFT: Alignment = Alignment::new(0., 0.);
+    pub const TOP: Alignment = Alignment::new(0.5, 0.);
+    pub const TOP_RIGHT: Alignment = Alignment::new(1., 0.);
+    pub const LEFT: Alignment = Alignment::new(0., 0.5);
+    pub const CENTER: Alignment = Alignment::new(0.5, 0.5);
+    pub const RIGHT: Alignment = Alignment::new(1., 0.5);
+    pub const BOTTOM_LEFT: Alignment = Alignment::new(0., 1.);
+    pub const BOTTOM: Alignment = Alignment::new(0.5, 1.);
+    pub const BOTTOM_RIGHT: Alignment = Alignment::new(1., 1.);
+
+    pub const fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    // Offset placing a child of the given size within the space.
+    pub fn offset(&self, child: Size, space: Size) -> Vec2 {
+        Vec2::new(
+            (space.width - child.width) * self.x,
+            (space.height - child.height) * self.y,
+        )
+    }
+}
+
+/// Space left around each side of a child.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Insets {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl Insets {
+    pub const fn new(left: f64, top: f64, right: f64, bottom: f64) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub const fn all(inset: f64) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+
+    pub const fn symmetric(horizontal: f64, vertical: f64) -> Self {
+        Self::new(horizontal, vertical, horizontal, vertical)
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.left + self.right, self.top + self.bottom)
+    }
+}
+
+pub trait ElementLayoutExt<This: Element + Sized> {
+    fn with_padding(self, insets: Insets) -> ElementPointer<Padding<This>>;
+    fn aligned(self, alignment: Alignment) -> ElementPointer<Align<This>>;
+    fn centered(self) -> ElementPointer<Align<This>>;
+    fn expanded(self, flex: f64) -> ElementPointer<Expanded<This>>;
+}
+
+impl<This: Element + Sized> ElementLayoutExt<This> for ElementPointer<This> {
+    fn with_padding(self, insets: Insets) -> ElementPointer<Padding<This>> {
+        Padding::new(self, insets)
+    }
+
+    fn aligned(self, alignment: Alignment) -> ElementPointer<Align<This>> {
+        Align::new(self, alignment)
+    }
+
+    fn centered(self) -> ElementPointer<Align<This>> {
+        Align::center(self)
+    }
+
+    fn expanded(self, flex: f64) -> ElementPointer<Expanded<This>> {
+        Expanded::new(self, flex)
+    }
+}
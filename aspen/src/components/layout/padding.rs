@@ -0,0 +1,42 @@
+use vello::kurbo::{Affine, Size};
+
+use super::{shrink, Insets};
+use crate::{
+    context_stack::{DrawContext, LayoutContext, UpdateContext},
+    element::{Element, ElementPointer},
+    token::Token,
+};
+
+/// Leaves empty space around a child.
+pub struct Padding<Child: Element> {
+    pub child: ElementPointer<Child>,
+    pub insets: Insets,
+}
+
+impl<Child: Element> Padding<Child> {
+    pub fn new(child: ElementPointer<Child>, insets: Insets) -> ElementPointer<Self> {
+        ElementPointer::new(Self { child, insets })
+    }
+}
+
+impl<Child: Element> Element for Padding<Child> {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        self.child.update(cx)
+    }
+
+    fn layout(&mut self, min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        let insets = self.insets.size();
+        let child_result = self
+            .child
+            .layout(shrink(min, insets), shrink(max, insets), cx);
+        child_result.position(Affine::translate((self.insets.left, self.insets.top)), cx) + insets
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        self.child.draw(cx)
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.child.tokens()
+    }
+}
@@ -0,0 +1,118 @@
+use vello::kurbo::{Affine, Size};
+
+use super::{boxed, ChildPointer};
+use crate::{
+    context_stack::{DrawContext, LayoutContext, UpdateContext},
+    element::{Element, ElementPointer},
+    token::Token,
+};
+
+/// Forces its child to a fixed width, height or both. Without a child it is empty space of that
+/// size.
+pub struct SizedBox {
+    child: Option<ChildPointer>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+}
+
+impl SizedBox {
+    pub fn new(size: Size) -> ElementPointer<Self> {
+        Self::with_dimensions(Some(size.width), Some(size.height))
+    }
+
+    pub fn width(width: f64) -> ElementPointer<Self> {
+        Self::with_dimensions(Some(width), None)
+    }
+
+    pub fn height(height: f64) -> ElementPointer<Self> {
+        Self::with_dimensions(None, Some(height))
+    }
+
+    fn with_dimensions(width: Option<f64>, height: Option<f64>) -> ElementPointer<Self> {
+        ElementPointer::new(Self {
+            child: None,
+            width,
+            height,
+        })
+    }
+
+    pub fn with_child(
+        mut this: ElementPointer<Self>,
+        child: ElementPointer<impl Element + 'static>,
+    ) -> ElementPointer<Self> {
+        this.child = Some(boxed(child));
+        this
+    }
+}
+
+impl Element for SizedBox {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        if let Some(child) = self.child.as_mut() {
+            child.update(cx);
+        }
+    }
+
+    fn layout(&mut self, min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        let tight = |dimension: Option<f64>, min: f64, max: f64| match dimension {
+            Some(dimension) => {
+                let dimension = dimension.clamp(min, max);
+                (dimension, dimension)
+            }
+            None => (min, max),
+        };
+        let (min_width, max_width) = tight(self.width, min.width, max.width);
+        let (min_height, max_height) = tight(self.height, min.height, max.height);
+        let min = Size::new(min_width, min_height);
+        let max = Size::new(max_width, max_height);
+
+        match self.child.as_mut() {
+            Some(child) => child.layout(min, max, cx).position(Affine::IDENTITY, cx),
+            None => min,
+        }
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        if let Some(child) = self.child.as_ref() {
+            child.draw(cx);
+        }
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.child.iter().flat_map(|child| child.tokens()).collect()
+    }
+}
+
+/// Fills all of the space available to it. In a row or column it takes a share of the space left
+/// over by the other children in proportion to its flex.
+pub struct Expanded<Child: Element> {
+    pub child: ElementPointer<Child>,
+    pub flex: f64,
+}
+
+impl<Child: Element> Expanded<Child> {
+    pub fn new(child: ElementPointer<Child>, flex: f64) -> ElementPointer<Self> {
+        ElementPointer::new(Self { child, flex })
+    }
+}
+
+impl<Child: Element> Element for Expanded<Child> {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        self.child.update(cx)
+    }
+
+    fn layout(&mut self, min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        let fill = |min: f64, max: f64| if max.is_finite() { max } else { min };
+        let filled = Size::new(fill(min.width, max.width), fill(min.height, max.height));
+        self.child
+            .layout(filled, max, cx)
+            .position(Affine::IDENTITY, cx)
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        self.child.draw(cx)
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.child.tokens()
+    }
+}
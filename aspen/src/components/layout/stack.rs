@@ -0,0 +1,106 @@
+use vello::kurbo::{Affine, Size};
+
+use super::{boxed, Alignment, ChildPointer};
+use crate::{
+    context_stack::{DrawContext, LayoutContext, UpdateContext},
+    element::{Element, ElementPointer},
+    token::Token,
+};
+
+/// Layers children on top of each other, later children drawn over earlier ones. Sized to fit its
+/// largest child.
+pub struct Stack {
+    children: Vec<ChildPointer>,
+    alignment: Alignment,
+}
+
+impl Stack {
+    pub fn new(alignment: Alignment) -> ElementPointer<Self> {
+        ElementPointer::new(Self {
+            children: Vec::new(),
+            alignment,
+        })
+    }
+
+    pub fn with_child(
+        mut this: ElementPointer<Self>,
+        child: ElementPointer<impl Element + 'static>,
+    ) -> ElementPointer<Self> {
+        this.children.push(boxed(child));
+        this
+    }
+}
+
+impl Element for Stack {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        for child in self.children.iter_mut() {
+            child.update(cx);
+        }
+    }
+
+    fn layout(&mut self, min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        let results: Vec<_> = self
+            .children
+            .iter_mut()
+            .map(|child| child.layout(Size::ZERO, max, cx))
+            .collect();
+        let size = results
+            .iter()
+            .fold(min, |size, result| size.max(result.size()))
+            .min(max);
+
+        for result in results {
+            let offset = self.alignment.offset(result.size(), size);
+            result.position(Affine::translate(offset), cx);
+        }
+        size
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        for child in self.children.iter() {
+            child.draw(cx);
+        }
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.children
+            .iter()
+            .flat_map(|child| child.tokens())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::Point;
+
+    use super::*;
+    use crate::{
+        components::layout::{ElementLayoutExt, SizedBox},
+        test_runner::TestRunner,
+    };
+
+    #[test]
+    fn stack_fits_its_largest_child_and_aligns_the_others() {
+        let mut tokens = Vec::new();
+        let test_runner = TestRunner::new(Size::new(200., 100.), |_| {
+            let wide = SizedBox::new(Size::new(120., 30.));
+            let tall = SizedBox::new(Size::new(40., 60.));
+            tokens = vec![wide.token(), tall.token()];
+
+            let stack = Stack::new(Alignment::BOTTOM_RIGHT);
+            let stack = Stack::with_child(stack, wide);
+            let stack = Stack::with_child(stack, tall);
+            tokens.push(stack.token());
+            stack.centered()
+        });
+        test_runner.layout();
+
+        let regions = test_runner.regions.borrow();
+        let origin = |token: &Token| regions[token].0 * Point::ZERO;
+        assert_eq!(regions[&tokens[2]].1, Size::new(120., 60.));
+        assert_eq!(origin(&tokens[2]), Point::new(40., 20.));
+        assert_eq!(origin(&tokens[0]), Point::new(0., 30.));
+        assert_eq!(origin(&tokens[1]), Point::new(80., 0.));
+    }
+}
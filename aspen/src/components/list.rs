@@ -0,0 +1,473 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    rc::Rc,
+};
+
+use vello::{
+    kurbo::{Affine, Rect, Size},
+    peniko::Brush,
+};
+use winit::keyboard::{Key, NamedKey};
+
+use crate::{
+    context_stack::{Context, DrawContext, LayoutContext, UpdateContext},
+    element::{Element, ElementPointer},
+    theme::ThemeColor,
+    token::Token,
+};
+
+// Rows scrolled out of view kept around so that their state survives scrolling back
+const RECYCLED_ROWS: usize = 32;
+
+/// Height of the rows of a list. Estimated rows are measured as they are laid out, with the
+/// estimate standing in for rows which haven't been yet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowHeight {
+    Fixed(f64),
+    Estimated(f64),
+}
+
+// Row heights along with their prefix sums so that rows can be found by offset in logarithmic
+// time however long the list is.
+struct RowHeights {
+    heights: Vec<f64>,
+    // Fenwick tree where each entry holds the sum of a power of two run of rows ending at it
+    tree: Vec<f64>,
+}
+
+impl RowHeights {
+    fn new(len: usize, height: f64) -> Self {
+        let heights = vec![height; len];
+        let mut tree = heights.clone();
+        for index in 0..len {
+            let parent = index | (index + 1);
+            if parent < len {
+                tree[parent] += tree[index];
+            }
+        }
+        Self { heights, tree }
+    }
+
+    fn len(&self) -> usize {
+        self.heights.len()
+    }
+
+    fn set(&mut self, index: usize, height: f64) {
+        let delta = height - self.heights[index];
+        if delta == 0. {
+            return;
+        }
+        self.heights[index] = height;
+        let mut index = index;
+        while index < self.len() {
+            self.tree[index] += delta;
+            index |= index + 1;
+        }
+    }
+
+    // Total height of the rows before the index
+    fn offset_of(&self, index: usize) -> f64 {
+        let mut offset = 0.;
+        let mut index = index.min(self.len());
+        while index > 0 {
+            offset += self.tree[index - 1];
+            index &= index - 1;
+        }
+        offset
+    }
+
+    fn total(&self) -> f64 {
+        self.offset_of(self.len())
+    }
+
+    // Index of the row containing the offset
+    fn index_at(&self, offset: f64) -> usize {
+        let mut index = 0;
+        let mut remaining = offset;
+        let mut step = self.len().next_power_of_two();
+        while step > 0 {
+            let next = index + step;
+            if next <= self.len() && self.tree[next - 1] <= remaining {
+                index = next;
+                remaining -= self.tree[next - 1];
+            }
+            step >>= 1;
+        }
+        index.min(self.len().saturating_sub(1))
+    }
+}
+
+struct VisibleRow<RowKey, Row: Element> {
+    index: usize,
+    key: RowKey,
+    element: ElementPointer<Row>,
+    // Vertical extent in the list's region
+    top: f64,
+    height: f64,
+}
+
+type SelectHandler = Rc<dyn Fn(usize, &Context)>;
+
+/// Vertically scrolling list which only builds, lays out and draws the rows in view. Rows are
+/// built on demand and kept by key, so a row's state follows its key as the list changes. Focused
+/// lists move their selection with the arrow, page, home and end keys.
+pub struct List<RowKey, Row: Element> {
+    len: usize,
+    key_of: Box<dyn Fn(usize) -> RowKey>,
+    build_row: Box<dyn Fn(usize) -> ElementPointer<Row>>,
+    row_height: RowHeight,
+    heights: RowHeights,
+    selection_fill: ThemeColor,
+    on_select: Option<SelectHandler>,
+
+    // Rows laid out during the last layout in index order
+    rows: Vec<VisibleRow<RowKey, Row>>,
+    // Most recently hidden rows last
+    recycled: VecDeque<(RowKey, ElementPointer<Row>)>,
+}
+
+#[derive(Default)]
+pub struct ListState {
+    offset: f64,
+    selected: Option<usize>,
+    // Row to scroll into view during the next layout
+    pending_reveal: Option<usize>,
+}
+
+impl ListState {
+    // Returns true if the selection changed.
+    fn select(&mut self, index: usize) -> bool {
+        self.pending_reveal = Some(index);
+        self.selected.replace(index) != Some(index)
+    }
+}
+
+impl<RowKey: Clone + Eq + Hash + 'static, Row: Element + 'static> List<RowKey, Row> {
+    pub fn new(
+        len: usize,
+        key_of: impl Fn(usize) -> RowKey + 'static,
+        build_row: impl Fn(usize) -> ElementPointer<Row> + 'static,
+        row_height: RowHeight,
+    ) -> ElementPointer<Self> {
+        let estimate = match row_height {
+            RowHeight::Fixed(height) | RowHeight::Estimated(height) => height,
+        };
+        ElementPointer::new(Self {
+            len,
+            key_of: Box::new(key_of),
+            build_row: Box::new(build_row),
+            row_height,
+            heights: RowHeights::new(len, estimate),
+            selection_fill: ThemeColor::Selection,
+            on_select: None,
+
+            rows: Vec::new(),
+            recycled: VecDeque::new(),
+        })
+    }
+
+    pub fn with_selection_fill(
+        mut this: ElementPointer<Self>,
+        selection_fill: impl Into<ThemeColor>,
+    ) -> ElementPointer<Self> {
+        this.selection_fill = selection_fill.into();
+        this
+    }
+
+    pub fn with_on_select(
+        mut this: ElementPointer<Self>,
+        on_select: impl Fn(usize, &Context) + 'static,
+    ) -> ElementPointer<Self> {
+        this.on_select = Some(Rc::new(on_select));
+        this
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Changes the number of items. Rows whose keys remain keep their elements, while measured
+    // heights are forgotten since they were recorded by index.
+    pub fn set_len(&mut self, len: usize) {
+        let estimate = match self.row_height {
+            RowHeight::Fixed(height) | RowHeight::Estimated(height) => height,
+        };
+        self.len = len;
+        self.heights = RowHeights::new(len, estimate);
+    }
+
+    // Drops every row so that they are built again, for when the items behind them change.
+    pub fn rebuild_rows(&mut self) {
+        self.rows.clear();
+        self.recycled.clear();
+    }
+
+    fn handle_keys(&mut self, cx: &mut UpdateContext) {
+        if !cx.is_directly_focused() || self.is_empty() {
+            return;
+        }
+
+        let page = self.rows.len().saturating_sub(1).max(1);
+        let last = self.len - 1;
+        let key_events: Vec<_> = cx.key_events().into();
+        for key_event in key_events {
+            if !key_event.state.is_pressed() {
+                continue;
+            }
+
+            let selected = cx.with_state(|state: &mut ListState, _| state.selected);
+            let index = match key_event.key {
+                Key::Named(NamedKey::ArrowDown) => selected.map_or(0, |index| index + 1),
+                Key::Named(NamedKey::ArrowUp) => {
+                    selected.map_or(0, |index| index.saturating_sub(1))
+                }
+                Key::Named(NamedKey::PageDown) => selected.map_or(0, |index| index + page),
+                Key::Named(NamedKey::PageUp) => {
+                    selected.map_or(0, |index| index.saturating_sub(page))
+                }
+                Key::Named(NamedKey::Home) => 0,
+                Key::Named(NamedKey::End) => last,
+                _ => continue,
+            }
+            .min(last);
+
+            if cx.with_state(|state: &mut ListState, _| state.select(index)) {
+                if let Some(on_select) = self.on_select.as_ref() {
+                    on_select(index, cx);
+                }
+            }
+            cx.request_redraw();
+        }
+    }
+
+    // Takes the row for the key from the last layout or the recycled rows, building it if
+    // neither has it.
+    fn take_row(
+        &mut self,
+        index: usize,
+        key: &RowKey,
+        previous: &mut HashMap<RowKey, ElementPointer<Row>>,
+    ) -> ElementPointer<Row> {
+        if let Some(element) = previous.remove(key) {
+            return element;
+        }
+        if let Some(position) = self
+            .recycled
+            .iter()
+            .position(|(recycled, _)| recycled == key)
+        {
+            return self.recycled.remove(position).unwrap().1;
+        }
+        (self.build_row)(index)
+    }
+}
+
+impl<RowKey: Clone + Eq + Hash + 'static, Row: Element + 'static>
+    ElementPointer<List<RowKey, Row>>
+{
+    pub fn selected(&self, cx: &Context) -> Option<usize> {
+        self.read_state(cx, |state: &ListState, _| state.selected)
+    }
+
+    // Selects the row and scrolls it into view during the next layout.
+    pub fn select(&self, index: usize, cx: &Context) {
+        self.with_state(cx, |state: &mut ListState, _| state.select(index));
+    }
+
+    pub fn scroll_to_row(&self, index: usize, cx: &Context) {
+        self.with_state(cx, |state: &mut ListState, _| {
+            state.pending_reveal = Some(index)
+        });
+    }
+}
+
+impl<RowKey: Clone + Eq + Hash + 'static, Row: Element + 'static> Element for List<RowKey, Row> {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        for row in self.rows.iter_mut() {
+            row.element.update(cx);
+        }
+        self.handle_keys(cx);
+        if cx.with_state(|state: &mut ListState, _| state.pending_reveal.is_some()) {
+            cx.request_redraw();
+        }
+    }
+
+    fn layout(&mut self, _min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        // Unbounded lists show every row
+        let viewport = if max.height.is_finite() {
+            max.height
+        } else {
+            self.heights.total()
+        };
+        let (mut offset, reveal) =
+            cx.with_state(|state: &mut ListState, _| (state.offset, state.pending_reveal.take()));
+        if let Some(index) = reveal.filter(|index| *index < self.len) {
+            let top = self.heights.offset_of(index);
+            let bottom = self.heights.offset_of(index + 1);
+            if top < offset {
+                offset = top;
+            } else if bottom > offset + viewport {
+                offset = bottom - viewport;
+            }
+        }
+        offset = offset.clamp(0., (self.heights.total() - viewport).max(0.));
+        let first = self.heights.index_at(offset);
+        let within = offset - self.heights.offset_of(first);
+
+        let (row_min, row_max) = {
+            let width = if max.width.is_finite() { max.width } else { 0. };
+            match self.row_height {
+                RowHeight::Fixed(height) => {
+                    (Size::new(width, height), Size::new(max.width, height))
+                }
+                RowHeight::Estimated(_) => {
+                    (Size::new(width, 0.), Size::new(max.width, f64::INFINITY))
+                }
+            }
+        };
+        let mut previous: HashMap<RowKey, ElementPointer<Row>> = self
+            .rows
+            .drain(..)
+            .map(|row| (row.key, row.element))
+            .collect();
+        let mut top = -within;
+        let mut widest: f64 = 0.;
+        let mut index = first;
+        while index < self.len && top < viewport {
+            let key = (self.key_of)(index);
+            let mut element = self.take_row(index, &key, &mut previous);
+            let result = element.layout(row_min, row_max, cx);
+            let size = result.size();
+            result.position(Affine::translate((0., top)), cx);
+            self.heights.set(index, size.height);
+            widest = widest.max(size.width);
+
+            self.rows.push(VisibleRow {
+                index,
+                key,
+                element,
+                top,
+                height: size.height,
+            });
+            top += size.height;
+            index += 1;
+        }
+
+        self.recycled.extend(previous);
+        while self.recycled.len() > RECYCLED_ROWS {
+            self.recycled.pop_front();
+        }
+
+        // Measuring rows above the first visible one may have moved it, so keep it in place
+        let offset = self.heights.offset_of(first) + within;
+        cx.with_state(|state: &mut ListState, _| state.offset = offset);
+
+        let width = if max.width.is_finite() {
+            max.width
+        } else {
+            widest
+        };
+        Size::new(width, viewport)
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        let region = cx.region();
+        cx.mouse_region(region).on_scroll(|cx| {
+            let delta = cx.scroll_delta().y;
+            // Clamped during layout
+            cx.with_state(|state: &mut ListState, _| state.offset -= delta);
+            cx.request_redraw();
+        });
+
+        let selected = cx.with_state(|state: &mut ListState, _| state.selected);
+        cx.push_layer(&region);
+        for row in self.rows.iter() {
+            let bounds = Rect::new(0., row.top, region.width(), row.top + row.height);
+            if selected == Some(row.index) {
+                cx.set_fill_brush(Brush::Solid(self.selection_fill.resolve(&cx.theme())));
+                cx.fill(&bounds);
+            }
+
+            // Registered before the row draws so that the row's own regions take priority
+            let index = row.index;
+            let on_select = self.on_select.clone();
+            cx.mouse_region(bounds).on_down(move |cx| {
+                if cx.with_state(|state: &mut ListState, _| state.select(index)) {
+                    if let Some(on_select) = on_select.as_ref() {
+                        on_select(index, cx);
+                    }
+                }
+                cx.focus();
+                cx.request_redraw();
+            });
+            row.element.draw(cx);
+        }
+        cx.pop_layer();
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.rows
+            .iter()
+            .flat_map(|row| row.element.tokens())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::window::CursorIcon;
+
+    use super::*;
+    use crate::{components::layout::SizedBox, test_runner::TestRunner};
+
+    #[test]
+    fn row_heights_find_rows_by_offset() {
+        let mut heights = RowHeights::new(10, 20.);
+        assert_eq!(heights.total(), 200.);
+        assert_eq!(heights.index_at(0.), 0);
+        assert_eq!(heights.index_at(59.), 2);
+        assert_eq!(heights.index_at(1000.), 9);
+
+        heights.set(1, 50.);
+        assert_eq!(heights.offset_of(3), 90.);
+        assert_eq!(heights.index_at(69.), 1);
+        assert_eq!(heights.index_at(70.), 2);
+        assert_eq!(heights.total(), 230.);
+    }
+
+    #[test]
+    fn only_rows_in_view_are_laid_out() {
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |_| {
+            List::new(
+                100_000,
+                |index| index,
+                |_| SizedBox::height(20.),
+                RowHeight::Fixed(20.),
+            )
+        });
+        let visible_rows = |test_runner: &TestRunner<List<usize, SizedBox>>| {
+            test_runner
+                .with_root(|root, _| root.rows.iter().map(|row| row.index).collect::<Vec<_>>())
+        };
+        test_runner.layout();
+        assert_eq!(visible_rows(&test_runner), vec![0, 1, 2, 3, 4]);
+
+        test_runner.expect_cursor_icon(winit::window::Cursor::Icon(CursorIcon::Default));
+        test_runner.with_root(|_, cx| cx.focus());
+        test_runner.input_key(Key::Named(NamedKey::End));
+        assert_eq!(
+            visible_rows(&test_runner),
+            vec![99_995, 99_996, 99_997, 99_998, 99_999]
+        );
+
+        test_runner.input_key(Key::Named(NamedKey::ArrowUp));
+        let selected =
+            test_runner.with_root(|_, cx| cx.with_state(|state: &mut ListState, _| state.selected));
+        assert_eq!(selected, Some(99_998));
+    }
+}
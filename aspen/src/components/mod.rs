@@ -2,12 +2,18 @@ pub mod board;
 pub mod border;
 pub mod button;
 pub mod resize_handles;
+pub mod scroll_view;
 pub mod editor;
+pub mod layout;
+pub mod list;
 pub mod window_buttons;
 
 pub use board::*;
 pub use border::*;
 pub use button::*;
 pub use resize_handles::*;
+pub use scroll_view::*;
 pub use editor::*;
+pub use layout::*;
+pub use list::*;
 pub use window_buttons::*;
@@ -0,0 +1,494 @@
+use std::time::Instant;
+
+use vello::{
+    kurbo::{Affine, Point, Rect, Size, Vec2},
+    peniko::Brush,
+};
+
+use crate::{
+    context_stack::{Context, DrawContext, LayoutContext, UpdateContext},
+    element::{Element, ElementPointer},
+    theme::ThemeColor,
+    token::Token,
+};
+
+const SCROLLBAR_THICKNESS: f64 = 6.;
+const SCROLLBAR_MARGIN: f64 = 2.;
+const MIN_THUMB_LENGTH: f64 = 20.;
+const FADE_LENGTH: f64 = 0.2;
+
+/// Shows the part of a child larger than itself which is scrolled into view. Scrolled by the mouse
+/// wheel, with shift scrolling sideways, or by dragging the scrollbars which fade in on hover.
+/// Descendants are scrolled into view when they gain focus.
+pub struct ScrollView<Child: Element> {
+    pub child: ElementPointer<Child>,
+    pub horizontal: bool,
+    pub vertical: bool,
+    pub scrollbar: ThemeColor,
+}
+
+#[derive(Default)]
+pub struct ScrollState {
+    offset: Vec2,
+    // Sizes recorded during layout
+    content: Size,
+    viewport: Size,
+    pending_offset: Option<Vec2>,
+    // Content space rect to scroll into view
+    pending_reveal: Option<Rect>,
+    revealed_focus: Option<Token>,
+    hovered: bool,
+    dragging: bool,
+    // When the scrollbars last started fading in or out
+    fade_start: Option<Instant>,
+}
+
+// Scrollbar thumb in the view's region along with how it moves the content.
+struct Scrollbar {
+    thumb: Rect,
+    // Unit vector along the scrolled axis
+    axis: Vec2,
+    // Content distance scrolled per unit the thumb is dragged
+    travel: f64,
+}
+
+// Start and length of a thumb within its track, absent when the content fits.
+fn thumb_span(track: f64, content: f64, viewport: f64, offset: f64) -> Option<(f64, f64)> {
+    if content <= viewport || track <= 0. {
+        return None;
+    }
+    let length = (track * viewport / content)
+        .max(MIN_THUMB_LENGTH)
+        .min(track);
+    let start = (track - length) * offset / (content - viewport);
+    Some((start, length))
+}
+
+impl ScrollState {
+    fn max_offset(&self) -> Vec2 {
+        Vec2::new(
+            (self.content.width - self.viewport.width).max(0.),
+            (self.content.height - self.viewport.height).max(0.),
+        )
+    }
+
+    fn scroll_to(&mut self, offset: Vec2) {
+        let max = self.max_offset();
+        self.offset = Vec2::new(offset.x.clamp(0., max.x), offset.y.clamp(0., max.y));
+    }
+
+    // Scrolls as little as possible to show the rect, preferring its top left corner when it
+    // doesn't fit.
+    fn reveal(&mut self, rect: Rect) {
+        let reveal_axis = |offset: f64, start: f64, end: f64, viewport: f64| {
+            let offset = if end > offset + viewport {
+                end - viewport
+            } else {
+                offset
+            };
+            offset.min(start)
+        };
+        self.scroll_to(Vec2::new(
+            reveal_axis(self.offset.x, rect.x0, rect.x1, self.viewport.width),
+            reveal_axis(self.offset.y, rect.y0, rect.y1, self.viewport.height),
+        ));
+    }
+
+    fn shown(&self) -> bool {
+        self.hovered || self.dragging
+    }
+
+    // Applies the change and starts fading the scrollbars if it shows or hides them. Returns true
+    // if they started fading.
+    fn update_shown(&mut self, now: Instant, change: impl FnOnce(&mut Self)) -> bool {
+        let was_shown = self.shown();
+        change(self);
+        let fading = was_shown != self.shown();
+        if fading {
+            self.fade_start = Some(now);
+        }
+        fading
+    }
+
+    fn fading(&self, now: Instant) -> bool {
+        self.fade_start
+            .is_some_and(|start| now.saturating_duration_since(start).as_secs_f64() < FADE_LENGTH)
+    }
+
+    fn scrollbar_opacity(&self, now: Instant) -> f64 {
+        let t = self.fade_start.map_or(1., |start| {
+            (now.saturating_duration_since(start).as_secs_f64() / FADE_LENGTH).min(1.)
+        });
+        if self.shown() {
+            t
+        } else {
+            1. - t
+        }
+    }
+
+    fn scrollbars(&self, horizontal: bool, vertical: bool) -> Vec<Scrollbar> {
+        let scrolls_x = horizontal && self.content.width > self.viewport.width;
+        let scrolls_y = vertical && self.content.height > self.viewport.height;
+        // Leave the corner free when both scrollbars are shown
+        let corner = SCROLLBAR_THICKNESS + SCROLLBAR_MARGIN;
+        let inset = |length: f64, other_shown: bool| {
+            length - SCROLLBAR_MARGIN * 2. - if other_shown { corner } else { 0. }
+        };
+
+        let mut scrollbars = Vec::new();
+        let track = inset(self.viewport.width, scrolls_y);
+        if let Some((start, length)) = thumb_span(
+            track,
+            self.content.width,
+            self.viewport.width,
+            self.offset.x,
+        )
+        .filter(|_| scrolls_x)
+        {
+            scrollbars.push(Scrollbar {
+                thumb: Rect::from_origin_size(
+                    (SCROLLBAR_MARGIN + start, self.viewport.height - corner),
+                    (length, SCROLLBAR_THICKNESS),
+                ),
+                axis: Vec2::new(1., 0.),
+                travel: (self.content.width - self.viewport.width) / (track - length).max(1.),
+            });
+        }
+        let track = inset(self.viewport.height, scrolls_x);
+        if let Some((start, length)) = thumb_span(
+            track,
+            self.content.height,
+            self.viewport.height,
+            self.offset.y,
+        )
+        .filter(|_| scrolls_y)
+        {
+            scrollbars.push(Scrollbar {
+                thumb: Rect::from_origin_size(
+                    (self.viewport.width - corner, SCROLLBAR_MARGIN + start),
+                    (SCROLLBAR_THICKNESS, length),
+                ),
+                axis: Vec2::new(0., 1.),
+                travel: (self.content.height - self.viewport.height) / (track - length).max(1.),
+            });
+        }
+        scrollbars
+    }
+}
+
+impl<Child: Element> ScrollView<Child> {
+    pub fn new(child: ElementPointer<Child>) -> ElementPointer<Self> {
+        Self::scrolling(child, false, true)
+    }
+
+    pub fn horizontal(child: ElementPointer<Child>) -> ElementPointer<Self> {
+        Self::scrolling(child, true, false)
+    }
+
+    pub fn both(child: ElementPointer<Child>) -> ElementPointer<Self> {
+        Self::scrolling(child, true, true)
+    }
+
+    fn scrolling(
+        child: ElementPointer<Child>,
+        horizontal: bool,
+        vertical: bool,
+    ) -> ElementPointer<Self> {
+        ElementPointer::new(Self {
+            child,
+            horizontal,
+            vertical,
+            scrollbar: ThemeColor::Muted,
+        })
+    }
+
+    pub fn with_scrollbar_color(
+        mut this: ElementPointer<Self>,
+        scrollbar: impl Into<ThemeColor>,
+    ) -> ElementPointer<Self> {
+        this.scrollbar = scrollbar.into();
+        this
+    }
+}
+
+impl<Child: Element> ElementPointer<ScrollView<Child>> {
+    // Offset of the content as of the last layout.
+    pub fn scroll_offset(&self, cx: &Context) -> Vec2 {
+        self.read_state(cx, |state: &ScrollState, _| state.offset)
+    }
+
+    // Scrolls to the offset, clamped to the content, during the next layout.
+    pub fn scroll_to(&self, offset: Vec2, cx: &Context) {
+        self.with_state(cx, |state: &mut ScrollState, _| {
+            state.pending_offset = Some(offset);
+        });
+    }
+
+    // Scrolls just enough to show the rect, given in the child's coordinates, during the next
+    // layout.
+    pub fn scroll_to_rect(&self, rect: Rect, cx: &Context) {
+        self.with_state(cx, |state: &mut ScrollState, _| {
+            state.pending_reveal = Some(rect);
+        });
+    }
+}
+
+impl<Child: Element> Element for ScrollView<Child> {
+    fn update(&mut self, cx: &mut UpdateContext) {
+        self.child.update(cx);
+
+        let mouse_down = cx.mouse_down();
+        let now = cx.frame_time();
+        let (redraw, fading) = cx.with_state(|state: &mut ScrollState, _| {
+            // Thumb drags end wherever the mouse is released
+            let released = state.update_shown(now, |state| state.dragging &= mouse_down);
+            let fading = released || state.fading(now);
+            let pending = state.pending_offset.is_some() || state.pending_reveal.is_some();
+            (fading || pending, fading)
+        });
+        if redraw {
+            cx.request_redraw();
+        }
+        if fading {
+            cx.request_animation_frame();
+        }
+    }
+
+    fn layout(&mut self, min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+        // Scrolled axes give the child as much room as it likes
+        let child_min = Size::new(
+            if self.horizontal { 0. } else { min.width },
+            if self.vertical { 0. } else { min.height },
+        );
+        let child_max = Size::new(
+            if self.horizontal {
+                f64::INFINITY
+            } else {
+                max.width
+            },
+            if self.vertical {
+                f64::INFINITY
+            } else {
+                max.height
+            },
+        );
+        let result = self.child.layout(child_min, child_max, cx);
+        let content = result.size();
+        let fill = |content: f64, min: f64, max: f64| {
+            if max.is_finite() {
+                max
+            } else {
+                content.max(min)
+            }
+        };
+        let size = Size::new(
+            fill(content.width, min.width, max.width),
+            fill(content.height, min.height, max.height),
+        );
+
+        let child_token = self.child.token();
+        let child_tokens = self.child.tokens();
+        let focused = cx
+            .focused_token()
+            .filter(|token| *token != child_token && child_tokens.contains(token));
+        let offset = cx.with_state(|state: &mut ScrollState, cx| {
+            state.content = content;
+            state.viewport = size;
+            if let Some(offset) = state.pending_offset.take() {
+                state.offset = offset;
+            }
+            if let Some(rect) = state.pending_reveal.take() {
+                state.reveal(rect);
+            }
+            if focused != state.revealed_focus {
+                state.revealed_focus = focused;
+                if let Some((transform, size)) =
+                    focused.and_then(|token| cx.region_within(child_token, token))
+                {
+                    state.reveal(
+                        transform.transform_rect_bbox(Rect::from_origin_size(Point::ZERO, size)),
+                    );
+                }
+            }
+            // Content may have shrunk since the offset was set
+            state.scroll_to(state.offset);
+            state.offset
+        });
+        result.position(Affine::translate(-offset), cx);
+
+        size
+    }
+
+    fn draw(&self, cx: &mut DrawContext) {
+        let region = cx.region();
+        let (horizontal, vertical) = (self.horizontal, self.vertical);
+        cx.mouse_region(region)
+            .on_scroll(move |cx| {
+                let delta = cx.scroll_delta();
+                // Mouse wheels only scroll vertically, so shift turns them sideways
+                let delta = if cx.modifiers().state().shift_key() && !cx.precise_scroll() {
+                    Vec2::new(delta.y, delta.x)
+                } else {
+                    delta
+                };
+                let delta = Vec2::new(
+                    if horizontal { delta.x } else { 0. },
+                    if vertical { delta.y } else { 0. },
+                );
+                cx.with_state(|state: &mut ScrollState, _| state.scroll_to(state.offset - delta));
+                cx.request_redraw();
+            })
+            .on_hover(|cx| {
+                if cx.with_state(|state: &mut ScrollState, cx| {
+                    state.update_shown(cx.frame_time(), |state| state.hovered = true)
+                }) {
+                    cx.request_redraw();
+                }
+            })
+            .on_leave(|cx| {
+                if cx.with_state(|state: &mut ScrollState, cx| {
+                    state.update_shown(cx.frame_time(), |state| state.hovered = false)
+                }) {
+                    cx.request_redraw();
+                }
+            });
+
+        cx.push_layer(&region);
+        self.child.draw(cx);
+        cx.pop_layer();
+
+        let (scrollbars, opacity) = cx.with_state(|state: &mut ScrollState, cx| {
+            (
+                state.scrollbars(horizontal, vertical),
+                state.scrollbar_opacity(cx.frame_time()),
+            )
+        });
+        cx.set_fill_brush(Brush::Solid(
+            self.scrollbar
+                .resolve(&cx.theme())
+                .multiply_alpha(opacity as f32),
+        ));
+        for scrollbar in scrollbars {
+            let Scrollbar {
+                thumb,
+                axis,
+                travel,
+            } = scrollbar;
+            // Registered after the child draws so that the thumbs take priority
+            cx.mouse_region(thumb.inflate(SCROLLBAR_MARGIN, SCROLLBAR_MARGIN))
+                .on_down(|cx| {
+                    if cx.with_state(|state: &mut ScrollState, cx| {
+                        state.update_shown(cx.frame_time(), |state| state.dragging = true)
+                    }) {
+                        cx.request_redraw();
+                    }
+                })
+                .on_drag(move |cx| {
+                    if let Some(delta) = cx.mouse_delta() {
+                        cx.with_state(|state: &mut ScrollState, _| {
+                            state.scroll_to(state.offset + axis * axis.dot(delta) * travel)
+                        });
+                        cx.request_redraw();
+                    }
+                });
+
+            if opacity > 0. {
+                cx.fill(&thumb.to_rounded_rect(SCROLLBAR_THICKNESS / 2.));
+            }
+        }
+    }
+
+    fn children(&self) -> Vec<Token> {
+        self.child.tokens()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::window::{Cursor, CursorIcon};
+
+    use super::*;
+    use crate::{
+        components::layout::{Align, Column},
+        test_runner::TestRunner,
+    };
+
+    // Takes focus when clicked.
+    struct Focusable;
+
+    impl Element for Focusable {
+        fn layout(&mut self, _min: Size, max: Size, _cx: &mut LayoutContext) -> Size {
+            Size::new(max.width, 60.)
+        }
+
+        fn draw(&self, cx: &mut DrawContext) {
+            let region = cx.region();
+            cx.mouse_region(region).on_down(|cx| cx.focus());
+        }
+    }
+
+    // Scroll view filling a 100 by 100 window over a column of ten rows 60 high. Returns the
+    // column's token.
+    fn scrolled_rows() -> (TestRunner<Align<ScrollView<Column>>>, Token) {
+        let mut column_token = None;
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |_| {
+            let column = (0..10).fold(Column::new(), |column, _| {
+                Column::with_child(column, ElementPointer::new(Focusable))
+            });
+            column_token = Some(column.token());
+            Align::center(ScrollView::new(column))
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        (test_runner, column_token.unwrap())
+    }
+
+    fn column_origin(test_runner: &TestRunner<Align<ScrollView<Column>>>, column: Token) -> Point {
+        test_runner.regions.borrow()[&column].0 * Point::ZERO
+    }
+
+    #[test]
+    fn scrolling_is_clamped_and_reveals_rects() {
+        let (test_runner, column) = scrolled_rows();
+        let scroll = |change: &dyn Fn(&ElementPointer<ScrollView<Column>>, &Context)| {
+            test_runner.with_root(|root, cx| change(&root.child, cx));
+            test_runner.layout();
+            column_origin(&test_runner, column)
+        };
+
+        let origin = scroll(&|view, cx| view.scroll_to_rect(Rect::new(0., 300., 10., 350.), cx));
+        assert_eq!(origin, Point::new(0., -250.));
+        // Rects already in view don't scroll
+        let origin = scroll(&|view, cx| view.scroll_to_rect(Rect::new(0., 260., 10., 300.), cx));
+        assert_eq!(origin, Point::new(0., -250.));
+        let origin = scroll(&|view, cx| view.scroll_to(Vec2::new(30., 10000.), cx));
+        assert_eq!(origin, Point::new(0., -500.));
+        test_runner.with_root(|root, cx| {
+            assert_eq!(root.child.scroll_offset(cx), Vec2::new(0., 500.));
+        });
+    }
+
+    #[test]
+    fn focused_descendants_are_scrolled_into_view() {
+        let (mut test_runner, column) = scrolled_rows();
+
+        // The second row is cut off by the bottom of the view
+        test_runner.move_mouse(Point::new(50., 80.));
+        test_runner.press_mouse();
+        test_runner.release_mouse();
+        assert_eq!(column_origin(&test_runner, column), Point::new(0., -20.));
+    }
+
+    #[test]
+    fn dragging_thumbs_scrolls() {
+        let (mut test_runner, column) = scrolled_rows();
+
+        test_runner.move_mouse(Point::new(95., 10.));
+        test_runner.press_mouse();
+        test_runner.move_mouse(Point::new(95., 30.));
+        test_runner.release_mouse();
+        let origin = column_origin(&test_runner, column);
+        assert!(origin.y < -100., "{origin:?}");
+    }
+}
@@ -6,6 +6,7 @@ use vello::{
 use crate::{
     context_stack::{DrawContext, LayoutContext, UpdateContext},
     element::{Element, ElementPointer},
+    theme::ThemeColor,
     token::Token,
     util::{PointExt, RectExt},
 };
@@ -18,7 +19,7 @@ const ICON_SHIFT: Vec2 = Vec2::new(0., -1.);
 const X_HEIGHT: f64 = 10.;
 
 pub struct WindowButtons {
-    title_background: Color,
+    title_background: ThemeColor,
 
     close: ElementPointer<Button>,
     maximize: ElementPointer<Button>,
@@ -26,40 +27,43 @@ pub struct WindowButtons {
 }
 
 impl WindowButtons {
-    pub fn new(
-        title_background: Color,
-        close_hover: Color,
-        other_hover: Color,
-        foreground: Color,
-    ) -> ElementPointer<Self> {
+    pub fn new() -> ElementPointer<Self> {
         let button_size = Size::new(TITLEBAR_HEIGHT * BUTTON_ASPECT_RATIO, TITLEBAR_HEIGHT);
         ElementPointer::new(Self {
-            title_background,
+            title_background: ThemeColor::Background(3),
 
             close: Button::new(
                 button_size,
-                Color::new([0., 0., 0., 0.]),
-                close_hover,
-                move |cx| Self::draw_close_icon(foreground, cx),
+                Color::TRANSPARENT,
+                ThemeColor::Danger,
+                |cx| Self::draw_close_icon(cx.theme().foreground, cx),
                 |cx| cx.close(),
             ),
             maximize: Button::new(
                 button_size,
-                Color::new([0., 0., 0., 0.]),
-                other_hover,
-                move |cx| Self::draw_maximize_icon(foreground, cx),
+                Color::TRANSPARENT,
+                ThemeColor::Background(4),
+                |cx| Self::draw_maximize_icon(cx.theme().foreground, cx),
                 |cx| cx.toggle_maximized(),
             ),
             minimize: Button::new(
                 button_size,
-                Color::new([0., 0., 0., 0.]),
-                other_hover,
-                move |cx| Self::draw_minimize_icon(foreground, cx),
+                Color::TRANSPARENT,
+                ThemeColor::Background(4),
+                |cx| Self::draw_minimize_icon(cx.theme().foreground, cx),
                 |cx| cx.minimize(),
             ),
         })
     }
 
+    pub fn with_title_background(
+        mut this: ElementPointer<Self>,
+        title_background: impl Into<ThemeColor>,
+    ) -> ElementPointer<Self> {
+        this.title_background = title_background.into();
+        this
+    }
+
     fn icon_rect(cx: &mut DrawContext) -> Rect {
         Rect::from_origin_size(
             (cx.region().center() - Vec2::new(X_HEIGHT / 2., X_HEIGHT / 2.)).snap(),
@@ -139,7 +143,7 @@ impl Element for WindowButtons {
     }
 
     fn draw(&self, cx: &mut DrawContext) {
-        cx.set_fill_brush(Brush::Solid(self.title_background));
+        cx.set_fill_brush(Brush::Solid(self.title_background.resolve(&cx.theme())));
 
         let region = cx.region();
         cx.fill(&region);
@@ -80,6 +80,7 @@ impl<'a> AttachedContext<'a> {
     }
 
     pub fn request_redraw(&self) {
+        self.context.invalidate_layout();
         self.window.request_redraw();
     }
 
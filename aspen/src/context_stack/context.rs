@@ -1,9 +1,9 @@
-use std::{any::Any, cell::RefCell, collections::{HashMap, hash_map::Entry}, default::Default, ops::Deref};
+use std::{any::Any, cell::RefCell, collections::{HashMap, HashSet, hash_map::Entry}, default::Default, ops::Deref, rc::Rc, time::Duration};
 
 use parley::{style::StyleProperty, Layout};
 use vello::peniko::Brush;
 
-use super::EventState;
+use super::{EventState, TimerHandle};
 
 use crate::{shaper::Shaper, token::Token};
 
@@ -13,6 +13,8 @@ pub struct Context<'a> {
     default_text_styles: Vec<StyleProperty<'static, Brush>>,
     pub(crate) states: &'a RefCell<HashMap<Token, Box<dyn Any>>>,
     focused_element: &'a RefCell<Option<Token>>,
+    // Elements whose cached layouts are stale
+    invalidated: &'a RefCell<HashSet<Token>>,
     pub(crate) element_token: Token,
     element_children: &'a Vec<Token>,
 }
@@ -30,6 +32,7 @@ impl<'a> Context<'a> {
         shaper: &'a RefCell<Shaper>,
         states: &'a RefCell<HashMap<Token, Box<dyn Any>>>,
         focused_element: &'a RefCell<Option<Token>>,
+        invalidated: &'a RefCell<HashSet<Token>>,
         element_token: Token,
         element_children: &'a Vec<Token>,
     ) -> Context<'a> {
@@ -39,6 +42,7 @@ impl<'a> Context<'a> {
             default_text_styles: Vec::new(),
             states,
             focused_element,
+            invalidated,
             element_token,
             element_children,
         }
@@ -86,9 +90,27 @@ impl<'a> Context<'a> {
         callback(state, self)
     }
 
+    /** Gets the element's state, invalidating its layout since the state may be changed. Use
+    `read_state` when only reading it */
     pub fn with_state<State: Any + Default, Result>(
         &self,
         callback: impl FnOnce(&mut State, &Self) -> Result,
+    ) -> Result {
+        self.invalidate_layout();
+        self.with_state_untracked(callback)
+    }
+
+    /** Reads the element's state without invalidating its layout */
+    pub fn read_state<State: Any + Default, Result>(
+        &self,
+        callback: impl FnOnce(&State, &Self) -> Result,
+    ) -> Result {
+        self.with_state_untracked(|state, cx| callback(state, cx))
+    }
+
+    pub(crate) fn with_state_untracked<State: Any + Default, Result>(
+        &self,
+        callback: impl FnOnce(&mut State, &Self) -> Result,
     ) -> Result {
         let mut states = self.states.borrow_mut();
         let state = match states.entry(self.element_token) {
@@ -117,11 +139,56 @@ impl<'a> Context<'a> {
             .map_or(false, |token| self.tokens().contains(token))
     }
 
+    pub fn focused_token(&self) -> Option<Token> {
+        *self.focused_element.borrow()
+    }
+
     pub fn focus(&self) {
         let mut focused_element = self.focused_element.borrow_mut();
+        // Layouts may depend on focus, such as scroll views revealing focused children
+        let mut invalidated = self.invalidated.borrow_mut();
+        invalidated.extend(*focused_element);
+        invalidated.insert(self.token());
         *focused_element = Some(self.token());
     }
 
+    /** Marks the element's cached layout as stale so that it and its ancestors are laid out again
+    during the next layout */
+    pub fn invalidate_layout(&self) {
+        self.invalidated.borrow_mut().insert(self.element_token);
+    }
+
+    /** Calls the callback with this element's context once the delay has passed on the frame
+    clock. Timers fire at the start of a frame, which is then redrawn */
+    pub fn set_timeout(&self, delay: Duration, callback: impl Fn(&Context) + 'static) -> TimerHandle {
+        self.event_state.timers.borrow_mut().add(
+            self.element_token,
+            self.element_children.clone(),
+            self.frame_time + delay,
+            None,
+            Rc::new(callback),
+        )
+    }
+
+    /** Calls the callback with this element's context every period until cancelled */
+    pub fn set_interval(&self, period: Duration, callback: impl Fn(&Context) + 'static) -> TimerHandle {
+        self.event_state.timers.borrow_mut().add(
+            self.element_token,
+            self.element_children.clone(),
+            self.frame_time + period,
+            Some(period),
+            Rc::new(callback),
+        )
+    }
+
+    pub fn cancel_timer(&self, handle: TimerHandle) {
+        self.event_state.timers.borrow_mut().cancel(handle);
+    }
+
+    pub fn timer_active(&self, handle: TimerHandle) -> bool {
+        self.event_state.timers.borrow().is_active(handle)
+    }
+
     pub fn token(&self) -> Token {
         self.element_token
     }
@@ -146,6 +213,7 @@ impl<'a> Context<'a> {
             states: self.states,
             default_text_styles: self.default_text_styles.clone(),
             focused_element: self.focused_element,
+            invalidated: self.invalidated,
             element_token,
             element_children,
         }
@@ -19,6 +19,17 @@ use crate::{
     token::Token,
 };
 
+// Drawn output of an element kept to be appended again in later frames instead of drawing it,
+// along with the mouse regions registered while drawing it.
+pub(crate) struct SceneFragment {
+    scene: Scene,
+    // Window space transform the element was drawn with
+    transform: Affine,
+    // Number of clips on the stack which came from the element's ancestors
+    inherited_clips: usize,
+    regions: Vec<MouseRegion>,
+}
+
 pub struct DrawContext<'a> {
     context: AttachedContext<'a>,
     mouse_region_manager: &'a mut MouseRegionManager,
@@ -241,6 +252,46 @@ impl<'a> DrawContext<'a> {
         }
     }
 
+    // Draws into a fragment which can be replayed in later frames, appending it to the scene.
+    pub(crate) fn record(&mut self, draw: impl FnOnce(&mut Self)) -> SceneFragment {
+        let first_region = self.mouse_region_manager.mouse_regions.len();
+        let outer = std::mem::take(self.scene);
+        draw(self);
+        let scene = std::mem::replace(self.scene, outer);
+        self.scene.append(&scene, None);
+
+        SceneFragment {
+            scene,
+            transform: self.current_transform(),
+            inherited_clips: self.clip_stack.len(),
+            regions: self.mouse_region_manager.mouse_regions[first_region..].to_vec(),
+        }
+    }
+
+    // Appends a recorded fragment moved to the current transform along with its mouse regions.
+    // Returns false without drawing if the transform changed by more than a translation, since
+    // the fragment would have been encoded differently.
+    pub(crate) fn replay(&mut self, fragment: &SceneFragment) -> bool {
+        let transform = self.current_transform();
+        let [a, b, c, d, _, _] = transform.as_coeffs();
+        let [recorded_a, recorded_b, recorded_c, recorded_d, _, _] = fragment.transform.as_coeffs();
+        if [a, b, c, d] != [recorded_a, recorded_b, recorded_c, recorded_d] {
+            return false;
+        }
+
+        let offset =
+            Affine::translate(transform.translation() - fragment.transform.translation());
+        self.scene.append(&fragment.scene, Some(offset));
+        for region in fragment.regions.iter() {
+            self.mouse_region_manager.add_region(region.replayed(
+                offset,
+                fragment.inherited_clips,
+                &self.clip_stack,
+            ));
+        }
+        true
+    }
+
     pub fn element_transform(&self) -> Affine {
         self.element_transform
     }
@@ -396,6 +447,18 @@ impl<'a> DrawContext<'a> {
             ))
     }
 
+    pub fn size_of<Other: Element>(&self, other: &ElementPointer<Other>) -> Size {
+        self.regions
+            .get(&other.token())
+            .map(|(_, size)| *size)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Layout must not have been completed for this element before drawing: {:?}",
+                    other.token()
+                )
+            })
+    }
+
     pub fn transform_of<Other: Element>(&self, other: &ElementPointer<Other>) -> Affine {
         self.transform_by_token(other.token())
     }
@@ -431,6 +494,12 @@ impl<'a> DrawContext<'a> {
         self.any_in_progress_mouse_regions_recursive(self.token())
     }
 
+    /** Checks a child for in progress mouse regions before it draws, so that elements which cull
+    their children themselves can keep drawing the ones being interacted with */
+    pub fn child_has_in_progress_mouse_regions(&self, child: Token) -> bool {
+        self.any_in_progress_mouse_regions_recursive(child)
+    }
+
     pub fn with_initialized_state<State: Any, Result>(&mut self, callback: impl FnOnce(&mut State, &mut DrawContext<'a>) -> Result) -> Result {
         let mut states = self.states.borrow_mut();
         let state = states
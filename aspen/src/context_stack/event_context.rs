@@ -52,6 +52,7 @@ impl<'a> EventContext<'a> {
     }
 
     pub fn request_redraw(&mut self) {
+        self.invalidate_layout();
         *self.redraw_requested = true;
     }
 
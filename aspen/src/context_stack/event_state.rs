@@ -1,6 +1,16 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
 use vello::kurbo::{Point, Rect, Size, Vec2};
 use winit::{event::{ElementState, Modifiers}, keyboard::Key};
 
+use super::Timers;
+
+use crate::theme::Theme;
+
 pub struct EventState {
     pub window_size: Size,
 
@@ -8,12 +18,48 @@ pub struct EventState {
     pub previous_mouse_position: Option<Point>,
     pub mouse_down: bool,
     pub right_mouse_down: bool,
+    pub middle_mouse_down: bool,
     pub was_mouse_down: bool,
     pub was_right_mouse_down: bool,
+    pub was_middle_mouse_down: bool,
     pub scroll_delta: Vec2,
+    // True if the scroll came in pixels rather than lines, as it does from trackpads
+    pub precise_scroll: bool,
+    // Change in magnification from pinch gestures, positive when zooming in
+    pub pinch_delta: f64,
 
     pub modifiers: Modifiers,
     pub key_events: Vec<KeyEvent>,
+
+    pub clock: Clock,
+    // Time the current frame started at according to the clock
+    pub frame_time: Instant,
+    // Time since the previous frame, which may be long after the app has been idle
+    pub frame_delta: Duration,
+    animation_frame_requested: Cell<bool>,
+    pub timers: RefCell<Timers>,
+
+    theme: RefCell<Rc<Theme>>,
+    theme_pending: Cell<bool>,
+    // True during the frame after the theme was switched
+    theme_changed: bool,
+}
+
+/// Source of frame times. Fake clocks only move when advanced, which lets tests step through
+/// animations and timers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clock {
+    System,
+    Fake(Instant),
+}
+
+impl Clock {
+    pub fn now(&self) -> Instant {
+        match self {
+            Clock::System => Instant::now(),
+            Clock::Fake(now) => *now,
+        }
+    }
 }
 
 impl EventState {
@@ -25,24 +71,87 @@ impl EventState {
             previous_mouse_position: None,
             mouse_down: false,
             right_mouse_down: false,
+            middle_mouse_down: false,
             was_mouse_down: false,
             was_right_mouse_down: false,
+            was_middle_mouse_down: false,
             scroll_delta: Vec2::new(0., 0.),
+            precise_scroll: false,
+            pinch_delta: 0.,
 
             modifiers: Modifiers::default(),
             key_events: Vec::new(),
+
+            clock: Clock::System,
+            frame_time: Instant::now(),
+            frame_delta: Duration::ZERO,
+            animation_frame_requested: Cell::new(false),
+            timers: RefCell::new(Timers::new()),
+
+            theme: RefCell::new(Rc::new(Theme::default())),
+            theme_pending: Cell::new(false),
+            theme_changed: false,
         }
     }
 
+    // Samples the clock for a new frame and clears the previous frame's animation frame request
+    pub fn begin_frame(&mut self) {
+        let now = self.clock.now();
+        self.frame_delta = now.saturating_duration_since(self.frame_time);
+        self.frame_time = now;
+        self.animation_frame_requested.set(false);
+        self.theme_changed = self.theme_pending.take();
+    }
+
     pub fn next_frame(&mut self) {
         self.was_mouse_down = self.mouse_down;
         self.was_right_mouse_down = self.right_mouse_down;
+        self.was_middle_mouse_down = self.middle_mouse_down;
         self.previous_mouse_position = self.mouse_position;
         self.scroll_delta = Vec2::new(0., 0.);
+        self.precise_scroll = false;
+        self.pinch_delta = 0.;
 
         self.key_events.clear();
     }
 
+    // Time Functions
+    pub fn frame_time(&self) -> Instant {
+        self.frame_time
+    }
+
+    pub fn frame_delta(&self) -> Duration {
+        self.frame_delta
+    }
+
+    /** Asks for another frame as soon as the display can show it. Request one every frame while
+    animating, since otherwise the app sleeps until the next event or timer */
+    pub fn request_animation_frame(&self) {
+        self.animation_frame_requested.set(true);
+    }
+
+    pub fn animation_frame_requested(&self) -> bool {
+        self.animation_frame_requested.get()
+    }
+
+    // Theme Functions
+    pub fn theme(&self) -> Rc<Theme> {
+        self.theme.borrow().clone()
+    }
+
+    /** Switches the theme. Every element is laid out and drawn again on the next frame */
+    pub fn set_theme(&self, theme: Theme) {
+        *self.theme.borrow_mut() = Rc::new(theme);
+        self.theme_pending.set(true);
+        self.request_animation_frame();
+    }
+
+    /** True during the frame after the theme was switched, for elements which cache anything
+    derived from it */
+    pub fn theme_changed(&self) -> bool {
+        self.theme_changed
+    }
+
     // Window Functions
     pub fn actual_window_size(&self) -> Size {
         self.window_size
@@ -61,6 +170,10 @@ impl EventState {
         self.right_mouse_down
     }
 
+    pub fn middle_mouse_down(&self) -> bool {
+        self.middle_mouse_down
+    }
+
     pub fn was_mouse_down(&self) -> bool {
         self.was_mouse_down
     }
@@ -69,6 +182,10 @@ impl EventState {
         self.was_right_mouse_down
     }
 
+    pub fn was_middle_mouse_down(&self) -> bool {
+        self.was_middle_mouse_down
+    }
+
     pub fn mouse_released(&self) -> bool {
         !self.mouse_down && self.was_mouse_down
     }
@@ -77,6 +194,10 @@ impl EventState {
         !self.right_mouse_down && self.was_right_mouse_down
     }
 
+    pub fn middle_mouse_released(&self) -> bool {
+        !self.middle_mouse_down && self.was_middle_mouse_down
+    }
+
     pub fn mouse_just_down(&self) -> bool {
         self.mouse_down && !self.was_mouse_down
     }
@@ -85,6 +206,10 @@ impl EventState {
         self.right_mouse_down && !self.was_right_mouse_down
     }
 
+    pub fn middle_mouse_just_down(&self) -> bool {
+        self.middle_mouse_down && !self.was_middle_mouse_down
+    }
+
     pub fn mouse_held(&self) -> bool {
         self.mouse_down && self.was_mouse_down
     }
@@ -111,6 +236,14 @@ impl EventState {
         self.scroll_delta
     }
 
+    pub fn precise_scroll(&self) -> bool {
+        self.precise_scroll
+    }
+
+    pub fn pinch_delta(&self) -> f64 {
+        self.pinch_delta
+    }
+
     // Keyboard Functions
     pub fn modifiers(&self) -> Modifiers {
         self.modifiers
@@ -14,6 +14,10 @@ pub struct LayoutContext<'a> {
     context: AttachedContext<'a>,
     regions: &'a mut HashMap<Token, (Affine, Size)>,
     children: &'a mut HashMap<Token, HashSet<Token>>,
+    // Child lookup recorded during the previous layout
+    previous_children: &'a HashMap<Token, HashSet<Token>>,
+    // Elements invalidated since the previous layout which haven't been laid out again yet
+    invalidated: &'a mut HashSet<Token>,
 }
 
 impl<'a> Deref for LayoutContext<'a> {
@@ -35,11 +39,15 @@ impl<'a> LayoutContext<'a> {
         context: AttachedContext<'a>,
         regions: &'a mut HashMap<Token, (Affine, Size)>,
         children: &'a mut HashMap<Token, HashSet<Token>>,
+        previous_children: &'a HashMap<Token, HashSet<Token>>,
+        invalidated: &'a mut HashSet<Token>,
     ) -> LayoutContext<'a> {
         LayoutContext {
             context,
             regions,
             children,
+            previous_children,
+            invalidated,
         }
     }
 
@@ -51,6 +59,46 @@ impl<'a> LayoutContext<'a> {
         self.regions.insert(element_token, (transform, size));
     }
 
+    // True if any of the elements were invalidated since the previous layout.
+    pub(crate) fn any_invalidated(&self, tokens: &[Token]) -> bool {
+        !self.invalidated.is_empty() && tokens.iter().any(|token| self.invalidated.contains(token))
+    }
+
+    // Once an element has been laid out its invalidation is used up, so that laying it out again
+    // during the same layout can reuse the result.
+    pub(crate) fn mark_laid_out(&mut self, token: Token) {
+        self.invalidated.remove(&token);
+    }
+
+    // Records the children of the elements as they were during the previous layout. Used when a
+    // subtree's cached layout is reused, which leaves the regions from the previous layout in
+    // place.
+    pub(crate) fn reuse_children(&mut self, tokens: &[Token]) {
+        for token in tokens {
+            if let Some(children) = self.previous_children.get(token) {
+                self.children
+                    .entry(*token)
+                    .or_default()
+                    .extend(children.iter().copied());
+            }
+        }
+    }
+
+    /** Returns the transform and size of a descendant relative to an ancestor if both have been
+    positioned during this layout */
+    pub fn region_within(&self, ancestor: Token, descendant: Token) -> Option<(Affine, Size)> {
+        for child in self.children.get(&ancestor)?.iter() {
+            let (transform, size) = self.regions.get(child).copied()?;
+            if *child == descendant {
+                return Some((transform, size));
+            }
+            if let Some((inner, size)) = self.region_within(*child, descendant) {
+                return Some((transform * inner, size));
+            }
+        }
+        None
+    }
+
     pub fn with_initialized_state<State: Any, Result>(&mut self, callback: impl FnOnce(&mut State, &mut LayoutContext<'a>) -> Result) -> Result {
         let mut states = self.states.borrow_mut();
         let state = states
@@ -95,6 +143,8 @@ impl<'a> LayoutContext<'a> {
             context: child_cx,
             regions: self.regions,
             children: self.children,
+            previous_children: self.previous_children,
+            invalidated: self.invalidated,
         }
     }
 }
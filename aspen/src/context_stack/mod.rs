@@ -4,6 +4,7 @@ pub mod draw_context;
 pub mod event_context;
 pub mod event_state;
 pub mod layout_context;
+pub mod timers;
 pub mod update_context;
 
 pub use attached_context::*;
@@ -12,4 +13,5 @@ pub use draw_context::*;
 pub use event_context::*;
 pub use event_state::*;
 pub use layout_context::*;
+pub use timers::*;
 pub use update_context::*;
@@ -0,0 +1,182 @@
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use super::Context;
+
+use crate::token::Token;
+
+pub(crate) type TimerCallback = Rc<dyn Fn(&Context)>;
+
+/// Handle to a timeout or interval which can be used to cancel it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerHandle(u64);
+
+struct Timer {
+    handle: TimerHandle,
+    // Element the callback is run as, along with its children when the timer was set
+    owner: Token,
+    children: Vec<Token>,
+    deadline: Instant,
+    interval: Option<Duration>,
+    callback: TimerCallback,
+}
+
+pub(crate) struct DueTimer {
+    pub owner: Token,
+    pub children: Vec<Token>,
+    pub callback: TimerCallback,
+}
+
+#[derive(Default)]
+pub struct Timers {
+    next_handle: u64,
+    timers: Vec<Timer>,
+}
+
+impl Timers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn add(
+        &mut self,
+        owner: Token,
+        children: Vec<Token>,
+        deadline: Instant,
+        interval: Option<Duration>,
+        callback: TimerCallback,
+    ) -> TimerHandle {
+        let handle = TimerHandle(self.next_handle);
+        self.next_handle += 1;
+        self.timers.push(Timer {
+            handle,
+            owner,
+            children,
+            deadline,
+            interval,
+            callback,
+        });
+        handle
+    }
+
+    pub fn cancel(&mut self, handle: TimerHandle) {
+        self.timers.retain(|timer| timer.handle != handle);
+    }
+
+    // Drops the timers of owners which no longer exist
+    pub(crate) fn retain_owners(&mut self, alive: impl Fn(Token) -> bool) {
+        self.timers.retain(|timer| alive(timer.owner));
+    }
+
+    pub fn is_active(&self, handle: TimerHandle) -> bool {
+        self.timers.iter().any(|timer| timer.handle == handle)
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.timers.iter().map(|timer| timer.deadline).min()
+    }
+
+    // Removes the timers which are due, rescheduling intervals, and returns them in the order
+    // they were due. Intervals which missed several periods only fire once.
+    pub(crate) fn take_due(&mut self, now: Instant) -> Vec<DueTimer> {
+        let mut due: Vec<(Instant, DueTimer)> = Vec::new();
+        self.timers.retain_mut(|timer| {
+            if timer.deadline > now {
+                return true;
+            }
+            due.push((
+                timer.deadline,
+                DueTimer {
+                    owner: timer.owner,
+                    children: timer.children.clone(),
+                    callback: timer.callback.clone(),
+                },
+            ));
+            let Some(interval) = timer.interval else {
+                return false;
+            };
+            timer.deadline += interval;
+            if timer.deadline <= now {
+                timer.deadline = now + interval;
+            }
+            true
+        });
+        due.sort_by_key(|(deadline, _)| *deadline);
+        due.into_iter().map(|(_, timer)| timer).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use vello::kurbo::Size;
+    use winit::window::{Cursor, CursorIcon};
+
+    use super::*;
+    use crate::{components::layout::SizedBox, test_runner::TestRunner};
+
+    #[test]
+    fn timers_fire_on_the_frame_clock() {
+        let fired = Rc::new(Cell::new(0));
+        let mut interval = None;
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |cx| {
+            let timeout_fired = fired.clone();
+            cx.set_timeout(Duration::from_millis(100), move |_| {
+                timeout_fired.set(timeout_fired.get() + 1)
+            });
+            let interval_fired = fired.clone();
+            interval = Some(cx.set_interval(Duration::from_millis(30), move |_| {
+                interval_fired.set(interval_fired.get() + 10)
+            }));
+            SizedBox::new(Size::new(10., 10.))
+        });
+        let interval = interval.unwrap();
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+
+        test_runner.tick();
+        assert_eq!(fired.get(), 0);
+        test_runner.advance_time(Duration::from_millis(50));
+        assert_eq!(fired.get(), 10);
+        // Missed periods of the interval only fire once
+        test_runner.advance_time(Duration::from_millis(60));
+        assert_eq!(fired.get(), 21);
+
+        test_runner.with_root(|_, cx| cx.cancel_timer(interval));
+        assert_eq!(test_runner.next_timer_deadline(), None);
+        test_runner.advance_time(Duration::from_millis(100));
+        assert_eq!(fired.get(), 21);
+    }
+
+    #[test]
+    fn timers_only_redraw_and_live_with_their_elements() {
+        let fired = Rc::new(Cell::new(0));
+        let mut timers = Vec::new();
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |cx| {
+            let idle_fired = fired.clone();
+            timers.push(cx.set_interval(Duration::from_millis(10), move |_| {
+                idle_fired.set(idle_fired.get() + 1)
+            }));
+            // Element which is never added to the tree, as if it was dropped
+            let dropped = SizedBox::new(Size::new(10., 10.));
+            timers.push(dropped.with_context(cx, |cx| {
+                cx.set_interval(Duration::from_millis(10), |_| panic!("owner was dropped"))
+            }));
+            SizedBox::new(Size::new(10., 10.))
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        test_runner.tick();
+
+        // Timers which change nothing don't cause a redraw
+        assert!(test_runner
+            .advance_time(Duration::from_millis(10))
+            .is_none());
+        assert_eq!(fired.get(), 1);
+        test_runner.with_root(|_, cx| {
+            assert!(cx.timer_active(timers[0]));
+            assert!(!cx.timer_active(timers[1]));
+        });
+    }
+}
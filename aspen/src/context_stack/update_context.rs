@@ -1,8 +1,15 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    any::Any,
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
+
+use vello::kurbo::{Affine, Point, Rect, Size};
 
-use super::AttachedContext;
+use super::{AttachedContext, Context};
 
 use crate::{
+    element::{Element, ElementPointer},
     mouse_region::{MouseRegion, MouseRegionManager},
     token::Token,
 };
@@ -11,6 +18,8 @@ pub struct UpdateContext<'a> {
     context: AttachedContext<'a>,
     mouse_region_manager: &'a mut MouseRegionManager,
     redraw_requested: &'a mut bool,
+    // Regions recorded by the previous layout pass
+    regions: &'a HashMap<Token, (Affine, Size)>,
 }
 
 impl<'a> Deref for UpdateContext<'a> {
@@ -32,11 +41,13 @@ impl<'a> UpdateContext<'a> {
         context: AttachedContext<'a>,
         mouse_region_manager: &'a mut MouseRegionManager,
         redraw_requested: &'a mut bool,
+        regions: &'a HashMap<Token, (Affine, Size)>,
     ) -> UpdateContext<'a> {
         UpdateContext {
             context,
             mouse_region_manager,
             redraw_requested,
+            regions,
         }
     }
 
@@ -45,9 +56,31 @@ impl<'a> UpdateContext<'a> {
     }
 
     pub fn request_redraw(&mut self) {
+        self.invalidate_layout();
         *self.redraw_requested = true;
     }
 
+    /** Gets the element's state without invalidating its layout, since updates run every frame.
+    Request a redraw after changing state which layout depends on */
+    pub fn with_state<State: Any + Default, Result>(
+        &self,
+        callback: impl FnOnce(&mut State, &Context<'a>) -> Result,
+    ) -> Result {
+        self.context.with_state_untracked(callback)
+    }
+
+    /** Returns this element's region from the previous layout if there was one */
+    pub fn region(&self) -> Option<Rect> {
+        self.regions
+            .get(&self.token())
+            .map(|(_, size)| Rect::from_origin_size(Point::ZERO, *size))
+    }
+
+    /** Returns the size computed for the element during the previous layout if there was one */
+    pub fn size_of<Other: Element>(&self, other: &ElementPointer<Other>) -> Option<Size> {
+        self.regions.get(&other.token()).map(|(_, size)| *size)
+    }
+
     pub fn child<'b>(
         &'b mut self,
         element_token: Token,
@@ -61,6 +94,7 @@ impl<'a> UpdateContext<'a> {
             context: child_cx,
             mouse_region_manager: self.mouse_region_manager,
             redraw_requested: self.redraw_requested,
+            regions: self.regions,
         }
     }
 }
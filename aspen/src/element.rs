@@ -1,12 +1,13 @@
 use std::{
     any::Any,
+    cell::RefCell,
     ops::{Deref, DerefMut},
 };
 
 use vello::kurbo::{Affine, Point, Rect, Size};
 
 use crate::{
-    context_stack::{Context, DrawContext, LayoutContext, UpdateContext},
+    context_stack::{Context, DrawContext, LayoutContext, SceneFragment, UpdateContext},
     token::Token,
 };
 
@@ -17,6 +18,13 @@ pub trait Element {
     fn children(&self) -> Vec<Token> {
         Vec::new()
     }
+
+    // Opts into keeping the element's drawn output to be reused by later frames until its layout
+    // runs again or it is moved by more than a translation. Only for elements whose drawing
+    // depends on nothing but their state and layout.
+    fn retain_drawing(&self) -> bool {
+        false
+    }
 }
 
 impl<E: Element + ?Sized> Element for Box<E> {
@@ -35,11 +43,26 @@ impl<E: Element + ?Sized> Element for Box<E> {
     fn children(&self) -> Vec<Token> {
         self.as_ref().children()
     }
+
+    fn retain_drawing(&self) -> bool {
+        self.as_ref().retain_drawing()
+    }
 }
 
 pub struct ElementPointer<E: Element> {
     token: Token,
     element: E,
+    layout_cache: Option<LayoutCache>,
+    drawing: RefCell<Option<SceneFragment>>,
+}
+
+// Constraints and result of an element's last layout along with the descendants it had. Reused
+// until the constraints or descendants change or one of them is invalidated.
+struct LayoutCache {
+    min: Size,
+    max: Size,
+    size: Size,
+    children: Vec<Token>,
 }
 
 impl<E: Element> ElementPointer<E> {
@@ -47,6 +70,8 @@ impl<E: Element> ElementPointer<E> {
         Self {
             token: Token::new::<E>(),
             element,
+            layout_cache: None,
+            drawing: RefCell::new(None),
         }
     }
 
@@ -54,6 +79,8 @@ impl<E: Element> ElementPointer<E> {
         ElementPointer {
             token: self.token,
             element: map(self.element),
+            layout_cache: None,
+            drawing: RefCell::new(None),
         }
     }
 
@@ -70,8 +97,29 @@ impl<E: Element> ElementPointer<E> {
     #[must_use]
     pub fn layout<'a>(&mut self, min: Size, max: Size, cx: &mut LayoutContext) -> LayoutResult {
         let children = self.children();
+        if let Some(cache) = self.layout_cache.as_ref() {
+            let unchanged = cache.min == min && cache.max == max && cache.children == children;
+            let tokens = self.tokens();
+            if unchanged && !cx.any_invalidated(&tokens) {
+                // Descendants keep the regions they were given last time
+                cx.reuse_children(&tokens);
+                return LayoutResult {
+                    size: cache.size,
+                    token: self.token,
+                };
+            }
+        }
+
         let mut child_cx = cx.child(self.token, &children);
         let size = self.element.layout(min, max, &mut child_cx).clamp(min, max);
+        cx.mark_laid_out(self.token);
+        *self.drawing.get_mut() = None;
+        self.layout_cache = Some(LayoutCache {
+            min,
+            max,
+            size,
+            children,
+        });
         LayoutResult {
             size,
             token: self.token,
@@ -96,7 +144,19 @@ impl<E: Element> ElementPointer<E> {
             }
         }
 
-        self.element.draw(&mut child_cx);
+        if !self.element.retain_drawing() {
+            self.element.draw(&mut child_cx);
+            return;
+        }
+
+        let mut drawing = self.drawing.borrow_mut();
+        if drawing
+            .as_ref()
+            .is_some_and(|fragment| child_cx.replay(fragment))
+        {
+            return;
+        }
+        *drawing = Some(child_cx.record(|cx| self.element.draw(cx)));
     }
 
     // Returns a list of tokens associated with this element. Includes the element's token and all
@@ -142,6 +202,14 @@ impl<E: Element> ElementPointer<E> {
     ) -> Result {
         self.with_context(cx, |cx| cx.with_state(callback))
     }
+
+    pub fn read_state<'a, State: Any + Default, Result>(
+        &self,
+        cx: &Context<'a>,
+        callback: impl FnOnce(&State, &Context) -> Result,
+    ) -> Result {
+        self.with_context(cx, |cx| cx.read_state(callback))
+    }
 }
 
 impl<E: Element> Deref for ElementPointer<E> {
@@ -153,7 +221,10 @@ impl<E: Element> Deref for ElementPointer<E> {
 }
 
 impl<E: Element> DerefMut for ElementPointer<E> {
+    // Changing an element directly may change its layout
     fn deref_mut(&mut self) -> &mut Self::Target {
+        self.layout_cache = None;
+        *self.drawing.get_mut() = None;
         &mut self.element
     }
 }
@@ -188,3 +259,188 @@ impl Deref for LayoutResult {
         &self.size
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use vello::kurbo::{Shape, Vec2};
+
+    use super::*;
+    use crate::{components::layout::Column, test_runner::TestRunner};
+
+    // Counts its layouts and grows when asked to during an update.
+    struct Counted {
+        size: Size,
+        layouts: Rc<Cell<usize>>,
+        grow: Rc<Cell<bool>>,
+    }
+
+    impl Counted {
+        fn new(layouts: &Rc<Cell<usize>>, grow: &Rc<Cell<bool>>) -> ElementPointer<Self> {
+            ElementPointer::new(Self {
+                size: Size::new(50., 20.),
+                layouts: layouts.clone(),
+                grow: grow.clone(),
+            })
+        }
+    }
+
+    impl Element for Counted {
+        fn update(&mut self, cx: &mut UpdateContext) {
+            if self.grow.take() {
+                self.size.height += 10.;
+                cx.request_redraw();
+            }
+        }
+
+        fn layout(&mut self, _min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+            self.layouts.set(self.layouts.get() + 1);
+            self.size
+        }
+    }
+
+    // Sizes itself from a width kept in its state.
+    struct Stateful {
+        layouts: Rc<Cell<usize>>,
+    }
+
+    impl Element for Stateful {
+        fn layout(&mut self, _min: Size, _max: Size, cx: &mut LayoutContext) -> Size {
+            self.layouts.set(self.layouts.get() + 1);
+            cx.with_state(|width: &mut f64, _| Size::new(*width, 10.))
+        }
+    }
+
+    // Retained element which counts its draws.
+    struct Drawn {
+        draws: Rc<Cell<usize>>,
+    }
+
+    impl Element for Drawn {
+        fn layout(&mut self, _min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+            Size::new(40., 40.)
+        }
+
+        fn draw(&self, cx: &mut DrawContext) {
+            self.draws.set(self.draws.get() + 1);
+            let region = cx.region();
+            cx.mouse_region(region);
+            cx.fill(&region);
+        }
+
+        fn retain_drawing(&self) -> bool {
+            true
+        }
+    }
+
+    // Places its child at an offset without changing the child's constraints.
+    struct Offset {
+        child: ElementPointer<Drawn>,
+        offset: Rc<Cell<Vec2>>,
+    }
+
+    impl Element for Offset {
+        fn layout(&mut self, _min: Size, max: Size, cx: &mut LayoutContext) -> Size {
+            self.child
+                .layout(Size::ZERO, max, cx)
+                .position(Affine::translate(self.offset.get()), cx);
+            max
+        }
+
+        fn draw(&self, cx: &mut DrawContext) {
+            self.child.draw(cx);
+        }
+
+        fn children(&self) -> Vec<Token> {
+            self.child.tokens()
+        }
+    }
+
+    #[test]
+    fn retained_drawings_are_replayed_when_moved() {
+        let draws = Rc::new(Cell::new(0));
+        let offset = Rc::new(Cell::new(Vec2::ZERO));
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |_| {
+            ElementPointer::new(Offset {
+                child: ElementPointer::new(Drawn {
+                    draws: draws.clone(),
+                }),
+                offset: offset.clone(),
+            })
+        });
+        let redraw = |test_runner: &mut TestRunner<Offset>| {
+            let child_lookup = test_runner.layout();
+            test_runner.draw(child_lookup);
+            let manager = test_runner.mouse_region_manager.borrow();
+            manager.mouse_regions[0].region.bounding_box()
+        };
+
+        redraw(&mut test_runner);
+        offset.set(Vec2::new(30., 10.));
+        test_runner.with_root(|_, cx| cx.invalidate_layout());
+        let moved_region = redraw(&mut test_runner);
+        assert_eq!(draws.get(), 1);
+        assert_eq!(moved_region, Rect::new(30., 10., 70., 50.));
+
+        // Invalidated elements are drawn again
+        test_runner.with_root(|root, cx| root.child.with_context(cx, |cx| cx.invalidate_layout()));
+        redraw(&mut test_runner);
+        assert_eq!(draws.get(), 2);
+    }
+
+    #[test]
+    fn reading_state_keeps_the_cached_layout() {
+        let layouts = Rc::new(Cell::new(0));
+        let test_runner = TestRunner::new(Size::new(100., 100.), |_| {
+            ElementPointer::new(Stateful {
+                layouts: layouts.clone(),
+            })
+        });
+
+        test_runner.layout();
+        let width = |cx: &Context| cx.read_state(|width: &f64, _| *width);
+        test_runner.with_root(|_, cx| assert_eq!(width(cx), 0.));
+        test_runner.layout();
+        assert_eq!(layouts.get(), 1);
+
+        test_runner.with_root(|_, cx| cx.with_state(|width: &mut f64, _| *width = 30.));
+        test_runner.layout();
+        assert_eq!(layouts.get(), 2);
+        test_runner.with_root(|_, cx| assert_eq!(width(cx), 30.));
+    }
+
+    #[test]
+    fn untouched_subtrees_reuse_their_layouts() {
+        let (first_layouts, second_layouts) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+        let (grow_first, grow_second) = (Rc::new(Cell::new(false)), Rc::new(Cell::new(false)));
+        let mut tokens = Vec::new();
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |_| {
+            let first = Counted::new(&first_layouts, &grow_first);
+            let second = Counted::new(&second_layouts, &grow_second);
+            tokens = vec![first.token(), second.token()];
+            let column = Column::with_child(Column::new(), first);
+            Column::with_child(column, second)
+        });
+        let root = test_runner.token();
+        let counts = || (first_layouts.get(), second_layouts.get());
+
+        test_runner.layout();
+        let child_lookup = test_runner.layout();
+        assert_eq!(counts(), (1, 1));
+        // Skipped subtrees still report their children
+        assert!(child_lookup[&root].contains(&tokens[1]));
+
+        grow_first.set(true);
+        test_runner.update();
+        test_runner.layout();
+        assert_eq!(counts(), (2, 1));
+        // The column is laid out again, moving the untouched child below the grown one
+        let (transform, _) = test_runner.regions.borrow()[&tokens[1]];
+        assert_eq!(transform * Point::ZERO, Point::new(0., 30.));
+
+        test_runner.application.event_state.window_size = Size::new(200., 100.);
+        test_runner.layout();
+        assert_eq!(counts(), (3, 2));
+    }
+}
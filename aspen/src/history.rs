@@ -0,0 +1,327 @@
+use std::{any::Any, collections::VecDeque, mem};
+
+use crate::context_stack::Context;
+
+// Roughly how many bytes of commands a history keeps before dropping the oldest entries
+const DEFAULT_MEMORY_CAP: usize = 16 * 1024 * 1024;
+
+/// A reversible edit of some target. Commands are applied once when executed and then reverted
+/// and reapplied as the user undoes and redoes them.
+pub trait Command<Target>: Any {
+    fn apply(&mut self, target: &mut Target, cx: &Context);
+    fn revert(&mut self, target: &mut Target, cx: &Context);
+
+    // Folds the command recorded right after this one into it, returning false if the two
+    // should stay separate undo steps.
+    fn merge(&mut self, _next: &dyn Any) -> bool {
+        false
+    }
+
+    // Approximate memory used by the command, counted against the history's memory cap.
+    fn size(&self) -> usize {
+        mem::size_of_val(self)
+    }
+}
+
+/// Group of commands which are undone and redone together.
+pub struct Transaction<Target> {
+    commands: Vec<Box<dyn Command<Target>>>,
+}
+
+impl<Target: 'static> Default for Transaction<Target> {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl<Target: 'static> Transaction<Target> {
+    fn push(&mut self, command: Box<dyn Command<Target>>) {
+        if let Some(last) = self.commands.last_mut() {
+            if last.merge(&*command as &dyn Any) {
+                return;
+            }
+        }
+        self.commands.push(command);
+    }
+
+    fn apply(&mut self, target: &mut Target, cx: &Context) {
+        for command in self.commands.iter_mut() {
+            command.apply(target, cx);
+        }
+    }
+
+    fn revert(&mut self, target: &mut Target, cx: &Context) {
+        for command in self.commands.iter_mut().rev() {
+            command.revert(target, cx);
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.commands.iter().map(|command| command.size()).sum()
+    }
+}
+
+/// Undo and redo stacks of commands applied to a target.
+pub struct History<Target> {
+    undo: VecDeque<Transaction<Target>>,
+    redo: Vec<Transaction<Target>>,
+    open: Option<Transaction<Target>>,
+    depth: usize,
+    memory_cap: usize,
+    // Set after undo, redo or a closed transaction so that the next command starts a new step
+    sealed: bool,
+}
+
+impl<Target: 'static> Default for History<Target> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MEMORY_CAP)
+    }
+}
+
+impl<Target: 'static> History<Target> {
+    pub fn new(memory_cap: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            open: None,
+            depth: 0,
+            memory_cap,
+            sealed: false,
+        }
+    }
+
+    // Applies the command and records it.
+    pub fn execute(
+        &mut self,
+        mut command: impl Command<Target>,
+        target: &mut Target,
+        cx: &Context,
+    ) {
+        command.apply(target, cx);
+        self.record(command);
+    }
+
+    // Records a command whose effect has already been applied to the target.
+    pub fn record(&mut self, command: impl Command<Target>) {
+        self.redo.clear();
+        let command = Box::new(command);
+        if let Some(open) = self.open.as_mut() {
+            open.push(command);
+            return;
+        }
+
+        let merged = !self.sealed
+            && self.undo.back_mut().is_some_and(|last| {
+                last.commands.len() == 1 && last.commands[0].merge(&*command as &dyn Any)
+            });
+        if !merged {
+            self.undo.push_back(Transaction {
+                commands: vec![command],
+            });
+        }
+        self.sealed = false;
+        self.enforce_memory_cap();
+    }
+
+    // Groups every command recorded until the matching end_transaction into one undo step.
+    // Transactions may be nested, in which case the outermost one determines the step.
+    pub fn begin_transaction(&mut self) {
+        if self.depth == 0 {
+            self.open = Some(Transaction::default());
+        }
+        self.depth += 1;
+    }
+
+    pub fn end_transaction(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth > 0 {
+            return;
+        }
+
+        if let Some(transaction) = self.open.take() {
+            if !transaction.commands.is_empty() {
+                self.undo.push_back(transaction);
+                self.sealed = true;
+                self.enforce_memory_cap();
+            }
+        }
+    }
+
+    // Stops the next recorded command from merging into the previous one.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(&mut self, target: &mut Target, cx: &Context) -> bool {
+        let Some(mut transaction) = self.undo.pop_back() else {
+            return false;
+        };
+
+        transaction.revert(target, cx);
+        self.redo.push(transaction);
+        self.sealed = true;
+        true
+    }
+
+    pub fn redo(&mut self, target: &mut Target, cx: &Context) -> bool {
+        let Some(mut transaction) = self.redo.pop() else {
+            return false;
+        };
+
+        transaction.apply(target, cx);
+        self.undo.push_back(transaction);
+        self.sealed = true;
+        true
+    }
+
+    fn enforce_memory_cap(&mut self) {
+        let mut size: usize = self.undo.iter().map(|transaction| transaction.size()).sum();
+        // Always keep the most recent step, even if it alone is over the cap
+        while size > self.memory_cap && self.undo.len() > 1 {
+            if let Some(oldest) = self.undo.pop_front() {
+                size -= oldest.size();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::Size;
+
+    use super::*;
+    use crate::{
+        context_stack::LayoutContext, element::Element, element::ElementPointer,
+        test_runner::TestRunner,
+    };
+
+    struct Empty;
+
+    impl Element for Empty {
+        fn layout(&mut self, min: Size, _max: Size, _cx: &mut LayoutContext) -> Size {
+            min
+        }
+    }
+
+    struct Add {
+        amount: i32,
+        merging: bool,
+    }
+
+    impl Command<i32> for Add {
+        fn apply(&mut self, target: &mut i32, _cx: &Context) {
+            *target += self.amount;
+        }
+
+        fn revert(&mut self, target: &mut i32, _cx: &Context) {
+            *target -= self.amount;
+        }
+
+        fn merge(&mut self, next: &dyn Any) -> bool {
+            match next.downcast_ref::<Add>() {
+                Some(next) if self.merging && next.merging => {
+                    self.amount += next.amount;
+                    true
+                }
+                _ => false,
+            }
+        }
+    }
+
+    #[test]
+    fn history_merges_and_groups_commands() {
+        let test_runner = TestRunner::new(Size::new(100., 100.), |_| ElementPointer::new(Empty));
+        test_runner.with_root(|_, cx| {
+            let mut value = 0;
+            let mut history = History::default();
+            history.execute(
+                Add {
+                    amount: 1,
+                    merging: true,
+                },
+                &mut value,
+                cx,
+            );
+            history.execute(
+                Add {
+                    amount: 2,
+                    merging: true,
+                },
+                &mut value,
+                cx,
+            );
+            history.begin_transaction();
+            history.execute(
+                Add {
+                    amount: 10,
+                    merging: false,
+                },
+                &mut value,
+                cx,
+            );
+            history.execute(
+                Add {
+                    amount: 20,
+                    merging: false,
+                },
+                &mut value,
+                cx,
+            );
+            history.end_transaction();
+            assert_eq!(value, 33);
+
+            assert!(history.undo(&mut value, cx));
+            assert_eq!(value, 3);
+            assert!(history.undo(&mut value, cx));
+            assert_eq!(value, 0);
+            assert!(!history.undo(&mut value, cx));
+
+            assert!(history.redo(&mut value, cx));
+            assert_eq!(value, 3);
+
+            // Recording after an undo drops the redo stack
+            history.execute(
+                Add {
+                    amount: 5,
+                    merging: false,
+                },
+                &mut value,
+                cx,
+            );
+            assert!(!history.can_redo());
+            assert_eq!(value, 8);
+        });
+    }
+
+    #[test]
+    fn history_drops_oldest_steps_over_memory_cap() {
+        let test_runner = TestRunner::new(Size::new(100., 100.), |_| ElementPointer::new(Empty));
+        test_runner.with_root(|_, cx| {
+            let mut value = 0;
+            let mut history = History::new(mem::size_of::<Add>() * 2);
+            for _ in 0..4 {
+                history.execute(
+                    Add {
+                        amount: 1,
+                        merging: false,
+                    },
+                    &mut value,
+                    cx,
+                );
+            }
+
+            while history.undo(&mut value, cx) {}
+            assert_eq!(value, 2);
+        });
+    }
+}
@@ -1,10 +1,14 @@
+pub mod animation;
 pub mod components;
 pub mod context_stack;
 pub mod element;
 pub mod geometry;
+pub mod history;
 pub mod mouse_region;
 pub mod winit_runner;
 pub mod shaper;
+pub mod theme;
+pub mod spatial;
 pub mod token;
 pub mod util;
 pub mod winit_renderer;
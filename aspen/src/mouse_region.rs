@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use vello::{
     kurbo::{Affine, BezPath, Point, Shape, Size, Vec2},
@@ -9,10 +12,13 @@ use winit::window::CursorIcon;
 
 use crate::{
     context_stack::{AttachedContext, EventContext},
+    spatial::SpatialGrid,
     token::Token,
 };
 
 const MIN_DRAG: f64 = 3.;
+// Window space size of the cells mouse regions are bucketed into for hit testing
+const REGION_INDEX_CELL_SIZE: f64 = 64.;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct RegionToken {
@@ -20,24 +26,29 @@ pub struct RegionToken {
     pub(crate) index: usize,
 }
 
+// Handlers are shared so that regions can be replayed from retained drawings
+#[allow(clippy::type_complexity)]
+#[derive(Clone)]
 pub struct MouseRegion {
     pub(crate) token: RegionToken,
     pub(crate) element_children: Vec<Token>,
-    region: BezPath,
+    pub(crate) region: BezPath,
     pub(crate) transform: Affine,
     icon: Option<CursorIcon>,
     clip_stack: Vec<BezPath>,
-    on_drag: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_right_drag: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_hover: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_leave: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_down: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_right_down: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_up: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_right_up: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_click: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_right_click: Option<Box<dyn Fn(&mut EventContext)>>,
-    on_scroll: Option<Box<dyn Fn(&mut EventContext)>>,
+    on_drag: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_right_drag: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_middle_drag: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_hover: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_leave: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_down: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_right_down: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_up: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_right_up: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_click: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_right_click: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_scroll: Option<Rc<dyn Fn(&mut EventContext)>>,
+    on_pinch: Option<Rc<dyn Fn(&mut EventContext)>>,
 }
 
 impl MouseRegion {
@@ -57,6 +68,7 @@ impl MouseRegion {
             icon: None,
             on_drag: None,
             on_right_drag: None,
+            on_middle_drag: None,
             on_hover: None,
             on_leave: None,
             on_down: None,
@@ -66,66 +78,100 @@ impl MouseRegion {
             on_click: None,
             on_right_click: None,
             on_scroll: None,
+            on_pinch: None,
         }
     }
 
+    // Copy of the region moved by a window space translation. Clips inherited from ancestors when
+    // the region was registered are replaced by the given ones.
+    pub(crate) fn replayed(
+        &self,
+        offset: Affine,
+        inherited_clips: usize,
+        clip_stack: &[BezPath],
+    ) -> Self {
+        let mut region = self.clone();
+        region.region = offset * self.region.clone();
+        region.transform = offset * self.transform;
+        region.clip_stack = clip_stack
+            .iter()
+            .cloned()
+            .chain(
+                self.clip_stack[inherited_clips..]
+                    .iter()
+                    .map(|clip| offset * clip.clone()),
+            )
+            .collect();
+        region
+    }
+
     pub fn with_icon(&mut self, icon: CursorIcon) -> &mut Self {
         self.icon = Some(icon);
         self
     }
 
     pub fn on_drag<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_drag = Some(Box::new(f));
+        self.on_drag = Some(Rc::new(f));
         self
     }
 
     pub fn on_right_drag<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_right_drag = Some(Box::new(f));
+        self.on_right_drag = Some(Rc::new(f));
+        self
+    }
+
+    pub fn on_middle_drag<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
+        self.on_middle_drag = Some(Rc::new(f));
         self
     }
 
     pub fn on_hover<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_hover = Some(Box::new(f));
+        self.on_hover = Some(Rc::new(f));
         self
     }
 
     pub fn on_leave<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_leave = Some(Box::new(f));
+        self.on_leave = Some(Rc::new(f));
         self
     }
 
     pub fn on_down<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_down = Some(Box::new(f));
+        self.on_down = Some(Rc::new(f));
         self
     }
 
     pub fn on_right_down<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_right_down = Some(Box::new(f));
+        self.on_right_down = Some(Rc::new(f));
         self
     }
 
     pub fn on_up<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_up = Some(Box::new(f));
+        self.on_up = Some(Rc::new(f));
         self
     }
 
     pub fn on_right_up<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_right_up = Some(Box::new(f));
+        self.on_right_up = Some(Rc::new(f));
         self
     }
 
     pub fn on_click<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_click = Some(Box::new(f));
+        self.on_click = Some(Rc::new(f));
         self
     }
 
     pub fn on_right_click<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_right_click = Some(Box::new(f));
+        self.on_right_click = Some(Rc::new(f));
         self
     }
 
     pub fn on_scroll<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
-        self.on_scroll = Some(Box::new(f));
+        self.on_scroll = Some(Rc::new(f));
+        self
+    }
+
+    pub fn on_pinch<F: Fn(&mut EventContext) + 'static>(&mut self, f: F) -> &mut Self {
+        self.on_pinch = Some(Rc::new(f));
         self
     }
 }
@@ -134,13 +180,19 @@ pub struct MouseRegionManager {
     pub(crate) mouse_regions: Vec<MouseRegion>,
     down: Option<Point>,
     right_down: Option<Point>,
+    middle_down: Option<Point>,
     drag_min_reached: bool,
     right_drag_min_reached: bool,
+    middle_drag_min_reached: bool,
     current_dragger: Option<RegionToken>,
     current_right_dragger: Option<RegionToken>,
+    current_middle_dragger: Option<RegionToken>,
     current_clicker: Option<RegionToken>,
     current_right_clicker: Option<RegionToken>,
     hovered_regions: HashSet<RegionToken>,
+    // Region indices bucketed by window space bounding box
+    region_index: SpatialGrid<usize>,
+    region_lookup: HashMap<RegionToken, usize>,
 }
 
 impl MouseRegionManager {
@@ -149,25 +201,62 @@ impl MouseRegionManager {
             mouse_regions: Vec::new(),
             down: None,
             right_down: None,
+            middle_down: None,
             drag_min_reached: false,
             right_drag_min_reached: false,
+            middle_drag_min_reached: false,
             current_dragger: None,
             current_right_dragger: None,
+            current_middle_dragger: None,
             current_clicker: None,
             current_right_clicker: None,
             hovered_regions: HashSet::new(),
+            region_index: SpatialGrid::new(REGION_INDEX_CELL_SIZE),
+            region_lookup: HashMap::new(),
         }
     }
 
     pub fn clear_regions(&mut self) {
         self.mouse_regions.clear();
+        self.region_index.clear();
+        self.region_lookup.clear();
     }
 
     pub fn add_region(&mut self, region: MouseRegion) -> &mut MouseRegion {
+        let index = self.mouse_regions.len();
+        self.region_index
+            .insert(region.region.bounding_box(), index);
+        self.region_lookup.insert(region.token, index);
         self.mouse_regions.push(region);
         self.mouse_regions.last_mut().unwrap()
     }
 
+    // Indices of the regions which could react to the current mouse state, topmost first. Only
+    // regions under the mouse, tracked by an in progress drag or previously hovered can.
+    fn candidate_regions(
+        &self,
+        mouse_position: Option<Point>,
+        tracked: impl IntoIterator<Item = RegionToken>,
+    ) -> Vec<usize> {
+        let mut candidates: Vec<usize> = mouse_position
+            .map(|position| {
+                self.region_index
+                    .query_point(position)
+                    .map(|(_, index)| *index)
+                    .collect()
+            })
+            .unwrap_or_default();
+        candidates.extend(
+            tracked
+                .into_iter()
+                .chain(self.hovered_regions.iter().copied())
+                .filter_map(|token| self.region_lookup.get(&token).copied()),
+        );
+        candidates.sort_unstable_by(|a, b| b.cmp(a));
+        candidates.dedup();
+        candidates
+    }
+
     /// Indicates if any mouse regions associated with this Token are currently tracked. Useful to
     /// continue rendering even if a region would normally be culled.
     pub fn token_currently_tracked(&self, token: Token) -> bool {
@@ -176,6 +265,9 @@ impl MouseRegionManager {
             || self
                 .current_right_dragger
                 .map_or(false, |region_token| region_token.token == token)
+            || self
+                .current_middle_dragger
+                .map_or(false, |region_token| region_token.token == token)
             || self
                 .current_clicker
                 .map_or(false, |region_token| region_token.token == token)
@@ -203,6 +295,11 @@ impl MouseRegionManager {
             self.right_drag_min_reached = false;
         }
 
+        if cx.middle_mouse_just_down() {
+            self.middle_down = cx.actual_mouse_position();
+            self.middle_drag_min_reached = false;
+        }
+
         let down = self.down;
         let mut drag_min_just_reached = false;
         if let Some((pos, down)) = cx.actual_mouse_position().zip(down) {
@@ -227,6 +324,17 @@ impl MouseRegionManager {
             }
         }
 
+        let middle_down = self.middle_down;
+        let mut middle_drag_min_just_reached = false;
+        if let Some((pos, middle_down)) = cx.actual_mouse_position().zip(middle_down) {
+            if (pos - middle_down).length() > MIN_DRAG {
+                if !self.middle_drag_min_reached {
+                    middle_drag_min_just_reached = true;
+                }
+                self.middle_drag_min_reached = true;
+            }
+        }
+
         let current_dragger = self.current_dragger;
         let current_clicker = self.current_clicker;
         if !cx.mouse_down() {
@@ -243,15 +351,30 @@ impl MouseRegionManager {
             self.current_right_clicker = None;
         }
 
+        let current_middle_dragger = self.current_middle_dragger;
+        if !cx.middle_mouse_down() {
+            self.middle_down = None;
+            self.current_middle_dragger = None;
+        }
+
         // Then for each kind of event, process regions in reverse order (topmost first).
         // And call their callbacks if they have any registered.
 
+        let candidates = self.candidate_regions(
+            cx.actual_mouse_position(),
+            current_dragger
+                .into_iter()
+                .chain(current_right_dragger)
+                .chain(current_middle_dragger),
+        );
+
         let mut redraw_requested = false;
         let mut cx = EventContext::new(cx, &mut redraw_requested, regions);
         let mut left_consumed = false;
         let mut right_consumed = false;
+        let mut middle_consumed = false;
         let mut scroll_consumed = false;
-        for region in self.mouse_regions.iter().rev() {
+        for region in candidates.iter().map(|index| &self.mouse_regions[*index]) {
             let mut clipped = false;
             for clip in region.clip_stack.iter() {
                 if !cx
@@ -343,6 +466,26 @@ impl MouseRegionManager {
                 }
             }
 
+            // On Middle Drag
+            if current_middle_dragger == Some(region.token)
+                && cx.middle_mouse_down()
+                && self.middle_drag_min_reached
+                && !middle_consumed
+                && cx.mouse_position() != cx.previous_mouse_position()
+            {
+                if let Some((on_middle_drag, middle_down)) =
+                    region.on_middle_drag.as_ref().zip(middle_down)
+                {
+                    let mut cx = cx.for_region(region);
+                    let middle_down = cx.transform.inverse() * middle_down;
+                    if middle_drag_min_just_reached {
+                        cx.delta_correction = cx.mouse_position().map(|pos| pos - middle_down);
+                    }
+                    on_middle_drag(&mut cx);
+                    middle_consumed = true;
+                }
+            }
+
             if ({
                 let this = &region;
                 cx.actual_mouse_position()
@@ -402,6 +545,14 @@ impl MouseRegionManager {
                     }
                 }
 
+                // On Middle Down
+                if cx.middle_mouse_just_down()
+                    && self.current_middle_dragger.is_none()
+                    && region.on_middle_drag.is_some()
+                {
+                    self.current_middle_dragger = Some(region.token);
+                }
+
                 // On Released
                 if cx.mouse_released() && !left_consumed {
                     if let Some(on_up) = &region.on_up {
@@ -441,7 +592,7 @@ impl MouseRegionManager {
         }
 
         // Scrolling also gets its own loop.
-        for region in self.mouse_regions.iter().rev() {
+        for region in candidates.iter().map(|index| &self.mouse_regions[*index]) {
             if {
                 let this = &region;
                 cx.actual_mouse_position()
@@ -457,6 +608,22 @@ impl MouseRegionManager {
             }
         }
 
+        // As do pinch gestures
+        if cx.pinch_delta() != 0. {
+            for region in candidates.iter().map(|index| &self.mouse_regions[*index]) {
+                if cx
+                    .actual_mouse_position()
+                    .map_or(false, |pos| region.region.contains(pos))
+                {
+                    if let Some(on_pinch) = &region.on_pinch {
+                        let mut cx = cx.for_region(region);
+                        on_pinch(&mut cx);
+                        break;
+                    }
+                }
+            }
+        }
+
         if !icon_set {
             cx.set_cursor(CursorIcon::Default);
         }
@@ -480,3 +647,106 @@ impl MouseRegionManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use vello::kurbo::Rect;
+    use winit::window::Cursor;
+
+    use super::*;
+    use crate::{
+        context_stack::{DrawContext, LayoutContext},
+        element::{Element, ElementPointer},
+        test_runner::TestRunner,
+    };
+
+    #[derive(Default)]
+    struct Recorded {
+        drags: RefCell<Vec<Vec2>>,
+        pinches: RefCell<Vec<f64>>,
+    }
+
+    // Covers part of the window with a region recording the gestures it receives.
+    struct Gestures {
+        recorded: Rc<Recorded>,
+    }
+
+    impl Element for Gestures {
+        fn layout(&mut self, _min: Size, max: Size, _cx: &mut LayoutContext) -> Size {
+            max
+        }
+
+        fn draw(&self, cx: &mut DrawContext) {
+            let (drags, pinches) = (self.recorded.clone(), self.recorded.clone());
+            cx.mouse_region(Rect::new(0., 0., 100., 100.))
+                .on_middle_drag(move |cx| drags.drags.borrow_mut().extend(cx.mouse_delta()))
+                .on_pinch(move |cx| pinches.pinches.borrow_mut().push(cx.pinch_delta()));
+        }
+    }
+
+    fn gestures() -> (TestRunner<Gestures>, Rc<Recorded>) {
+        let recorded = Rc::new(Recorded::default());
+        let mut test_runner = TestRunner::new(Size::new(200., 200.), |_| {
+            ElementPointer::new(Gestures {
+                recorded: recorded.clone(),
+            })
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        let child_lookup = test_runner.layout();
+        test_runner.draw(child_lookup);
+        (test_runner, recorded)
+    }
+
+    #[test]
+    fn middle_drags_start_in_regions_and_follow_the_mouse() {
+        let (mut test_runner, recorded) = gestures();
+        let drags = &recorded.drags;
+        test_runner.move_mouse(Point::new(10., 10.));
+        test_runner.application.event_state.middle_mouse_down = true;
+        test_runner.tick();
+
+        // Small movements aren't drags yet, and the first drag makes up for them
+        test_runner.move_mouse(Point::new(12., 10.));
+        assert!(drags.borrow().is_empty());
+        test_runner.move_mouse(Point::new(30., 10.));
+        // Drags carry on outside of the region they started in
+        test_runner.move_mouse(Point::new(150., 20.));
+        assert_eq!(
+            *drags.borrow(),
+            vec![Vec2::new(20., 0.), Vec2::new(120., 10.)]
+        );
+
+        test_runner.application.event_state.middle_mouse_down = false;
+        test_runner.tick();
+        test_runner.move_mouse(Point::new(40., 40.));
+        assert_eq!(drags.borrow().len(), 2);
+
+        // Middle presses outside of the region don't drag it
+        test_runner.move_mouse(Point::new(150., 150.));
+        test_runner.application.event_state.middle_mouse_down = true;
+        test_runner.tick();
+        test_runner.move_mouse(Point::new(50., 50.));
+        assert_eq!(drags.borrow().len(), 2);
+    }
+
+    #[test]
+    fn pinches_reach_the_region_under_the_mouse() {
+        let (mut test_runner, recorded) = gestures();
+        let pinches = &recorded.pinches;
+        test_runner.move_mouse(Point::new(50., 50.));
+        test_runner.application.event_state.pinch_delta = 0.25;
+        test_runner.tick();
+        assert_eq!(*pinches.borrow(), vec![0.25]);
+
+        // Frames without a pinch don't report one
+        test_runner.tick();
+        assert_eq!(pinches.borrow().len(), 1);
+
+        test_runner.move_mouse(Point::new(150., 150.));
+        test_runner.application.event_state.pinch_delta = -0.5;
+        test_runner.tick();
+        assert_eq!(pinches.borrow().len(), 1);
+    }
+}
@@ -1,10 +1,13 @@
 pub use crate::{
+    animation::{Animated, Easing, SpringConfig},
     components::*,
     context_stack::{
         AttachedContext, Context, DrawContext, EventContext, LayoutContext, UpdateContext,
     },
     element::{Element, ElementPointer},
+    history::{Command, History},
     mouse_region::MouseRegion,
+    theme::{Theme, ThemeColor, ThemeError},
     token::Token,
     util::*,
     winit_runner::run,
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use vello::kurbo::{Point, Rect};
+
+// Items covering more cells than this are kept in a list which every query checks instead
+const MAX_CELLS_PER_ITEM: i64 = 64;
+
+/// Uniform grid bucketing items by their bounding boxes so that queries only look at items
+/// near the queried area. Queries return items in the order they were inserted.
+pub struct SpatialGrid<T> {
+    cell_size: f64,
+    items: Vec<(Rect, T)>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    large: Vec<usize>,
+}
+
+impl<T> SpatialGrid<T> {
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size,
+            items: Vec::new(),
+            cells: HashMap::new(),
+            large: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.cells.clear();
+        self.large.clear();
+    }
+
+    // Range of cells covered by the rect, or None if it covers more than max_cells or isn't
+    // finite.
+    fn cell_range(&self, rect: Rect, max_cells: i64) -> Option<((i64, i64), (i64, i64))> {
+        if !rect.is_finite() {
+            return None;
+        }
+
+        let min = (
+            (rect.x0 / self.cell_size).floor() as i64,
+            (rect.y0 / self.cell_size).floor() as i64,
+        );
+        let max = (
+            (rect.x1 / self.cell_size).floor() as i64,
+            (rect.y1 / self.cell_size).floor() as i64,
+        );
+        let count = (max.0.saturating_sub(min.0).saturating_add(1))
+            .saturating_mul(max.1.saturating_sub(min.1).saturating_add(1));
+        (count <= max_cells).then_some((min, max))
+    }
+
+    pub fn insert(&mut self, rect: Rect, item: T) {
+        let index = self.items.len();
+        let rect = rect.abs();
+        match self.cell_range(rect, MAX_CELLS_PER_ITEM) {
+            Some((min, max)) => {
+                for x in min.0..=max.0 {
+                    for y in min.1..=max.1 {
+                        self.cells.entry((x, y)).or_default().push(index);
+                    }
+                }
+            }
+            None => self.large.push(index),
+        }
+        self.items.push((rect, item));
+    }
+
+    // Indices of items whose bounding boxes overlap the rect, in insertion order.
+    fn query_indices(&self, rect: Rect) -> Vec<usize> {
+        let mut indices = self.large.clone();
+        // Queries covering more cells than there are items are cheaper to answer by checking
+        // every item
+        match self.cell_range(rect, self.items.len() as i64) {
+            Some((min, max)) => {
+                for x in min.0..=max.0 {
+                    for y in min.1..=max.1 {
+                        if let Some(cell) = self.cells.get(&(x, y)) {
+                            indices.extend_from_slice(cell);
+                        }
+                    }
+                }
+                indices.sort_unstable();
+                indices.dedup();
+            }
+            None => indices = (0..self.items.len()).collect(),
+        }
+
+        indices.retain(|index| {
+            let item_rect = self.items[*index].0;
+            item_rect.x0 <= rect.x1
+                && rect.x0 <= item_rect.x1
+                && item_rect.y0 <= rect.y1
+                && rect.y0 <= item_rect.y1
+        });
+        indices
+    }
+
+    pub fn query(&self, rect: Rect) -> impl Iterator<Item = (Rect, &T)> + '_ {
+        self.query_indices(rect.abs())
+            .into_iter()
+            .map(|index| (self.items[index].0, &self.items[index].1))
+    }
+
+    pub fn query_point(&self, point: Point) -> impl Iterator<Item = (Rect, &T)> + '_ {
+        self.query(Rect::from_points(point, point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_finds_overlapping_items_in_insertion_order() {
+        let mut grid = SpatialGrid::new(10.);
+        grid.insert(Rect::new(0., 0., 5., 5.), "small");
+        grid.insert(Rect::new(-1e6, -1e6, 1e6, 1e6), "large");
+        grid.insert(Rect::new(4., 4., 25., 25.), "spanning");
+        grid.insert(Rect::new(100., 100., 110., 110.), "far");
+
+        let found: Vec<_> = grid
+            .query_point(Point::new(4.5, 4.5))
+            .map(|(_, item)| *item)
+            .collect();
+        assert_eq!(found, vec!["small", "large", "spanning"]);
+
+        let found: Vec<_> = grid
+            .query(Rect::new(20., 20., 105., 105.))
+            .map(|(_, item)| *item)
+            .collect();
+        assert_eq!(found, vec!["large", "spanning", "far"]);
+
+        let found: Vec<_> = grid
+            .query(Rect::new(-1e9, -1e9, 1e9, 1e9))
+            .map(|(_, item)| *item)
+            .collect();
+        assert_eq!(found.len(), 4);
+    }
+}
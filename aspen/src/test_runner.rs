@@ -1,12 +1,12 @@
-use std::{collections::{HashMap, HashSet}, ops::Deref};
+use std::{collections::{HashMap, HashSet}, ops::Deref, time::{Duration, Instant}};
 
 use mockall::predicate::eq;
-use vello::{kurbo::Size, Scene};
+use vello::{kurbo::{Point, Size}, Scene};
 use winit::{event::ElementState, keyboard::{Key, SmolStr}, window::Cursor};
 
 use crate::{
     application::Application, context_stack::{
-        KeyEvent, attached_context::{MockContextEventLoop, MockContextWindow}, context::Context
+        Clock, EventState, KeyEvent, attached_context::{MockContextEventLoop, MockContextWindow}, context::Context
     }, element::{Element, ElementPointer}, token::Token
 };
 
@@ -21,8 +21,14 @@ impl<Root: Element> TestRunner<Root> {
     pub fn new<F>(window_size: Size, root_constructor: F) -> Self 
         where F: for<'a> FnOnce(&mut Context<'a>) -> ElementPointer<Root>,
     {
-        let mut application = Application::<Root>::new(root_constructor);
-        application.event_state.window_size = window_size;
+        let mut event_state = EventState::new();
+        event_state.window_size = window_size;
+        // Time only passes when the test advances it, including for timers set while the root
+        // is constructed
+        let start = Instant::now();
+        event_state.clock = Clock::Fake(start);
+        event_state.frame_time = start;
+        let application = Application::<Root>::with_event_state(event_state, root_constructor);
 
         let mut window = MockContextWindow::new();
         window.expect_request_redraw().return_const(());
@@ -40,10 +46,25 @@ impl<Root: Element> TestRunner<Root> {
         while let Some(newly_drawn_scene) = self.application.tick(&self.window, &self.event_loop) {
             // Continue to tick until draw not requested
             drawn_scene = Some(newly_drawn_scene);
+            // Animations only move on once the clock is advanced
+            if self.application.event_state.animation_frame_requested() {
+                break;
+            }
         }
         drawn_scene
     }
 
+    pub fn now(&self) -> Instant {
+        self.application.event_state.clock.now()
+    }
+
+    /** Moves the fake clock forward and ticks, firing any timers which came due */
+    pub fn advance_time(&mut self, duration: Duration) -> Option<Scene> {
+        let now = self.now() + duration;
+        self.application.event_state.clock = Clock::Fake(now);
+        self.tick()
+    }
+
     pub fn refresh_tokens(&mut self) {
         self.application.refresh_tokens();
     }
@@ -93,6 +114,21 @@ impl<Root: Element> TestRunner<Root> {
         self.tick();
     }
 
+    pub fn move_mouse(&mut self, position: Point) {
+        self.application.event_state.mouse_position = Some(position);
+        self.tick();
+    }
+
+    pub fn press_mouse(&mut self) {
+        self.application.event_state.mouse_down = true;
+        self.tick();
+    }
+
+    pub fn release_mouse(&mut self) {
+        self.application.event_state.mouse_down = false;
+        self.tick();
+    }
+
     pub fn with_root<Result>(&self, callback: impl FnOnce(&Root, &Context) -> Result) -> Result {
         self.application.with_root(callback)
     }
@@ -0,0 +1,396 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use rust_embed::RustEmbed;
+use serde_json::{json, Map, Value};
+use vello::peniko::Color;
+
+use super::Theme;
+use crate::{
+    context_stack::{Context, TimerHandle},
+    util::parse_hex_color,
+};
+
+// How often watched theme files are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(RustEmbed)]
+#[folder = "themes/"]
+struct BuiltinThemes;
+
+/// Reason a theme file could not be loaded. Validation errors carry the path of the offending
+/// key, such as `colors.background[2]`.
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(io::Error),
+    Syntax(serde_json::Error),
+    Missing { key: String },
+    Unknown { key: String },
+    Invalid { key: String, expected: &'static str },
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Io(error) => write!(f, "could not read theme: {error}"),
+            ThemeError::Syntax(error) => write!(f, "invalid theme json: {error}"),
+            ThemeError::Missing { key } => write!(f, "theme is missing `{key}`"),
+            ThemeError::Unknown { key } => write!(f, "theme has unknown key `{key}`"),
+            ThemeError::Invalid { key, expected } => {
+                write!(f, "theme key `{key}` should be {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+// Object in a theme file being read, remembering the keys taken from it so that anything left
+// over can be reported as unknown
+struct Fields<'a> {
+    path: String,
+    map: &'a Map<String, Value>,
+    read: Vec<&'static str>,
+}
+
+impl<'a> Fields<'a> {
+    fn new(value: &'a Value, path: String) -> Result<Self, ThemeError> {
+        let Some(map) = value.as_object() else {
+            return Err(ThemeError::Invalid {
+                key: if path.is_empty() {
+                    "<root>".to_string()
+                } else {
+                    path
+                },
+                expected: "an object",
+            });
+        };
+        Ok(Self {
+            path,
+            map,
+            read: Vec::new(),
+        })
+    }
+
+    fn key(&self, name: &str) -> String {
+        if self.path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{name}", self.path)
+        }
+    }
+
+    fn optional(&mut self, name: &'static str) -> Option<&'a Value> {
+        self.read.push(name);
+        self.map.get(name)
+    }
+
+    fn get(&mut self, name: &'static str) -> Result<&'a Value, ThemeError> {
+        self.optional(name).ok_or_else(|| ThemeError::Missing {
+            key: self.key(name),
+        })
+    }
+
+    fn object(&mut self, name: &'static str) -> Result<Fields<'a>, ThemeError> {
+        Fields::new(self.get(name)?, self.key(name))
+    }
+
+    fn string(&mut self, name: &'static str) -> Result<String, ThemeError> {
+        let value = self.get(name)?;
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or(ThemeError::Invalid {
+                key: self.key(name),
+                expected: "a string",
+            })
+    }
+
+    fn bool(&mut self, name: &'static str) -> Result<bool, ThemeError> {
+        let value = self.get(name)?;
+        value.as_bool().ok_or(ThemeError::Invalid {
+            key: self.key(name),
+            expected: "true or false",
+        })
+    }
+
+    fn color(&mut self, name: &'static str) -> Result<Color, ThemeError> {
+        let value = self.get(name)?;
+        color(value, self.key(name))
+    }
+
+    fn text_size(&mut self, name: &'static str, default: f32) -> Result<f32, ThemeError> {
+        let Some(value) = self.optional(name) else {
+            return Ok(default);
+        };
+        match value.as_f64() {
+            Some(size) if size > 0. => Ok(size as f32),
+            _ => Err(ThemeError::Invalid {
+                key: self.key(name),
+                expected: "a positive number",
+            }),
+        }
+    }
+
+    fn finish(self) -> Result<(), ThemeError> {
+        match self
+            .map
+            .keys()
+            .find(|key| !self.read.contains(&key.as_str()))
+        {
+            Some(key) => Err(ThemeError::Unknown { key: self.key(key) }),
+            None => Ok(()),
+        }
+    }
+}
+
+fn color(value: &Value, key: String) -> Result<Color, ThemeError> {
+    value
+        .as_str()
+        .and_then(parse_hex_color)
+        .ok_or(ThemeError::Invalid {
+            key,
+            expected: "a hex color like \"#rrggbb\" or \"#rrggbbaa\"",
+        })
+}
+
+fn to_hex(color: Color) -> String {
+    let rgba = color.to_rgba8();
+    if rgba.a == 255 {
+        format!("#{:02x}{:02x}{:02x}", rgba.r, rgba.g, rgba.b)
+    } else {
+        format!("#{:02x}{:02x}{:02x}{:02x}", rgba.r, rgba.g, rgba.b, rgba.a)
+    }
+}
+
+impl Theme {
+    /// Reads a theme from the json format used by theme files. Text sizes may be left out, in
+    /// which case the defaults are used, but every color has to be given.
+    pub fn from_json(json: &str) -> Result<Theme, ThemeError> {
+        let value: Value = serde_json::from_str(json).map_err(ThemeError::Syntax)?;
+        let mut root = Fields::new(&value, String::new())?;
+        let name = root.string("name")?;
+        let dark = root.bool("dark")?;
+
+        let mut colors = root.object("colors")?;
+        let background_dim = colors.color("background_dim")?;
+        let levels = colors.get("background")?;
+        let levels = match levels.as_array() {
+            Some(levels) if levels.len() == 6 => levels,
+            _ => {
+                return Err(ThemeError::Invalid {
+                    key: colors.key("background"),
+                    expected: "a list of 6 hex colors",
+                })
+            }
+        };
+        let mut background = [Color::BLACK; 6];
+        for (i, level) in levels.iter().enumerate() {
+            background[i] = color(level, format!("{}[{i}]", colors.key("background")))?;
+        }
+        let foreground = colors.color("foreground")?;
+        let muted = colors.color("muted")?;
+        let accent = colors.color("accent")?;
+        let selection = colors.color("selection")?;
+        let danger = colors.color("danger")?;
+        let highlight = colors.color("highlight")?;
+        colors.finish()?;
+
+        let (mut small_text_size, mut text_size, mut heading_text_size) = (12., 16., 22.);
+        if let Some(sizes) = root.optional("text_sizes") {
+            let mut sizes = Fields::new(sizes, "text_sizes".to_string())?;
+            small_text_size = sizes.text_size("small", small_text_size)?;
+            text_size = sizes.text_size("body", text_size)?;
+            heading_text_size = sizes.text_size("heading", heading_text_size)?;
+            sizes.finish()?;
+        }
+        root.finish()?;
+
+        Ok(Theme {
+            name,
+            dark,
+            background_dim,
+            background,
+            foreground,
+            muted,
+            accent,
+            selection,
+            danger,
+            highlight,
+            small_text_size,
+            text_size,
+            heading_text_size,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let value = json!({
+            "name": self.name,
+            "dark": self.dark,
+            "colors": {
+                "background_dim": to_hex(self.background_dim),
+                "background": self.background.map(to_hex),
+                "foreground": to_hex(self.foreground),
+                "muted": to_hex(self.muted),
+                "accent": to_hex(self.accent),
+                "selection": to_hex(self.selection),
+                "danger": to_hex(self.danger),
+                "highlight": to_hex(self.highlight),
+            },
+            "text_sizes": {
+                "small": self.small_text_size,
+                "body": self.text_size,
+                "heading": self.heading_text_size,
+            },
+        });
+        serde_json::to_string_pretty(&value).unwrap()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Theme, ThemeError> {
+        let json = fs::read_to_string(path).map_err(ThemeError::Io)?;
+        Theme::from_json(&json)
+    }
+
+    /// Theme embedded in the binary by the name of its file in `themes/`, such as
+    /// `everforest-dark`. Panics if the embedded file is invalid.
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let file = BuiltinThemes::get(&format!("{name}.json"))?;
+        let json = std::str::from_utf8(&file.data)
+            .unwrap_or_else(|error| panic!("built-in theme {name} is not utf-8: {error}"));
+        Some(
+            Theme::from_json(json)
+                .unwrap_or_else(|error| panic!("built-in theme {name} is invalid: {error}")),
+        )
+    }
+
+    pub fn builtin_names() -> Vec<String> {
+        BuiltinThemes::iter()
+            .filter_map(|file| file.strip_suffix(".json").map(str::to_string))
+            .collect()
+    }
+
+    /// Loads the theme file and switches to it, then keeps checking the file on the frame clock
+    /// and switches again whenever it is saved with a different theme. Files which fail to load
+    /// are passed to `on_error` and the current theme is kept. Cancel the returned timer to stop
+    /// watching.
+    pub fn watch(
+        path: impl Into<PathBuf>,
+        on_error: impl Fn(ThemeError) + 'static,
+        cx: &Context,
+    ) -> TimerHandle {
+        let path = path.into();
+        let last = RefCell::new(None);
+        let unreadable = Cell::new(false);
+        let reload = move |cx: &Context| {
+            let json = match fs::read_to_string(&path) {
+                Ok(json) => json,
+                Err(error) => {
+                    // Only reported once until the file can be read again
+                    if !unreadable.replace(true) {
+                        on_error(ThemeError::Io(error));
+                    }
+                    return;
+                }
+            };
+            unreadable.set(false);
+            if last.borrow().as_ref() == Some(&json) {
+                return;
+            }
+            match Theme::from_json(&json) {
+                Ok(theme) if *cx.theme() != theme => cx.set_theme(theme),
+                Ok(_) => {}
+                Err(error) => on_error(error),
+            }
+            *last.borrow_mut() = Some(json);
+        };
+        reload(cx);
+        cx.set_interval(WATCH_INTERVAL, reload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use vello::kurbo::Size;
+    use winit::window::{Cursor, CursorIcon};
+
+    use super::*;
+    use crate::{components::layout::SizedBox, test_runner::TestRunner};
+
+    fn error_key(json: &str) -> String {
+        match Theme::from_json(json) {
+            Err(ThemeError::Missing { key })
+            | Err(ThemeError::Unknown { key })
+            | Err(ThemeError::Invalid { key, .. }) => key,
+            result => panic!("expected a validation error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn theme_files_report_the_offending_key() {
+        let mut names = Theme::builtin_names();
+        names.sort();
+        assert_eq!(names, ["everforest-dark", "everforest-light"]);
+        for name in names {
+            assert!(Theme::builtin(&name).is_some(), "{name}");
+        }
+        let dark = Theme::dark();
+        assert_eq!(dark.background[0], Color::from_rgb8(0x27, 0x2e, 0x33));
+        assert_eq!(Theme::from_json(&dark.to_json()).unwrap(), dark);
+
+        let json = dark.to_json();
+        let broken = json.replace("#374145", "#37414g");
+        assert_eq!(error_key(&broken), "colors.background[2]");
+        let broken = json.replace("\"accent\"", "\"acent\"");
+        assert_eq!(error_key(&broken), "colors.accent");
+        let broken = json.replace("\"small\"", "\"tiny\"");
+        assert_eq!(error_key(&broken), "text_sizes.tiny");
+        let broken = json.replace("\"dark\": true", "\"dark\": \"yes\"");
+        assert_eq!(error_key(&broken), "dark");
+        assert!(matches!(
+            Theme::from_json("{ \"name\": "),
+            Err(ThemeError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn watched_theme_files_apply_when_saved() {
+        let path = std::env::temp_dir().join(format!("aspen-theme-{}.json", std::process::id()));
+        fs::write(&path, Theme::light().to_json()).unwrap();
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |cx| {
+            let errors = errors.clone();
+            Theme::watch(&path, move |error| errors.borrow_mut().push(error), cx);
+            SizedBox::new(Size::new(10., 10.))
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        test_runner.tick();
+        test_runner.with_root(|_, cx| assert_eq!(cx.theme().name, "Everforest Light"));
+        // Checking an unchanged file, or one saved with the same theme, doesn't redraw
+        assert!(test_runner.advance_time(WATCH_INTERVAL).is_none());
+        fs::write(&path, format!("{}\n", Theme::light().to_json())).unwrap();
+        assert!(test_runner.advance_time(WATCH_INTERVAL).is_none());
+
+        // Broken saves are reported and keep the current theme
+        fs::write(&path, "{ \"name\": ").unwrap();
+        test_runner.advance_time(WATCH_INTERVAL);
+        test_runner.with_root(|_, cx| assert_eq!(cx.theme().name, "Everforest Light"));
+        assert!(matches!(errors.borrow()[..], [ThemeError::Syntax(_)]));
+
+        let mut theme = Theme::dark();
+        theme.name = "Edited".to_string();
+        fs::write(&path, theme.to_json()).unwrap();
+        test_runner.advance_time(WATCH_INTERVAL);
+        test_runner.with_root(|_, cx| assert_eq!(*cx.theme(), theme));
+        // The switch redraws on the following frame, after which watching is idle again
+        assert!(test_runner.tick().is_some());
+        assert!(test_runner.advance_time(WATCH_INTERVAL).is_none());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(errors.borrow().len(), 1);
+    }
+}
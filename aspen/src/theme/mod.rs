@@ -0,0 +1,137 @@
+use serde_derive::{Deserialize, Serialize};
+use vello::peniko::Color;
+
+pub use file::ThemeError;
+
+mod file;
+
+/// Semantic colors and text sizes components read their defaults from. Provided to every element
+/// through its context and switched at runtime with `set_theme`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub dark: bool,
+    // Backdrop behind everything else
+    pub background_dim: Color,
+    // Background levels from the base background up to the most raised surfaces
+    pub background: [Color; 6],
+    pub foreground: Color,
+    // Secondary content such as wires and inactive icons
+    pub muted: Color,
+    pub accent: Color,
+    // Fill behind selected text and content
+    pub selection: Color,
+    pub danger: Color,
+    // Soft fill for content which should stand out, such as notes
+    pub highlight: Color,
+    pub small_text_size: f32,
+    pub text_size: f32,
+    pub heading_text_size: f32,
+}
+
+impl Theme {
+    /// Dark variant of the Everforest palette.
+    pub fn dark() -> Self {
+        Self::builtin("everforest-dark").expect("built-in themes are valid")
+    }
+
+    /// Light variant of the Everforest palette.
+    pub fn light() -> Self {
+        Self::builtin("everforest-light").expect("built-in themes are valid")
+    }
+
+    // Background at the level, clamped to the most raised one
+    pub fn background(&self, level: usize) -> Color {
+        self.background[level.min(self.background.len() - 1)]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+mod color_components {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use vello::peniko::Color;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        color.components.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        Ok(Color::new(<[f32; 4]>::deserialize(deserializer)?))
+    }
+}
+
+/// Color which is either fixed or follows a token of the current theme. Components resolve these
+/// while drawing so that they pick up theme switches.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ThemeColor {
+    Fixed(#[serde(with = "color_components")] Color),
+    BackgroundDim,
+    Background(usize),
+    Foreground,
+    Muted,
+    Accent,
+    Selection,
+    Danger,
+    Highlight,
+}
+
+impl ThemeColor {
+    pub fn resolve(&self, theme: &Theme) -> Color {
+        match *self {
+            ThemeColor::Fixed(color) => color,
+            ThemeColor::BackgroundDim => theme.background_dim,
+            ThemeColor::Background(level) => theme.background(level),
+            ThemeColor::Foreground => theme.foreground,
+            ThemeColor::Muted => theme.muted,
+            ThemeColor::Accent => theme.accent,
+            ThemeColor::Selection => theme.selection,
+            ThemeColor::Danger => theme.danger,
+            ThemeColor::Highlight => theme.highlight,
+        }
+    }
+}
+
+impl From<Color> for ThemeColor {
+    fn from(color: Color) -> Self {
+        ThemeColor::Fixed(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::Size;
+    use winit::window::{Cursor, CursorIcon};
+
+    use super::*;
+    use crate::{
+        components::{layout::SizedBox, ElementBorderExt},
+        test_runner::TestRunner,
+    };
+
+    #[test]
+    fn switching_themes_redraws_everything() {
+        let mut test_runner = TestRunner::new(Size::new(100., 100.), |_| {
+            SizedBox::new(Size::new(10., 10.)).with_border(5.)
+        });
+        test_runner.expect_cursor_icon(Cursor::Icon(CursorIcon::Default));
+        test_runner.tick();
+        assert!(test_runner.tick().is_none());
+        assert_eq!(
+            ThemeColor::Background(9).resolve(&Theme::dark()),
+            Theme::dark().background[5]
+        );
+
+        test_runner.with_root(|_, cx| cx.set_theme(Theme::light()));
+        assert!(test_runner.tick().is_some());
+        test_runner.with_root(|_, cx| {
+            assert!(!cx.theme().dark);
+            assert!(!cx.theme_changed());
+        });
+        assert!(test_runner.tick().is_none());
+    }
+}
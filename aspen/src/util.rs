@@ -7,6 +7,8 @@ pub trait Mixable: Sized {
     fn add(&self, other: &Self) -> Self;
     fn sub(&self, other: &Self) -> Self;
     fn scale(&self, factor: f64) -> Self;
+    // Size of the value as a difference between two others. Used to tell when springs settle.
+    fn magnitude(&self) -> f64;
     fn mix(&self, other: &Self, factor: f64) -> Self {
         let factor = factor.clamp(0., 1.);
         self.add(&other.sub(&self).scale(factor))
@@ -25,6 +27,10 @@ impl Mixable for f64 {
     fn scale(&self, factor: f64) -> Self {
         self * factor
     }
+
+    fn magnitude(&self) -> f64 {
+        self.abs()
+    }
 }
 
 impl Mixable for Color {
@@ -40,6 +46,11 @@ impl Mixable for Color {
         *self * factor as f32
     }
 
+    fn magnitude(&self) -> f64 {
+        let [r, g, b, a] = self.components;
+        ((r * r + g * g + b * b + a * a) as f64).sqrt()
+    }
+
     fn mix(&self, other: &Self, factor: f64) -> Self {
         self.lerp(*other, (factor as f32).clamp(0., 1.), HueDirection::Shorter)
     }
@@ -64,6 +75,73 @@ impl Mixable for Rect {
             self.size() * factor,
         )
     }
+    fn magnitude(&self) -> f64 {
+        let (origin, size) = (self.origin().to_vec2(), self.size().to_vec2());
+        origin.hypot().hypot(size.hypot())
+    }
+}
+
+impl Mixable for Point {
+    fn add(&self, other: &Self) -> Self {
+        *self + other.to_vec2()
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        (*self - *other).to_point()
+    }
+
+    fn scale(&self, factor: f64) -> Self {
+        (self.to_vec2() * factor).to_point()
+    }
+
+    fn magnitude(&self) -> f64 {
+        self.to_vec2().hypot()
+    }
+
+    fn mix(&self, other: &Self, factor: f64) -> Self {
+        self.lerp(*other, factor.clamp(0., 1.))
+    }
+}
+
+impl Mixable for Vec2 {
+    fn add(&self, other: &Self) -> Self {
+        *self + *other
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        *self - *other
+    }
+
+    fn scale(&self, factor: f64) -> Self {
+        *self * factor
+    }
+
+    fn magnitude(&self) -> f64 {
+        self.hypot()
+    }
+}
+
+// Transforms are mixed coefficient by coefficient, which suits translations and scales but not
+// rotations.
+impl Mixable for Affine {
+    fn add(&self, other: &Self) -> Self {
+        let (a, b) = (self.as_coeffs(), other.as_coeffs());
+        Affine::new(std::array::from_fn(|i| a[i] + b[i]))
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        let (a, b) = (self.as_coeffs(), other.as_coeffs());
+        Affine::new(std::array::from_fn(|i| a[i] - b[i]))
+    }
+
+    fn scale(&self, factor: f64) -> Self {
+        Affine::new(self.as_coeffs().map(|coeff| coeff * factor))
+    }
+
+    fn magnitude(&self) -> f64 {
+        let squares = self.as_coeffs().map(|coeff| coeff * coeff);
+        squares.iter().sum::<f64>().sqrt()
+    }
 }
 
 pub trait RectExt {
@@ -125,3 +203,14 @@ impl AffineExt for Affine {
         )
     }
 }
+
+// Parses colors written as `#rrggbb` or `#rrggbbaa`, with the leading `#` optional
+pub fn parse_hex_color(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !matches!(hex.len(), 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some(Color::from_rgba8(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
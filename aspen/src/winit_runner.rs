@@ -6,7 +6,7 @@ use vello::kurbo::{Point, Size, Vec2};
 use winit::{
     application::ApplicationHandler,
     event::MouseScrollDelta,
-    event::{ElementState, MouseButton, WindowEvent},
+    event::{ElementState, MouseButton, StartCause, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     platform::windows::WindowAttributesExtWindows,
     window::{Window, WindowAttributes, WindowId},
@@ -45,14 +45,29 @@ impl<Root: Element> WinitApplicationHandler<Root> {
             if !window.is_visible().unwrap_or_default() {
                 window.set_visible(true);
             }
+        }
 
-            // Request a redraw so that we can continue timers if they need to
+        // Keep drawing while animating, otherwise sleep until the next event or timer
+        if self.application.event_state.animation_frame_requested() {
             window.request_redraw();
         }
+        event_loop.set_control_flow(match self.application.next_timer_deadline() {
+            Some(deadline) => ControlFlow::WaitUntil(deadline),
+            None => ControlFlow::Wait,
+        });
     }
 }
 
 impl<A: Element> ApplicationHandler for WinitApplicationHandler<A> {
+    fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+        // Timers fire during the frame drawn once their deadline is reached
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            if let Some(renderer) = self.renderer.as_ref() {
+                renderer.window.request_redraw();
+            }
+        }
+    }
+
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -101,18 +116,31 @@ impl<A: Element> ApplicationHandler for WinitApplicationHandler<A> {
                 self.application.event_state.right_mouse_down = state == ElementState::Pressed;
                 self.renderer.as_ref().unwrap().window.request_redraw();
             }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Middle,
+                ..
+            } => {
+                self.application.event_state.middle_mouse_down = state == ElementState::Pressed;
+                self.renderer.as_ref().unwrap().window.request_redraw();
+            }
             WindowEvent::MouseWheel { delta, .. } => {
                 match delta {
                     MouseScrollDelta::LineDelta(x, y) => {
                         self.application.event_state.scroll_delta += Vec2::new(x as f64 * 10., y as f64 * 10.)
                     }
                     MouseScrollDelta::PixelDelta(delta) => {
-                        self.application.event_state.scroll_delta += Vec2::new(delta.x as f64, delta.y as f64)
+                        self.application.event_state.scroll_delta += Vec2::new(delta.x as f64, delta.y as f64);
+                        self.application.event_state.precise_scroll = true;
                     }
                 }
 
                 self.renderer.as_ref().unwrap().window.request_redraw();
             }
+            WindowEvent::PinchGesture { delta, .. } if !delta.is_nan() => {
+                self.application.event_state.pinch_delta += delta;
+                self.renderer.as_ref().unwrap().window.request_redraw();
+            }
 
             // Keyboard Specific Events
             WindowEvent::ModifiersChanged(modifiers) => {
@@ -3,7 +3,6 @@
 mod pando;
 // mod ngs;
 mod todo;
-mod util;
 
 // A NodeGraph or .ng file format and editor for node graph data.
 // Backing format is a sqlite database which contains:
@@ -60,5 +59,11 @@ use pando::Pando;
 use aspen::prelude::*;
 
 fn main() {
-    run(|cx| Pando::new(cx))
+    run(|cx| {
+        // Theme file to use instead of the built in one, reloaded whenever it is saved
+        if let Some(path) = std::env::var_os("PANDO_THEME") {
+            Theme::watch(path, |error| eprintln!("{error}"), cx);
+        }
+        Pando::new(cx)
+    })
 }
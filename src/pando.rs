@@ -1,6 +1,6 @@
 use aspen::prelude::*;
 
-use crate::{todo::Todo, util::*};
+use crate::todo::Todo;
 
 pub struct Pando {
     board: ElementPointer<Board>,
@@ -10,22 +10,64 @@ pub struct Pando {
 
 impl Pando {
     pub fn new(cx: &mut Context) -> ElementPointer<Pando> {
-        let mut board = Board::new_dotgrid(Affine::IDENTITY, *BACKGROUND0, *BACKGROUND3, cx);
-        board.add_child(Todo::new(cx).as_pinnable(Point::new(-100., -100.), cx));
+        let board = Board::new_dotgrid(Affine::IDENTITY, cx);
+        let board = Board::with_grid_snap(board, GridSnap::Edges);
+        let board = Board::with_smart_guides(board, true);
+        let mut board = Board::with_minimap(
+            board,
+            Minimap::new(
+                Size::new(200., 150.),
+                ThemeColor::BackgroundDim,
+                ThemeColor::Background(5),
+                ThemeColor::Foreground,
+            ),
+        );
+        let specification = board.add_child(Todo::pin(
+            "Finish Specification Language",
+            Point::new(-100., -100.),
+            cx,
+        ));
+        for (index, dependency) in [
+            "Write Example Todo",
+            "Write Desired Specification",
+            "Generalize Specification",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let dependency = board.add_child(Todo::pin(
+                dependency,
+                Point::new(-300. + index as f64 * 200., 100.),
+                cx,
+            ));
+            board.connect(specification, dependency, Some(Direction::Below));
+        }
+        board.apply_graph_layout(GraphLayout::Layered, cx);
+
         board.add_child(PinWrapper::new_sized(
             Point::new(000., 200.),
             Size::new(300., 300.),
             {
-                let mut board =
-                    Board::new_dotgrid(Affine::IDENTITY, *BACKGROUND1, *BACKGROUND4, cx);
-                board.add_child(Todo::new(cx).as_pinnable(Point::ZERO, cx));
+                let background = DotGrid::new(ThemeColor::Background(1), ThemeColor::Background(4));
+                let mut board = Board::new(Affine::IDENTITY, background, cx);
+                board.add_child(Todo::pin("The quick brown fox jumps", Point::ZERO, cx));
                 board
             },
             cx,
         ));
+        board.add_frame(Frame::new(
+            "Scratchpad",
+            Rect::new(-170., 10., 170., 370.),
+            ThemeColor::Accent,
+        ));
+        board.add_note(Note::new(
+            "Drag a frame by its header to move the pins inside of it",
+            Point::new(360., 200.),
+            ThemeColor::Highlight,
+        ));
 
         ElementPointer::new(Pando {
-            window_buttons: WindowButtons::new(*BACKGROUND3, *CLOSE, *BACKGROUND4, *FOREGROUND),
+            window_buttons: WindowButtons::new(),
             resize_handles: ResizeHandles::new(),
             board,
         })
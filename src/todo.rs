@@ -1,30 +1,38 @@
 use aspen::prelude::*;
 
-use crate::util::*;
-
 pub struct Todo {
     editor: ElementPointer<Border<Editor>>,
 }
 
 impl Todo {
-    pub fn new<'a>(cx: &mut Context<'a>) -> ElementPointer<Self> {
+    pub fn new<'a>(text: &str, cx: &mut Context<'a>) -> ElementPointer<Self> {
         Self {
-            editor: Editor::new("The quick brown fox jumps".to_string(), 16.0, Brush::Solid(*FOREGROUND), Brush::Solid(*BACKGROUND_BLUE), Brush::Solid(*FOREGROUND), cx).with_border(
-                10.,
-                Brush::Solid(*BACKGROUND5),
-                Brush::Solid(*BACKGROUND1),
-            ),
+            editor: Editor::new(text.to_string(), cx).with_border(10.),
         }
         .into()
     }
+
+    // Pins the todo to a board, using its current text as the title shown when zoomed out.
+    pub fn pin<'a>(
+        text: &str,
+        center: Point,
+        cx: &mut Context<'a>,
+    ) -> ElementPointer<PinWrapper<Self>> {
+        PinWrapper::with_title_from(Self::new(text, cx).as_pinnable(center, cx), |todo, cx| {
+            todo.with_context(cx, |cx| {
+                todo.editor
+                    .with_context(cx, |cx| todo.editor.child.text(cx))
+            })
+        })
+    }
 }
 
 impl Element for Todo {
     fn update(&mut self, cx: &mut UpdateContext) {
         self.editor.fill = if cx.is_focused() {
-            Brush::Solid(*BACKGROUND4)
+            ThemeColor::Background(4)
         } else {
-            Brush::Solid(*BACKGROUND5)
+            ThemeColor::Background(5)
         };
 
         self.editor.update(cx);